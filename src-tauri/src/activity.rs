@@ -0,0 +1,89 @@
+use chrono::{DateTime, Utc, serde::ts_seconds, serde::ts_seconds_option};
+use serde::{Deserialize, Serialize};
+use crate::{TagId, TaskId};
+
+pub type CommentId = i64;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A comment in the discussion of a task, in markdown.
+pub struct Comment {
+    pub id: CommentId,
+    pub task_id: TaskId,
+    pub author: String,
+    pub text: String,
+    #[serde(with = "ts_seconds")]
+    pub create_time: DateTime<Utc>,
+    /// The last time the comment was edited, or `None` if it never was.
+    #[serde(with = "ts_seconds_option")]
+    pub edit_time: Option<DateTime<Utc>>,
+}
+
+impl Comment {
+    pub fn from_parts(task_id: TaskId, editable: &EditableCommentData, generated: &GeneratedCommentData) -> Self {
+        Self {
+            id: generated.id,
+            task_id,
+            author: editable.author.clone(),
+            text: editable.text.clone(),
+            create_time: generated.create_time,
+            edit_time: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `Comment` modifiable by the client.
+pub struct EditableCommentData {
+    pub author: String,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `Comment` determined by the database when a new comment is created.
+pub struct GeneratedCommentData {
+    pub id: CommentId,
+    #[serde(with = "ts_seconds")]
+    pub create_time: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `Comment` determined by the database when an existing comment is modified.
+pub struct ModifiedCommentData {
+    #[serde(with = "ts_seconds")]
+    pub edit_time: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Something that happened to a task, recorded by the database as it happens.
+pub enum TaskEvent {
+    Created,
+    Edited,
+    Finished,
+    Unfinished,
+    TagChanged {
+        from: Option<TagId>,
+        to: Option<TagId>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// An entry of the activity feed of a task.
+pub enum ActivityKind {
+    Event { event: TaskEvent },
+    Comment { comment: Comment },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// An entry of the activity feed of a task, at the time it happened.
+pub struct Activity {
+    #[serde(with = "ts_seconds")]
+    pub time: DateTime<Utc>,
+    pub kind: ActivityKind,
+}
@@ -0,0 +1,53 @@
+use std::path::Path;
+use chrono::{DateTime, Utc, serde::ts_seconds};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::TaskId;
+
+pub type AttachmentId = i64;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A file attached to a task. Its contents are stored once per distinct `hash`,
+/// however many tasks the file is attached to.
+pub struct Attachment {
+    pub id: AttachmentId,
+    pub task_id: TaskId,
+    /// The name of the file when it was attached.
+    pub name: String,
+    /// Size of the file in bytes.
+    pub size: i64,
+    pub mime: String,
+    /// SHA-256 of the contents of the file, in lowercase hexadecimal.
+    pub hash: String,
+    #[serde(with = "ts_seconds")]
+    pub create_time: DateTime<Utc>,
+}
+
+/// SHA-256 of `contents`, in lowercase hexadecimal.
+pub fn content_hash(contents: &[u8]) -> String {
+    Sha256::digest(contents).iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// The MIME type of a file guessed from the extension of its name,
+/// or `application/octet-stream` if unknown.
+pub fn guess_mime(name: &str) -> &'static str {
+    let extension = Path::new(name).extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+    match extension.as_deref() {
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("svg") => "image/svg+xml",
+        Some("pdf") => "application/pdf",
+        Some("txt") => "text/plain",
+        Some("md") => "text/markdown",
+        Some("csv") => "text/csv",
+        Some("html") | Some("htm") => "text/html",
+        Some("json") => "application/json",
+        Some("zip") => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use crate::Task;
+
+pub type BoardId = i64;
+pub type ColumnId = i64;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A kanban board, moving tasks through its columns of workflow states.
+pub struct Board {
+    pub id: BoardId,
+    pub name: String,
+}
+
+impl Board {
+    pub fn from_parts(editable: &EditableBoardData, generated: &GeneratedBoardData) -> Self {
+        Self {
+            id: generated.id,
+            name: editable.name.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `Board` modifiable by the client.
+pub struct EditableBoardData {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `Board` determined by the database when a new board is created.
+pub struct GeneratedBoardData {
+    pub id: BoardId,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A workflow state on a board, such as "In Progress". A task is in at most one column of any board.
+pub struct BoardColumn {
+    pub id: ColumnId,
+    pub board_id: BoardId,
+    pub name: String,
+    /// The most tasks this column may hold, or `None` for no limit.
+    pub wip_limit: Option<u32>,
+    /// Tasks entering a terminal column are marked as done, and marked as not done when leaving it.
+    pub terminal: bool,
+}
+
+impl BoardColumn {
+    pub fn from_parts(board_id: BoardId, editable: &EditableColumnData, generated: &GeneratedColumnData) -> Self {
+        Self {
+            id: generated.id,
+            board_id,
+            name: editable.name.clone(),
+            wip_limit: editable.wip_limit,
+            terminal: editable.terminal,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `BoardColumn` modifiable by the client.
+pub struct EditableColumnData {
+    pub name: String,
+    pub wip_limit: Option<u32>,
+    pub terminal: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `BoardColumn` determined by the database when a new column is created.
+pub struct GeneratedColumnData {
+    pub id: ColumnId,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A column along with its tasks, in their order within it.
+pub struct ColumnCards {
+    pub column: BoardColumn,
+    pub tasks: Vec<Task>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A board with all of its columns in order.
+pub struct BoardView {
+    pub board: Board,
+    pub columns: Vec<ColumnCards>,
+}
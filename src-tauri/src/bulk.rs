@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use crate::{DbResult, TagId, TaskFilterOptions, TaskId};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// The tasks a bulk operation applies to: either the tasks with these ids, in the given order,
+/// or the tasks that pass a filter, in their manual order. Like a list of tasks, a filter leaves out
+/// deferred tasks and tasks of archived projects unless asked for; see `TaskFilterOptions`.
+pub enum BulkTarget {
+    Ids { ids: Vec<TaskId> },
+    Filter { filter: Box<TaskFilterOptions> },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A change applied to every task of a bulk operation.
+pub enum BulkAction {
+    Finish,
+    Unfinish,
+    Delete,
+    Pause,
+    Unpause,
+    /// Sets the tag of each task, or removes it if `None`.
+    Retag { tag: Option<TagId> },
+    /// Moves the due time of each task by this many seconds, which may be negative.
+    /// Tasks without a due time are left as they are, and all-day due dates only move by whole days.
+    ShiftDue { seconds: i64 },
+    SetDifficulty { difficulty: i32 },
+}
+
+#[derive(Debug, PartialEq)]
+/// The result of a bulk action on one task.
+pub struct BulkItemResult {
+    pub id: TaskId,
+    pub result: DbResult<()>,
+}
+
+#[derive(Debug, PartialEq)]
+/// The results of a bulk operation, one for each task it applied to.
+pub struct BulkOutcome {
+    pub results: Vec<BulkItemResult>,
+    /// Whether every change was undone because one of them failed and rolling back was requested.
+    pub rolled_back: bool,
+}
+
+impl BulkOutcome {
+    /// Whether the action failed for any of the tasks.
+    pub fn has_failures(&self) -> bool {
+        self.results.iter().any(|item| item.result.is_err())
+    }
+}
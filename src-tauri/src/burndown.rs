@@ -0,0 +1,49 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Difficulty of a set of tasks at the end of a day, for burndown and burnup charts.
+pub struct BurndownPoint {
+    pub date: NaiveDate,
+    /// Difficulty of the tasks created by this day.
+    pub total: i64,
+    /// Difficulty of the tasks completed by this day.
+    pub completed: i64,
+    /// `total - completed`
+    pub remaining: i64,
+    /// Estimate of the tasks created and not completed by this day; tasks without an estimate count none.
+    pub remaining_estimate_minutes: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Difficulty of a set of tasks completed during a period.
+pub struct VelocityPoint {
+    /// The first day of the period.
+    pub start: NaiveDate,
+    pub completed: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// When the remaining difficulty of a set of tasks will be completed at the average velocity.
+pub struct BurndownForecast {
+    /// Average difficulty completed per period.
+    pub velocity: f64,
+    pub remaining: i64,
+    /// The end of the period the remaining difficulty is expected to be completed in,
+    /// or `None` if nothing was completed to base it on or it is too far ahead for a date.
+    pub completion_date: Option<NaiveDate>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Chart-ready burndown, burnup and velocity series of a set of tasks over a range of days.
+pub struct BurndownReport {
+    /// One point per day.
+    pub burndown: Vec<BurndownPoint>,
+    /// One point per period.
+    pub velocity: Vec<VelocityPoint>,
+    pub forecast: BurndownForecast,
+}
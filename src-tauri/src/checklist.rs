@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use crate::TaskId;
+
+pub type ChecklistItemId = i64;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// An item of the checklist of a task.
+pub struct ChecklistItem {
+    pub id: ChecklistItemId,
+    pub task_id: TaskId,
+    pub text: String,
+    pub checked: bool,
+}
+
+impl ChecklistItem {
+    pub fn from_parts(task_id: TaskId, editable: &EditableChecklistItemData, generated: &GeneratedChecklistItemData) -> Self {
+        Self {
+            id: generated.id,
+            task_id,
+            text: editable.text.clone(),
+            checked: editable.checked,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `ChecklistItem` modifiable by the client.
+pub struct EditableChecklistItemData {
+    pub text: String,
+    pub checked: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `ChecklistItem` determined by the database when a new item is created.
+pub struct GeneratedChecklistItemData {
+    pub id: ChecklistItemId,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+/// How many of the checklist items of a task are checked.
+pub struct ChecklistProgress {
+    pub checked: i64,
+    pub total: i64,
+}
+
+impl ChecklistProgress {
+    /// Whether every item is checked, which is also the case without items.
+    pub fn is_complete(&self) -> bool {
+        self.checked == self.total
+    }
+}
+
+/// Splits markdown task list lines such as `- [ ] Buy milk` or `* [x] Call back` out of `body`.
+///
+/// Returns the rest of the body, and the items of those lines in order.
+pub fn extract_checklist(body: &str) -> (String, Vec<EditableChecklistItemData>) {
+    let mut rest = vec![];
+    let mut items = vec![];
+    for line in body.lines() {
+        match parse_checklist_line(line) {
+            Some(item) => items.push(item),
+            None => rest.push(line),
+        }
+    }
+    (rest.join("\n"), items)
+}
+
+fn parse_checklist_line(line: &str) -> Option<EditableChecklistItemData> {
+    let line = line.trim_start();
+    let line = line.strip_prefix("- ").or_else(|| line.strip_prefix("* "))?;
+    let (checked, text) = if let Some(text) = line.strip_prefix("[ ]") {
+        (false, text)
+    } else if let Some(text) = line.strip_prefix("[x]").or_else(|| line.strip_prefix("[X]")) {
+        (true, text)
+    } else {
+        return None;
+    };
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    Some(EditableChecklistItemData { text: String::from(text), checked })
+}
@@ -0,0 +1,14 @@
+use chrono::{DateTime, Utc};
+
+/// Source of the current time, so time dependent logic can be tested with a fake clock.
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The real clock of this system.
+pub struct SystemClock;
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
@@ -1,7 +1,32 @@
-use std::path::{Path};
-use chrono::Utc;
-use rusqlite::{Connection, Row};
-use crate::{EditableTaskData, Tag, EditableTagData, GeneratedTagData, TagId, Task, TaskId, GeneratedTaskData, FinishedTaskData, ModifiedTaskData};
+mod reminders;
+mod time_entries;
+mod focus;
+mod stats;
+mod burndown;
+mod habits;
+mod urgency;
+mod planner;
+mod projects;
+mod boards;
+mod ranks;
+mod checklists;
+mod activity;
+mod attachments;
+mod links;
+mod markdown;
+mod templates;
+mod task_operations;
+mod bulk;
+mod estimates;
+mod savepoint;
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use rusqlite::{Connection, OptionalExtension, Row, Transaction};
+use crate::{EditableTaskData, Tag, EditableTagData, GeneratedTagData, TagId, Task, TaskId, GeneratedTaskData, FinishedTaskData, ModifiedTaskData, QuickAddedTaskData, MyDateTime, ReminderId, TimeEntryId, TimeBlockId, ProjectId, BoardId, ColumnId, ChecklistItemId, CommentId, AttachmentId, TemplateId, TaskEvent, parse_quick_add, rank_after, rank_sequence, parse_timezone};
+use savepoint::DbSavepoint;
 
 #[derive(Debug, PartialEq)]
 /// Errors that can occur during operation of the database.
@@ -14,7 +39,76 @@ pub enum DbError {
     TaskDoesNotExistError { id: TaskId, },
     /// Error that occurred due to a command to the database that attempted to change the done
     /// status of the task with `id` to the status it is already in.
-    TaskStatusError { id: TaskId, actual_status: bool }
+    TaskStatusError { id: TaskId, actual_status: bool },
+    /// Error that occurred due to a command to the database with an unknown IANA timezone `name`.
+    InvalidTimezoneError { name: String },
+    /// Error that occurred due to a command to the database with an invalid reminder `id`.
+    ReminderDoesNotExistError { id: ReminderId },
+    /// Error that occurred due to a command to the database with a duration, or a time moved by one,
+    /// out of the supported range.
+    DurationOutOfRangeError,
+    /// Error that occurred due to a command to the database with a date at the end of the supported range.
+    DateOutOfRangeError,
+    /// Error that occurred due to a command to the database with a range of dates that ends before it starts,
+    /// or is longer than `MAX_RANGE_DAYS`.
+    InvalidDateRangeError,
+    /// Error that occurred due to a command to the database with an all-day due time that is missing,
+    /// or is not a date stored with `all_day_time`.
+    InvalidAllDayError,
+    /// Error that occurred due to a command to the database that moved the all-day due date
+    /// of the task with `id` by a time that is not a whole number of days.
+    AllDayShiftError { id: TaskId },
+    /// Error that occurred due to a command to the database with an invalid time entry `id`.
+    TimeEntryDoesNotExistError { id: TimeEntryId },
+    /// Error that occurred due to a command to the database with a time entry that ends before it starts.
+    InvalidTimeEntryError,
+    /// Error that occurred due to a command to the database that attempted to run a second timer
+    /// while the time entry with `id` is running.
+    TimerRunningError { id: TimeEntryId },
+    /// Error that occurred due to a command to the database with focus session lengths that are not positive.
+    InvalidFocusSettingsError,
+    /// Error that occurred due to a command to the database about a habit with a task `id` that is not a habit.
+    NotAHabitError { id: TaskId },
+    /// Error that occurred due to a command to the database with a habit that is never due.
+    InvalidHabitError,
+    /// Error that occurred due to a command to the database with an invalid time block `id`.
+    TimeBlockDoesNotExistError { id: TimeBlockId },
+    /// Error that occurred due to a command to the database with a time block that doesn't end after it starts.
+    InvalidTimeBlockError,
+    /// Error that occurred due to a command to the database with working hours that end before they start,
+    /// or a duration estimate that is not positive.
+    InvalidPlannerSettingsError,
+    /// Error that occurred due to a command to the database with an invalid project `id`.
+    ProjectDoesNotExistError { id: ProjectId },
+    /// Error that occurred due to a command to the database with an invalid board `id`.
+    BoardDoesNotExistError { id: BoardId },
+    /// Error that occurred due to a command to the database with an invalid board column `id`.
+    ColumnDoesNotExistError { id: ColumnId },
+    /// Error that occurred due to a command to the database that attempted to move a task into
+    /// the column with `id`, which already holds its WIP `limit` of tasks.
+    WipLimitError { id: ColumnId, limit: u32 },
+    /// Error that occurred due to a command to the database with an invalid checklist item `id`.
+    ChecklistItemDoesNotExistError { id: ChecklistItemId },
+    /// Error that occurred due to a command to the database that attempted to finish the task with `id`
+    /// while `unchecked` items of its checklist are not checked, when complete checklists are required.
+    ChecklistIncompleteError { id: TaskId, unchecked: i64 },
+    /// Error that occurred due to a command to the database with an invalid comment `id`.
+    CommentDoesNotExistError { id: CommentId },
+    /// Error that occurred due to a command to the database with an invalid attachment `id`.
+    AttachmentDoesNotExistError { id: AttachmentId },
+    /// Error that occurred due to reading or writing the files of attachments.
+    AttachmentFileError { message: String },
+    /// Error that occurred due to a command to the database to back up into the directory of
+    /// the database or of its attachments, which would overwrite the files being copied.
+    BackupDirectoryError,
+    /// Error that occurred due to a command to the database with an invalid template `id`.
+    TemplateDoesNotExistError { id: TemplateId },
+    /// Error that occurred due to a command to the database with a `line` past the end of
+    /// the body of the task with `id`.
+    LineDoesNotExistError { id: TaskId, line: usize },
+    /// Error that occurred due to a command to the database with an estimate that is not positive,
+    /// or remaining effort that is negative or without an estimate, or either over `TaskEstimate::MAX_MINUTES`.
+    InvalidEstimateError,
 }
 
 impl<'a> From<rusqlite::Error> for DbError {
@@ -23,20 +117,75 @@ impl<'a> From<rusqlite::Error> for DbError {
     }
 }
 
-type DbResult<T> = Result<T, DbError>;
+pub type DbResult<T> = Result<T, DbError>;
 
 pub struct Db {
     conn: Connection, // note connection implements Drop
+    /// Where the contents of attachments are stored, next to the database file.
+    attachment_directory: PathBuf,
 }
 
 impl Db {
     const TAG_TABLE: &'static str = "tags";
     const TASK_TABLE: &'static str = "tasks";
     const TAG_TASK_TABLE: &'static str = "tags_tasks";
+    const SETTINGS_TABLE: &'static str = "settings";
+    const REMINDER_TABLE: &'static str = "reminders";
+    const TIME_ENTRY_TABLE: &'static str = "time_entries";
+    const FOCUS_STATE_TABLE: &'static str = "focus_state";
+    const FOCUS_SESSION_TABLE: &'static str = "focus_sessions";
+    const HABIT_TABLE: &'static str = "habits";
+    const HABIT_CHECK_IN_TABLE: &'static str = "habit_check_ins";
+    const TIME_BLOCK_TABLE: &'static str = "time_blocks";
+    const PROJECT_TABLE: &'static str = "projects";
+    const PROJECT_TASK_TABLE: &'static str = "projects_tasks";
+    const BOARD_TABLE: &'static str = "boards";
+    const BOARD_COLUMN_TABLE: &'static str = "board_columns";
+    const BOARD_CARD_TABLE: &'static str = "board_cards";
+    const CHECKLIST_TABLE: &'static str = "checklist_items";
+    const COMMENT_TABLE: &'static str = "comments";
+    const TASK_EVENT_TABLE: &'static str = "task_events";
+    const ATTACHMENT_TABLE: &'static str = "attachments";
+    const TASK_LINK_TABLE: &'static str = "task_links";
+    const RENDERED_BODY_TABLE: &'static str = "rendered_bodies";
+    const TEMPLATE_TABLE: &'static str = "templates";
+    const TEMPLATE_CHECKLIST_TABLE: &'static str = "template_checklist_items";
+
+    const ATTACHMENT_DIRECTORY_NAME: &'static str = "attachments";
+
+    const USER_TIMEZONE_SETTING: &'static str = "user_timezone";
+
+    /// Changes to the schema made after the tables created in `connect`, in order.
+    /// A database with `user_version` `n` has had the first `n` of these applied.
+    const MIGRATIONS: &'static [fn(&Transaction) -> rusqlite::Result<()>] = &[
+        Db::migrate_all_day_and_settings,
+        Db::migrate_integer_timestamps,
+        Db::migrate_reminders,
+        Db::migrate_time_entries,
+        Db::migrate_focus_sessions,
+        Db::migrate_habits,
+        Db::migrate_time_blocks,
+        Db::migrate_projects,
+        Db::migrate_boards,
+        Db::migrate_task_ranks,
+        Db::migrate_checklists,
+        Db::migrate_activity,
+        Db::migrate_attachments,
+        Db::migrate_task_links,
+        Db::migrate_rendered_bodies,
+        Db::migrate_templates,
+        Db::migrate_defer_times,
+        Db::migrate_estimates,
+        Db::migrate_auto_scheduled_blocks,
+        Db::migrate_cards_per_board,
+    ];
 
     /// Creates a database instance from either an empty/ nonexistent file or an existing database.
     pub fn connect<P: AsRef<Path>>(database_file: P) -> DbResult<Self> {
-        let connection = Connection::open(database_file)?;
+        let attachment_directory = database_file.as_ref().parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(Db::ATTACHMENT_DIRECTORY_NAME);
+        let mut connection = Connection::open(database_file)?;
         connection.execute(&format!(r#"
             create table if not exists {} (
                 "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
@@ -67,11 +216,487 @@ impl Db {
                 PRIMARY KEY (task_id, tag_id)
             );
         "#, Db::TAG_TASK_TABLE), ()).unwrap();
+        Db::migrate(&mut connection)?;
         Ok(Self {
-            conn: connection
+            conn: connection,
+            attachment_directory,
         })
     }
 
+    /// Applies the `MIGRATIONS` this database has not had yet, each in its own transaction.
+    fn migrate(connection: &mut Connection) -> DbResult<()> {
+        let version: usize = connection.query_row("PRAGMA user_version;", [], |row| row.get(0))?;
+        for (index, migration) in Db::MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = connection.transaction()?;
+            migration(&tx)?;
+            tx.pragma_update(None, "user_version", index + 1)?;
+            tx.commit()?;
+        }
+        Ok(())
+    }
+
+    fn migrate_all_day_and_settings(tx: &Transaction) -> rusqlite::Result<()> {
+        tx.execute(&format!(r#"
+            ALTER TABLE {} ADD COLUMN "due_all_day" INTEGER NOT NULL DEFAULT 0;
+        "#, Db::TASK_TABLE), ())?;
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "key" TEXT NOT NULL PRIMARY KEY,
+                "value" TEXT NOT NULL
+            );
+        "#, Db::SETTINGS_TABLE), ())?;
+        Ok(())
+    }
+
+    /// Converts the text timestamps written by chrono into integer seconds (see `MyDateTime`),
+    /// rebuilding the tables so their time columns are declared as integers.
+    fn migrate_integer_timestamps(tx: &Transaction) -> rusqlite::Result<()> {
+        Db::rebuild_table(tx, Db::TAG_TABLE, r#"
+            "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+            "name" TEXT NOT NULL,
+            "color" INTEGER NOT NULL,
+            "active" INTEGER NOT NULL,
+            "create_time" INTEGER NOT NULL
+        "#, &["id", "name", "color", "active"], &["create_time"])?;
+        Db::rebuild_table(tx, Db::TASK_TABLE, r#"
+            "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+            "title" TEXT NOT NULL,
+            "body" TEXT NOT NULL,
+            "difficulty" INTEGER NOT NULL,
+            "create_time" INTEGER NOT NULL,
+            "last_edit_time" INTEGER NOT NULL,
+            "due_time" INTEGER,
+            "due_all_day" INTEGER NOT NULL DEFAULT 0,
+            "target_time" INTEGER,
+            "done_time" INTEGER,
+            "paused" INTEGER
+        "#, &["id", "title", "body", "difficulty", "due_all_day", "paused"],
+            &["create_time", "last_edit_time", "due_time", "target_time", "done_time"])?;
+        tx.execute(&format!(
+            "CREATE INDEX tasks_due_time ON {} (due_time);", Db::TASK_TABLE
+        ), ())?;
+        tx.execute(&format!(
+            "CREATE INDEX tasks_done_time ON {} (done_time);", Db::TASK_TABLE
+        ), ())?;
+        Ok(())
+    }
+
+    /// Recreates `table` with new `columns`, copying over the `kept` columns as is and
+    /// converting the `time_columns` from text to integer seconds.
+    /// Keeps the autoincrement sequence, so ids of deleted rows are still not reused.
+    fn rebuild_table(tx: &Transaction, table: &str, columns: &str, kept: &[&str], time_columns: &[&str]) -> rusqlite::Result<()> {
+        let sequence: Option<i64> = tx.query_row(
+            "SELECT seq FROM sqlite_sequence WHERE name = ?1", (table,), |row| row.get(0)
+        ).optional()?;
+        tx.execute(&format!("CREATE TABLE {table}_new ({columns});"), ())?;
+        let names = kept.iter().chain(time_columns).copied().collect::<Vec<_>>().join(", ");
+        let values = kept.iter().map(|column| column.to_string())
+            .chain(time_columns.iter().map(|column| format!(
+                // integers (already converted) are kept, text is parsed by sqlite
+                "CASE typeof({column}) WHEN 'text' THEN CAST(strftime('%s', {column}) AS INTEGER) ELSE {column} END"
+            )))
+            .collect::<Vec<_>>().join(", ");
+        tx.execute(&format!("INSERT INTO {table}_new ({names}) SELECT {values} FROM {table};"), ())?;
+        tx.execute(&format!("DROP TABLE {table};"), ())?;
+        tx.execute(&format!("ALTER TABLE {table}_new RENAME TO {table};"), ())?;
+        if let Some(sequence) = sequence {
+            // the new table only has a row here if it has rows itself
+            let current: Option<i64> = tx.query_row(
+                "SELECT seq FROM sqlite_sequence WHERE name = ?1", (table,), |row| row.get(0)
+            ).optional()?;
+            tx.execute("DELETE FROM sqlite_sequence WHERE name = ?1", (table,))?;
+            tx.execute(
+                "INSERT INTO sqlite_sequence (name, seq) values (?1, ?2)", (table, current.unwrap_or(0).max(sequence))
+            )?;
+        }
+        Ok(())
+    }
+
+    fn migrate_reminders(tx: &Transaction) -> rusqlite::Result<()> {
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+                "task_id" INTEGER NOT NULL,
+                "kind" TEXT NOT NULL,
+                "value" INTEGER NOT NULL,
+                "snoozed_until" INTEGER,
+                "dismissed" INTEGER NOT NULL,
+                "last_fired_time" INTEGER
+            );
+        "#, Db::REMINDER_TABLE), ())?;
+        Ok(())
+    }
+
+    fn migrate_time_entries(tx: &Transaction) -> rusqlite::Result<()> {
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+                "task_id" INTEGER NOT NULL,
+                "start_time" INTEGER NOT NULL,
+                "end_time" INTEGER
+            );
+        "#, Db::TIME_ENTRY_TABLE), ())?;
+        tx.execute(&format!(
+            "CREATE INDEX time_entries_task_id ON {} (task_id);", Db::TIME_ENTRY_TABLE
+        ), ())?;
+        Ok(())
+    }
+
+    fn migrate_focus_sessions(tx: &Transaction) -> rusqlite::Result<()> {
+        // holds at most the one session in progress
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "id" INTEGER NOT NULL PRIMARY KEY CHECK (id = 0),
+                "task_id" INTEGER NOT NULL,
+                "phase" TEXT NOT NULL,
+                "phase_start" INTEGER NOT NULL,
+                "phase_end" INTEGER NOT NULL,
+                "completed_in_cycle" INTEGER NOT NULL
+            );
+        "#, Db::FOCUS_STATE_TABLE), ())?;
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+                "task_id" INTEGER NOT NULL,
+                "start_time" INTEGER NOT NULL,
+                "end_time" INTEGER NOT NULL
+            );
+        "#, Db::FOCUS_SESSION_TABLE), ())?;
+        tx.execute(&format!(
+            "CREATE INDEX focus_sessions_end_time ON {} (end_time);", Db::FOCUS_SESSION_TABLE
+        ), ())?;
+        Ok(())
+    }
+
+    fn migrate_habits(tx: &Transaction) -> rusqlite::Result<()> {
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "task_id" INTEGER NOT NULL PRIMARY KEY,
+                "every_days" INTEGER NOT NULL,
+                "grace_days" INTEGER NOT NULL
+            );
+        "#, Db::HABIT_TABLE), ())?;
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "task_id" INTEGER NOT NULL,
+                "date" TEXT NOT NULL,
+                PRIMARY KEY (task_id, date)
+            );
+        "#, Db::HABIT_CHECK_IN_TABLE), ())?;
+        Ok(())
+    }
+
+    fn migrate_time_blocks(tx: &Transaction) -> rusqlite::Result<()> {
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+                "task_id" INTEGER,
+                "title" TEXT NOT NULL,
+                "start_time" INTEGER NOT NULL,
+                "end_time" INTEGER NOT NULL,
+                "fixed" INTEGER NOT NULL
+            );
+        "#, Db::TIME_BLOCK_TABLE), ())?;
+        tx.execute(&format!(
+            "CREATE INDEX time_blocks_start_time ON {} (start_time);", Db::TIME_BLOCK_TABLE
+        ), ())?;
+        Ok(())
+    }
+
+    fn migrate_projects(tx: &Transaction) -> rusqlite::Result<()> {
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+                "name" TEXT NOT NULL,
+                "description" TEXT NOT NULL,
+                "status" TEXT NOT NULL,
+                "deadline" INTEGER,
+                "archived" INTEGER NOT NULL,
+                "create_time" INTEGER NOT NULL
+            );
+        "#, Db::PROJECT_TABLE), ())?;
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "task_id" INTEGER NOT NULL PRIMARY KEY,
+                "project_id" INTEGER NOT NULL,
+                "position" INTEGER NOT NULL
+            );
+        "#, Db::PROJECT_TASK_TABLE), ())?;
+        tx.execute(&format!(
+            "CREATE INDEX projects_tasks_project_id ON {} (project_id, position);", Db::PROJECT_TASK_TABLE
+        ), ())?;
+        Ok(())
+    }
+
+    fn migrate_boards(tx: &Transaction) -> rusqlite::Result<()> {
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+                "name" TEXT NOT NULL
+            );
+        "#, Db::BOARD_TABLE), ())?;
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+                "board_id" INTEGER NOT NULL,
+                "name" TEXT NOT NULL,
+                "wip_limit" INTEGER,
+                "terminal" INTEGER NOT NULL,
+                "position" INTEGER NOT NULL
+            );
+        "#, Db::BOARD_COLUMN_TABLE), ())?;
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "task_id" INTEGER NOT NULL PRIMARY KEY,
+                "column_id" INTEGER NOT NULL,
+                "position" INTEGER NOT NULL
+            );
+        "#, Db::BOARD_CARD_TABLE), ())?;
+        tx.execute(&format!(
+            "CREATE INDEX board_cards_column_id ON {} (column_id, position);", Db::BOARD_CARD_TABLE
+        ), ())?;
+        Ok(())
+    }
+
+    fn migrate_task_ranks(tx: &Transaction) -> rusqlite::Result<()> {
+        tx.execute(&format!(r#"
+            ALTER TABLE {} ADD COLUMN "rank" TEXT NOT NULL DEFAULT '';
+        "#, Db::TASK_TABLE), ())?;
+        // existing tasks keep their order of insertion
+        let ids: Vec<TaskId> = tx.prepare(&format!("SELECT id FROM {} ORDER BY id", Db::TASK_TABLE))?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        for (id, rank) in ids.iter().zip(rank_sequence(ids.len())) {
+            tx.execute(&format!(
+                "UPDATE {} SET rank = ?2 WHERE id = ?1;", Db::TASK_TABLE
+            ), (id, rank))?;
+        }
+        tx.execute(&format!(
+            "CREATE INDEX tasks_rank ON {} (rank);", Db::TASK_TABLE
+        ), ())?;
+        Ok(())
+    }
+
+    fn migrate_checklists(tx: &Transaction) -> rusqlite::Result<()> {
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+                "task_id" INTEGER NOT NULL,
+                "text" TEXT NOT NULL,
+                "checked" INTEGER NOT NULL,
+                "position" INTEGER NOT NULL
+            );
+        "#, Db::CHECKLIST_TABLE), ())?;
+        tx.execute(&format!(
+            "CREATE INDEX checklist_items_task_id ON {} (task_id, position);", Db::CHECKLIST_TABLE
+        ), ())?;
+        Ok(())
+    }
+
+    fn migrate_activity(tx: &Transaction) -> rusqlite::Result<()> {
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+                "task_id" INTEGER NOT NULL,
+                "author" TEXT NOT NULL,
+                "text" TEXT NOT NULL,
+                "create_time" INTEGER NOT NULL,
+                "edit_time" INTEGER
+            );
+        "#, Db::COMMENT_TABLE), ())?;
+        tx.execute(&format!(
+            "CREATE INDEX comments_task_id ON {} (task_id);", Db::COMMENT_TABLE
+        ), ())?;
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+                "task_id" INTEGER NOT NULL,
+                "time" INTEGER NOT NULL,
+                "kind" TEXT NOT NULL,
+                "from_tag" INTEGER,
+                "to_tag" INTEGER
+            );
+        "#, Db::TASK_EVENT_TABLE), ())?;
+        tx.execute(&format!(
+            "CREATE INDEX task_events_task_id ON {} (task_id);", Db::TASK_EVENT_TABLE
+        ), ())?;
+        Ok(())
+    }
+
+    fn migrate_attachments(tx: &Transaction) -> rusqlite::Result<()> {
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+                "task_id" INTEGER NOT NULL,
+                "name" TEXT NOT NULL,
+                "size" INTEGER NOT NULL,
+                "mime" TEXT NOT NULL,
+                "hash" TEXT NOT NULL,
+                "create_time" INTEGER NOT NULL
+            );
+        "#, Db::ATTACHMENT_TABLE), ())?;
+        tx.execute(&format!(
+            "CREATE INDEX attachments_task_id ON {} (task_id);", Db::ATTACHMENT_TABLE
+        ), ())?;
+        tx.execute(&format!(
+            "CREATE INDEX attachments_hash ON {} (hash);", Db::ATTACHMENT_TABLE
+        ), ())?;
+        Ok(())
+    }
+
+    /// Creates the links of the tasks that already exist from their bodies.
+    fn migrate_task_links(tx: &Transaction) -> rusqlite::Result<()> {
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "source_id" INTEGER NOT NULL,
+                "position" INTEGER NOT NULL,
+                "title" TEXT,
+                "target_id" INTEGER,
+                PRIMARY KEY (source_id, position)
+            );
+        "#, Db::TASK_LINK_TABLE), ())?;
+        tx.execute(&format!(
+            "CREATE INDEX task_links_target_id ON {} (target_id);", Db::TASK_LINK_TABLE
+        ), ())?;
+        tx.execute(&format!(
+            "CREATE INDEX task_links_title ON {} (title);", Db::TASK_LINK_TABLE
+        ), ())?;
+        let bodies = {
+            let mut stmt = tx.prepare(&format!("SELECT id, body FROM {}", Db::TASK_TABLE))?;
+            let iter = stmt.query_map([], |row| Ok((row.get::<_, TaskId>(0)?, row.get::<_, String>(1)?)))?;
+            iter.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        for (id, body) in bodies {
+            Db::update_task_links(tx, id, &body)?;
+        }
+        Ok(())
+    }
+
+    /// Rendered bodies are a cache; see `rendered_body`.
+    fn migrate_rendered_bodies(tx: &Transaction) -> rusqlite::Result<()> {
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "task_id" INTEGER NOT NULL PRIMARY KEY,
+                "html" TEXT NOT NULL,
+                "preview" TEXT NOT NULL
+            );
+        "#, Db::RENDERED_BODY_TABLE), ())?;
+        Ok(())
+    }
+
+    fn migrate_templates(tx: &Transaction) -> rusqlite::Result<()> {
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+                "name" TEXT NOT NULL,
+                "title" TEXT NOT NULL,
+                "body" TEXT NOT NULL,
+                "tag_id" INTEGER,
+                "difficulty" INTEGER NOT NULL,
+                "due_offset" INTEGER,
+                "due_all_day" INTEGER NOT NULL,
+                "target_offset" INTEGER
+            );
+        "#, Db::TEMPLATE_TABLE), ())?;
+        tx.execute(&format!(r#"
+            create table if not exists {} (
+                "template_id" INTEGER NOT NULL,
+                "position" INTEGER NOT NULL,
+                "text" TEXT NOT NULL,
+                PRIMARY KEY (template_id, position)
+            );
+        "#, Db::TEMPLATE_CHECKLIST_TABLE), ())?;
+        Ok(())
+    }
+
+    fn migrate_defer_times(tx: &Transaction) -> rusqlite::Result<()> {
+        tx.execute(&format!(r#"
+            ALTER TABLE {} ADD COLUMN "defer_time" INTEGER;
+        "#, Db::TASK_TABLE), ())?;
+        Ok(())
+    }
+
+    fn migrate_estimates(tx: &Transaction) -> rusqlite::Result<()> {
+        tx.execute(&format!(r#"
+            ALTER TABLE {} ADD COLUMN "estimate_minutes" INTEGER;
+        "#, Db::TASK_TABLE), ())?;
+        tx.execute(&format!(r#"
+            ALTER TABLE {} ADD COLUMN "remaining_minutes" INTEGER;
+        "#, Db::TASK_TABLE), ())?;
+        Ok(())
+    }
+
+    fn migrate_auto_scheduled_blocks(tx: &Transaction) -> rusqlite::Result<()> {
+        // blocks made before are kept as the user's, since they can't be told apart
+        tx.execute(&format!(r#"
+            ALTER TABLE {} ADD COLUMN "auto_scheduled" INTEGER NOT NULL DEFAULT 0;
+        "#, Db::TIME_BLOCK_TABLE), ())?;
+        Ok(())
+    }
+
+    fn migrate_cards_per_board(tx: &Transaction) -> rusqlite::Result<()> {
+        // a task could only be on one board, keyed by the task alone
+        tx.execute(&format!(r#"
+            CREATE TABLE {0}_new (
+                "board_id" INTEGER NOT NULL,
+                "task_id" INTEGER NOT NULL,
+                "column_id" INTEGER NOT NULL,
+                "position" INTEGER NOT NULL,
+                PRIMARY KEY (board_id, task_id)
+            );
+        "#, Db::BOARD_CARD_TABLE), ())?;
+        tx.execute(&format!(r#"
+            INSERT INTO {0}_new (board_id, task_id, column_id, position)
+            SELECT columns.board_id, cards.task_id, cards.column_id, cards.position
+            FROM {0} cards INNER JOIN {1} columns ON columns.id = cards.column_id;
+        "#, Db::BOARD_CARD_TABLE, Db::BOARD_COLUMN_TABLE), ())?;
+        tx.execute(&format!("DROP TABLE {};", Db::BOARD_CARD_TABLE), ())?;
+        tx.execute(&format!("ALTER TABLE {0}_new RENAME TO {0};", Db::BOARD_CARD_TABLE), ())?;
+        tx.execute(&format!(
+            "CREATE INDEX board_cards_column_id ON {} (column_id, position);", Db::BOARD_CARD_TABLE
+        ), ())?;
+        Ok(())
+    }
+
+    fn setting(&self, key: &str) -> DbResult<Option<String>> {
+        Ok(self.conn.query_row(&format!(
+            "SELECT value FROM {} WHERE key = ?1", Db::SETTINGS_TABLE
+        ), (key,), |row| row.get("value")).optional()?)
+    }
+
+    fn set_setting(&mut self, key: &str, value: &str) -> DbResult<()> {
+        self.conn.execute(&format!(r#"
+            INSERT INTO {} (key, value) values (?1, ?2)
+            ON CONFLICT (key) DO UPDATE SET value = excluded.value;
+        "#, Db::SETTINGS_TABLE), (key, value))?;
+        Ok(())
+    }
+
+    /// The setting with `key` parsed from text, or `None` if it was never set or doesn't parse.
+    fn parsed_setting<T: FromStr>(&self, key: &str) -> DbResult<Option<T>> {
+        Ok(self.setting(key)?.and_then(|value| value.parse().ok()))
+    }
+
+    /// The timezone of the user, used to give dates such as "today" a meaning.
+    /// Defaults to UTC if it was never set.
+    pub fn user_timezone(&self) -> DbResult<Tz> {
+        Ok(self.setting(Db::USER_TIMEZONE_SETTING)?
+            .and_then(|name| parse_timezone(&name))
+            .unwrap_or(Tz::UTC))
+    }
+
+    /// Sets the timezone of the user by its IANA name, such as `"America/New_York"`.
+    ///
+    /// # Failure
+    /// Returns `DbError::InvalidTimezoneError` if `name` is not a known timezone.
+    pub fn set_user_timezone(&mut self, name: &str) -> DbResult<()> {
+        let timezone = match parse_timezone(name) {
+            Some(timezone) => timezone,
+            None => return Err(DbError::InvalidTimezoneError { name: name.to_string() }),
+        };
+        self.set_setting(Db::USER_TIMEZONE_SETTING, timezone.name())
+    }
+
     // // note: see https://users.rust-lang.org/t/closure-accepting-an-iterator-as-a-parameter/77905/4
     // /// Exposes an iterator over all tags in this database, in order of insertion.
     // /// Accepts a function which you can use to do whatever with the iterator,
@@ -104,8 +729,8 @@ impl Db {
     ///
     /// Returns the fields generated for this tag.
     pub fn add_new_tag(&mut self, data: &EditableTagData) -> DbResult<GeneratedTagData> {
-        let now = Utc::now();
-        let tx = self.conn.transaction()?;
+        let now = MyDateTime::now();
+        let tx = self.conn.savepoint()?;
         tx.execute(&format!(
             "INSERT INTO {} (name, color, active, create_time) values (?1, ?2, ?3, ?4);", Db::TAG_TABLE
         ), (&data.name, &data.color, data.active, &now))?;
@@ -113,7 +738,7 @@ impl Db {
         tx.commit()?;
         Ok(GeneratedTagData {
             id: new_id,
-            create_time: now,
+            create_time: now.into(),
         })
     }
 
@@ -136,7 +761,7 @@ impl Db {
     /// # Failure
     /// Returns `DbError::TagDoesNotExistError` if the tag being modified doesn't exist in the database.
     pub fn modify_tag(&mut self, id: TagId, modify: &EditableTagData) -> DbResult<()> {
-        let tx = self.conn.transaction()?;
+        let tx = self.conn.savepoint()?;
         let rows = tx.execute(&format!(r#"
                 UPDATE {} SET
                     name = ?2,
@@ -152,12 +777,12 @@ impl Db {
         }
     }
 
-    /// Delete a tag by its id in the database (and removes it from any tasks that have this tag).
+    /// Delete a tag by its id in the database (and removes it from any tasks and templates that have this tag).
     ///
     /// # Failure
     /// Returns `DbError::TagDoesNotExistError` if the tag being deleted doesn't exist in the database.
     pub fn delete_tag(&mut self, id: TagId) -> DbResult<()> {
-        let tx = self.conn.transaction()?;
+        let tx = self.conn.savepoint()?;
         let rows = tx.execute(&format!(
             "DELETE FROM {} WHERE id = ?1", {Db::TAG_TABLE}
         ), (id,))?;
@@ -170,6 +795,12 @@ impl Db {
             "DELETE FROM {} WHERE tag_id = ?1",
             Db::TAG_TASK_TABLE
         ), (id,))?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE key = ?1", Db::SETTINGS_TABLE
+        ), (format!("{}{}", Db::URGENCY_TAG_WEIGHT_SETTING_PREFIX, id),))?;
+        tx.execute(&format!(
+            "UPDATE {} SET tag_id = NULL WHERE tag_id = ?1", Db::TEMPLATE_TABLE
+        ), (id,))?;
 
         tx.commit()?;
         Ok(())
@@ -190,10 +821,10 @@ impl Db {
     }
 
     /// Convenience method to retrieves all tasks stored in this database
-    /// in order of insertion.
+    /// in their manual order (see `move_task_before`), which is order of insertion unless changed.
     pub fn all_tasks(&self) -> DbResult<Vec<Task>> {
         let mut stmt = self.conn.prepare(
-            &format!("SELECT * FROM {}", Db::TASK_TABLE)
+            &format!("SELECT * FROM {} ORDER BY rank, id", Db::TASK_TABLE)
         ).unwrap();
         let iter = stmt.query_map([], |row| self.task_from_row(row))?;
         Ok(iter.map(|task| task.unwrap()).collect())
@@ -206,32 +837,71 @@ impl Db {
     /// * Its done time to None
     ///
     /// Returns the fields generated for this task.
+    ///
+    /// # Failure
+    /// Returns `DbError::TagDoesNotExistError` if attempted to add a tag that doesn't exist.
+    /// Returns `DbError::InvalidAllDayError` if the all-day due time is not valid (see `EditableTaskData::is_valid`).
     pub fn add_new_task(&mut self, data: &EditableTaskData) -> DbResult<GeneratedTaskData> {
+        if !data.is_valid() {
+            return Err(DbError::InvalidAllDayError);
+        }
         if let Some(tag_id) = data.tag {
             if self.tag_by_id(tag_id).unwrap().is_none() {
                 return Err(DbError::TagDoesNotExistError {id: tag_id});
             }
         }
 
-        let now = Utc::now();
-        let tx = self.conn.transaction()?;
+        let now = MyDateTime::now();
+        let tx = self.conn.savepoint()?;
+        let new_id = Db::insert_task(&tx, data, &now)?;
+        tx.commit()?;
+        Ok(GeneratedTaskData {
+            id: new_id,
+            create_time: now.into(),
+            last_edit_time: now.into(),
+            done_time: None,
+        })
+    }
+
+    /// Inserts a new task created at `now` as the last in the manual order, returning its id.
+    /// Its tag must exist.
+    fn insert_task(tx: &Connection, data: &EditableTaskData, now: &MyDateTime) -> DbResult<TaskId> {
+        let last_rank: Option<String> = tx.query_row(&format!(
+            "SELECT max(rank) FROM {}", Db::TASK_TABLE
+        ), [], |row| row.get(0))?;
+        let rank = rank_after(last_rank.as_deref());
         tx.execute(&format!(r#"
             INSERT INTO {}
-            (title, body, difficulty, create_time, last_edit_time, due_time, target_time, paused) values
-            (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8);
-        "#, Db::TASK_TABLE), (&data.title, &data.body, data.difficulty, &now, &now, &data.due_time, &data.target_time, data.paused))?;
+            (title, body, difficulty, create_time, last_edit_time, due_time, due_all_day, target_time, paused, rank) values
+            (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10);
+        "#, Db::TASK_TABLE), (&data.title, &data.body, data.difficulty, now, now,
+                              data.due_time.map(MyDateTime::from), data.due_all_day,
+                              data.target_time.map(MyDateTime::from), data.paused, &rank))?;
         let new_id = tx.last_insert_rowid();
+        if rank.len() > Db::MAX_RANK_LENGTH {
+            Db::rebalance_ranks(tx)?;
+        }
         if let Some(tag) = data.tag {
             tx.execute(&format!(
                 "INSERT INTO {} (task_id, tag_id) values (?1, ?2);", Db::TAG_TASK_TABLE
             ), (new_id, tag))?;
         }
-        tx.commit()?;
-        Ok(GeneratedTaskData {
-            id: new_id,
-            create_time: now.clone(),
-            last_edit_time: now,
-            done_time: None,
+        Db::update_task_links(tx, new_id, &data.body)?;
+        Db::resolve_task_links(tx, new_id, &data.title)?;
+        Db::record_task_event(tx, new_id, (*now).into(), &TaskEvent::Created)?;
+        Ok(new_id)
+    }
+
+    /// Parses a quick-add line (see `parse_quick_add`) against the tags in this database,
+    /// resolving relative dates against `now`, and adds the task it describes.
+    ///
+    /// Returns the parsed line along with the fields generated for this task.
+    pub fn quick_add_task<Tz: TimeZone>(&mut self, input: &str, now: &DateTime<Tz>) -> DbResult<QuickAddedTaskData> {
+        let parsed = parse_quick_add(input, now, &self.all_tags()?);
+        let generated = self.add_new_task(&parsed.data)?;
+        Ok(QuickAddedTaskData {
+            parsed,
+            generated,
         })
     }
 
@@ -250,19 +920,26 @@ impl Db {
     }
 
     /// Modifies an existing task in the database, updating the last edit time to now.
+    /// Renaming the task also rewrites `[[Old title]]` links to it in the bodies of other tasks.
     ///
     /// # Failure
     /// Returns `DbError::TaskDoesNotExistError` if the task being modified doesn't exist in the database.
     /// Returns `DbError::TagDoesNotExistError` if attempted to add a tag that doesn't exist.
+    /// Returns `DbError::InvalidAllDayError` if the all-day due time is not valid (see `EditableTaskData::is_valid`).
     pub fn modify_task(&mut self, id: TaskId, data: &EditableTaskData) -> DbResult<ModifiedTaskData> {
+        if !data.is_valid() {
+            return Err(DbError::InvalidAllDayError);
+        }
         if let Some(tag_id) = data.tag {
             if self.tag_by_id(tag_id).unwrap().is_none() {
                 return Err(DbError::TagDoesNotExistError {id: tag_id});
             }
         }
 
-        let now = Utc::now();
-        let tx = self.conn.transaction()?;
+        let old_title = self.task_by_id(id)?.map(|task| task.title);
+        let old_tag = self.get_task_tag(id);
+        let now = MyDateTime::now();
+        let tx = self.conn.savepoint()?;
         let rows = tx.execute(&format!(r#"
                 UPDATE {} SET
                     title = ?2,
@@ -270,11 +947,13 @@ impl Db {
                     difficulty = ?4,
                     last_edit_time = ?5,
                     due_time = ?6,
-                    target_time = ?7,
-                    paused = ?8
+                    due_all_day = ?7,
+                    target_time = ?8,
+                    paused = ?9
                 WHERE id = ?1;
         "#, Db::TASK_TABLE),
-      (id, &data.title, &data.body, data.difficulty, &now, &data.due_time, &data.target_time, data.paused))?;
+      (id, &data.title, &data.body, data.difficulty, &now, data.due_time.map(MyDateTime::from),
+       data.due_all_day, data.target_time.map(MyDateTime::from), data.paused))?;
 
         if rows == 0 {
             return Err(DbError::TaskDoesNotExistError { id });
@@ -293,19 +972,40 @@ impl Db {
                 "#, Db::TAG_TASK_TABLE
             ), (id, tag_id))?;
         }
+        Db::update_task_links(&tx, id, &data.body)?;
+        Db::invalidate_rendered_body(&tx, id)?;
+        if let Some(old_title) = old_title.filter(|title| *title != data.title) {
+            Db::rename_task_links(&tx, id, &old_title, &data.title)?;
+        }
+        Db::record_task_event(&tx, id, now.into(), &TaskEvent::Edited)?;
+        if old_tag != data.tag {
+            Db::record_task_event(&tx, id, now.into(), &TaskEvent::TagChanged { from: old_tag, to: data.tag })?;
+        }
         tx.commit()?;
 
         Ok(ModifiedTaskData {
-            last_edit_time: now,
+            last_edit_time: now.into(),
         })
     }
 
-    /// Delete a task by its id in the database.
+    /// Delete a task by its id in the database, along with the stored contents of its attachments
+    /// that no other task has.
+    /// Links to the task are kept, but broken (see `broken_links`).
     ///
     /// # Failure
     /// Returns `DbError::TaskDoesNotExistError` if the task being deleted doesn't exist in the database.
     pub fn delete_task(&mut self, id: TaskId) -> DbResult<()> {
-        let tx = self.conn.transaction()?;
+        let tx = self.conn.savepoint()?;
+        Db::delete_task_rows(&tx, id)?;
+        tx.commit()?;
+        // the task is deleted either way; see collect_attachment_garbage
+        _ = self.collect_attachment_garbage();
+        Ok(())
+    }
+
+    /// Deletes a task along with everything that belongs to it, except the stored contents
+    /// of its attachments; see `collect_attachment_garbage`.
+    fn delete_task_rows(tx: &Connection, id: TaskId) -> DbResult<()> {
         let rows = tx.execute(&format!(
             "DELETE FROM {} WHERE id = ?1;", Db::TASK_TABLE
         ), (id,))?;
@@ -317,64 +1017,175 @@ impl Db {
         tx.execute(&format!(
             "DELETE FROM {} WHERE task_id = ?1;", Db::TAG_TASK_TABLE
         ), (id,))?;
-        tx.commit()?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1;", Db::REMINDER_TABLE
+        ), (id,))?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1;", Db::TIME_ENTRY_TABLE
+        ), (id,))?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1;", Db::FOCUS_SESSION_TABLE
+        ), (id,))?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1;", Db::FOCUS_STATE_TABLE
+        ), (id,))?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1;", Db::HABIT_TABLE
+        ), (id,))?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1;", Db::HABIT_CHECK_IN_TABLE
+        ), (id,))?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1;", Db::TIME_BLOCK_TABLE
+        ), (id,))?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1;", Db::PROJECT_TASK_TABLE
+        ), (id,))?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1;", Db::BOARD_CARD_TABLE
+        ), (id,))?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1;", Db::CHECKLIST_TABLE
+        ), (id,))?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1;", Db::COMMENT_TABLE
+        ), (id,))?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1;", Db::TASK_EVENT_TABLE
+        ), (id,))?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1;", Db::ATTACHMENT_TABLE
+        ), (id,))?;
+        Db::delete_task_links(tx, id)?;
+        Db::invalidate_rendered_body(tx, id)?;
         Ok(())
     }
 
-    /// Mark a task as done, updating the done time of this task. Returns the new done time.
+    /// Mark a task as done now; see `finish_task_at`.
+    pub fn finish_task(&mut self, id: TaskId) -> DbResult<FinishedTaskData> {
+        self.finish_task_at(id, Utc::now())
+    }
+
+    /// Mark a task as done at `now`, updating the done time of this task and stopping its timer if it is running.
+    /// Returns the new done time.
+    ///
+    /// A habit is instead checked in for the day of `now` (see `check_in_habit`) and stays not done.
     ///
     /// # Failure
     /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
-    /// Returns `DbError::TaskStatusError` if the task is already finished.
-    pub fn finish_task(&mut self, id: TaskId) -> DbResult<FinishedTaskData> {
+    /// Returns `DbError::TaskStatusError` if the task is already finished, or the habit already checked in today.
+    /// Returns `DbError::ChecklistIncompleteError` if items of its checklist are unchecked
+    /// and complete checklists are required (see `set_require_complete_checklist`).
+    pub fn finish_task_at(&mut self, id: TaskId, now: DateTime<Utc>) -> DbResult<FinishedTaskData> {
         let task = match self.task_by_id(id)? {
             Some(task) => task,
             None => return Err(DbError::TaskDoesNotExistError {id}),
         };
+        if !task.checklist.is_complete() && self.require_complete_checklist()? {
+            return Err(DbError::ChecklistIncompleteError {
+                id,
+                unchecked: task.checklist.total - task.checklist.checked,
+            });
+        }
+        if self.habit(id)?.is_some() {
+            let today = self.habit_today(&now)?;
+            let mut savepoint = DbSavepoint::new(self, "finish_habit")?;
+            savepoint.check_in_habit(id, today)?;
+            Db::stop_task_timer(&savepoint.conn, id, now)?;
+            savepoint.commit()?;
+            return Ok(FinishedTaskData {
+                done_time: None,
+            });
+        }
         if task.done_time.is_some() {
             return Err(DbError::TaskStatusError { id, actual_status: true });
         }
-        let done_time = Some(Utc::now());
-        let tx = self.conn.transaction()?;
+        let now = MyDateTime::from(now);
+        let done_time = Some(now);
+        let tx = self.conn.savepoint()?;
         tx.execute(&format!(
             "UPDATE {} SET done_time = ?2 WHERE id = ?1;", Db::TASK_TABLE
         ), (id, &done_time))?;
+        Db::stop_task_timer(&tx, id, now.into())?;
+        Db::record_task_event(&tx, id, now.into(), &TaskEvent::Finished)?;
         tx.commit()?;
         Ok(FinishedTaskData {
-            done_time,
+            done_time: done_time.map(DateTime::from),
         })
     }
 
-    /// Mark a task as not done, updating the done time of this task. Returns the new done time.
+    /// Mark a task as not done now; see `unfinish_task_at`.
+    pub fn unfinish_task(&mut self, id: TaskId) -> DbResult<FinishedTaskData> {
+        self.unfinish_task_at(id, Utc::now())
+    }
+
+    /// Mark a task as not done at `now`, updating the done time of this task. Returns the new done time.
+    ///
+    /// A habit instead has its check-in for the day of `now` removed.
     ///
     /// # Failure
     /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
-    /// Returns `DbError::TaskStatusError` if the task is already not finished.
-    pub fn unfinish_task(&mut self, id: TaskId) -> DbResult<FinishedTaskData> {
+    /// Returns `DbError::TaskStatusError` if the task is already not finished, or the habit not checked in today.
+    pub fn unfinish_task_at(&mut self, id: TaskId, now: DateTime<Utc>) -> DbResult<FinishedTaskData> {
         let task = match self.task_by_id(id)? {
             Some(task) => task,
             None => return Err(DbError::TaskDoesNotExistError {id}),
         };
+        if self.habit(id)?.is_some() {
+            let today = self.habit_today(&now)?;
+            self.undo_habit_check_in(id, today)?;
+            return Ok(FinishedTaskData {
+                done_time: None,
+            });
+        }
         if task.done_time.is_none() {
             return Err(DbError::TaskStatusError { id, actual_status: false });
         }
-        let done_time = None;
-        let tx = self.conn.transaction()?;
+        let done_time: Option<DateTime<Utc>> = None;
+        let tx = self.conn.savepoint()?;
         tx.execute(&format!(
             "UPDATE {} SET done_time = ?2 WHERE id = ?1;", Db::TASK_TABLE
-        ), (id, &done_time))?;
+        ), (id, done_time.map(MyDateTime::from)))?;
+        Db::record_task_event(&tx, id, MyDateTime::from(now).into(), &TaskEvent::Unfinished)?;
         tx.commit()?;
         Ok(FinishedTaskData {
             done_time,
         })
     }
 
+    /// Hides a task from default views until `until`, after which it is shown again by itself,
+    /// or shows it again now if `until` is `None`. Its last edit time is unchanged.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    pub fn defer_task(&mut self, id: TaskId, until: Option<DateTime<Utc>>) -> DbResult<()> {
+        let rows = self.conn.execute(&format!(
+            "UPDATE {} SET defer_time = ?2 WHERE id = ?1;", Db::TASK_TABLE
+        ), (id, until.map(MyDateTime::from)))?;
+        if rows == 0 {
+            return Err(DbError::TaskDoesNotExistError { id });
+        } else if rows > 1 {
+            panic!("Defer task changed {} rows!", rows);
+        }
+        Ok(())
+    }
+
+    /// Defers a task until `duration` after `now` (see `defer_task`).
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    /// Returns `DbError::DurationOutOfRangeError` if `duration` moves `now` out of the range of times.
+    pub fn snooze_task(&mut self, id: TaskId, duration: Duration, now: DateTime<Utc>) -> DbResult<()> {
+        let until = now.checked_add_signed(duration).ok_or(DbError::DurationOutOfRangeError)?;
+        self.defer_task(id, Some(until))
+    }
+
     /// Applies a filter function to the tasks in this database, returning
-    /// the tasks that pass it.
+    /// the tasks that pass it in their manual order.
     pub fn filter_tasks<P>(&self, predicate: P) -> DbResult<Vec<Task>>
         where P: Fn(&Task) -> bool {
         let mut stmt = self.conn.prepare(
-            &format!("SELECT * FROM {}", Db::TASK_TABLE)
+            &format!("SELECT * FROM {} ORDER BY rank, id", Db::TASK_TABLE)
         ).unwrap();
         let iter = stmt
             .query_map([], |row| self.task_from_row(row))?
@@ -383,19 +1194,26 @@ impl Db {
         Ok(iter.collect())
     }
 
+    /// Retrieves the tasks due on `date` in the timezone of the user (see `Task::due_date`),
+    /// in their manual order.
+    pub fn tasks_due_on(&self, date: NaiveDate) -> DbResult<Vec<Task>> {
+        let timezone = self.user_timezone()?;
+        self.filter_tasks(|task| task.due_date(&timezone) == Some(date))
+    }
+
     fn tag_from_row(row: &Row) -> rusqlite::Result<Tag> {
         Ok(Tag {
             id: row.get("id")?,
             name: row.get("name")?,
             color: row.get("color")?,
             active: row.get("active")?,
-            create_time: row.get("create_time")?,
+            create_time: row.get::<_, MyDateTime>("create_time")?.into(),
         })
     }
 
     fn get_task_tag(&self, id: TaskId) -> Option<TagId> {
         let mut stmt = self.conn.prepare(
-            &format!("SELECT tag_id FROM {} WHERE task_id = ?1", {Db::TAG_TASK_TABLE})
+            &format!("SELECT tag_id FROM {} WHERE task_id = ?1 ORDER BY rowid", {Db::TAG_TASK_TABLE})
         ).unwrap();
         let tag_id_result: rusqlite::Result<TagId> = stmt.query_row(
             [id], |result| Ok(result.get("tag_id")?)
@@ -403,21 +1221,43 @@ impl Db {
         tag_id_result.ok()
     }
 
+    /// The project of the task with `id` and whether that project is archived.
+    fn get_task_project(&self, id: TaskId) -> (Option<ProjectId>, bool) {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT project_id, archived FROM {} JOIN {} ON id = project_id WHERE task_id = ?1",
+            Db::PROJECT_TASK_TABLE, Db::PROJECT_TABLE
+        )).unwrap();
+        stmt.query_row([id], |row| Ok((Some(row.get("project_id")?), row.get("archived")?)))
+            .unwrap_or((None, false))
+    }
+
     fn task_from_row(&self, row: &Row) -> rusqlite::Result<Task> {
         let id = row.get("id")?;
-        Ok(Task {
+        let (project, archived) = self.get_task_project(id);
+        let checklist = self.checklist_progress(id).unwrap_or_default();
+        let mut task = Task {
             id,
             title: row.get("title")?,
             tag: self.get_task_tag(id),
             body: row.get("body")?,
             difficulty: row.get("difficulty")?,
-            create_time: row.get("create_time")?,
-            last_edit_time: row.get("last_edit_time")?,
-            due_time: row.get("due_time")?,
-            target_time: row.get("target_time")?,
-            done_time: row.get("done_time")?,
+            create_time: row.get::<_, MyDateTime>("create_time")?.into(),
+            last_edit_time: row.get::<_, MyDateTime>("last_edit_time")?.into(),
+            due_time: row.get::<_, Option<MyDateTime>>("due_time")?.map(DateTime::from),
+            due_all_day: row.get("due_all_day")?,
+            target_time: row.get::<_, Option<MyDateTime>>("target_time")?.map(DateTime::from),
+            done_time: row.get::<_, Option<MyDateTime>>("done_time")?.map(DateTime::from),
             paused: row.get("paused")?,
-        })
+            project,
+            archived,
+            checklist,
+            defer_time: row.get::<_, Option<MyDateTime>>("defer_time")?.map(DateTime::from),
+            deferred: false,
+            estimate_minutes: row.get("estimate_minutes")?,
+            remaining_minutes: row.get("remaining_minutes")?,
+        };
+        task.deferred = task.is_deferred_at(&Utc::now());
+        Ok(task)
     }
 
 }
\ No newline at end of file
@@ -0,0 +1,145 @@
+use chrono::{DateTime, Utc};
+use rusqlite::types::Type;
+use rusqlite::{Connection, OptionalExtension, Row};
+use crate::{Activity, ActivityKind, Comment, CommentId, Db, DbError, DbResult, EditableCommentData, GeneratedCommentData, ModifiedCommentData, MyDateTime, TaskEvent, TaskId};
+
+impl Db {
+    /// Add a new comment to a task, created now. Returns the fields generated for this comment.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    pub fn add_comment(&mut self, task_id: TaskId, data: &EditableCommentData) -> DbResult<GeneratedCommentData> {
+        if self.task_by_id(task_id)?.is_none() {
+            return Err(DbError::TaskDoesNotExistError { id: task_id });
+        }
+        let now = MyDateTime::now();
+        self.conn.execute(&format!(
+            "INSERT INTO {} (task_id, author, text, create_time) values (?1, ?2, ?3, ?4);", Db::COMMENT_TABLE
+        ), (task_id, &data.author, &data.text, &now))?;
+        Ok(GeneratedCommentData {
+            id: self.conn.last_insert_rowid(),
+            create_time: now.into(),
+        })
+    }
+
+    /// Retrieve the comment with this id, or `None` if it doesn't exist in the database.
+    pub fn comment_by_id(&self, id: CommentId) -> DbResult<Option<Comment>> {
+        Ok(self.conn.query_row(&format!(
+            "SELECT * FROM {} WHERE id = ?1", Db::COMMENT_TABLE
+        ), (id,), Db::comment_from_row).optional()?)
+    }
+
+    /// Retrieves the comments of a task, oldest first.
+    pub fn comments_for_task(&self, task_id: TaskId) -> DbResult<Vec<Comment>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT * FROM {} WHERE task_id = ?1 ORDER BY create_time, id", Db::COMMENT_TABLE
+        )).unwrap();
+        let iter = stmt.query_map((task_id,), Db::comment_from_row)?;
+        Ok(iter.map(|comment| comment.unwrap()).collect())
+    }
+
+    /// Modifies an existing comment in the database, updating its edit time to now.
+    ///
+    /// # Failure
+    /// Returns `DbError::CommentDoesNotExistError` if the comment doesn't exist in the database.
+    pub fn modify_comment(&mut self, id: CommentId, data: &EditableCommentData) -> DbResult<ModifiedCommentData> {
+        let now = MyDateTime::now();
+        let rows = self.conn.execute(&format!(
+            "UPDATE {} SET author = ?2, text = ?3, edit_time = ?4 WHERE id = ?1;", Db::COMMENT_TABLE
+        ), (id, &data.author, &data.text, &now))?;
+        match rows {
+            0 => Err(DbError::CommentDoesNotExistError { id }),
+            1 => Ok(ModifiedCommentData {
+                edit_time: now.into(),
+            }),
+            other => panic!("Modify comment changed {} rows!", other),
+        }
+    }
+
+    /// Delete a comment by its id in the database.
+    ///
+    /// # Failure
+    /// Returns `DbError::CommentDoesNotExistError` if the comment doesn't exist in the database.
+    pub fn delete_comment(&mut self, id: CommentId) -> DbResult<()> {
+        let rows = self.conn.execute(&format!(
+            "DELETE FROM {} WHERE id = ?1;", Db::COMMENT_TABLE
+        ), (id,))?;
+        match rows {
+            0 => Err(DbError::CommentDoesNotExistError { id }),
+            1 => Ok(()),
+            other => panic!("Delete comment changed {} rows!", other),
+        }
+    }
+
+    /// The history of a task, oldest first: its comments interleaved with its creation, edits,
+    /// completions and tag changes. Tasks created before events were recorded only have events since.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    pub fn task_activity(&self, task_id: TaskId) -> DbResult<Vec<Activity>> {
+        if self.task_by_id(task_id)?.is_none() {
+            return Err(DbError::TaskDoesNotExistError { id: task_id });
+        }
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT * FROM {} WHERE task_id = ?1 ORDER BY id", Db::TASK_EVENT_TABLE
+        )).unwrap();
+        let mut activity: Vec<Activity> = stmt.query_map((task_id,), Db::event_from_row)?
+            .map(|event| event.unwrap())
+            .chain(self.comments_for_task(task_id)?.into_iter().map(|comment| Activity {
+                time: comment.create_time,
+                kind: ActivityKind::Comment { comment },
+            }))
+            .collect();
+        // stable, so events stay before comments made in the same second
+        activity.sort_by_key(|activity| activity.time);
+        Ok(activity)
+    }
+
+    /// Records that `event` happened to a task at `time`, as part of the transaction that made it happen.
+    pub(super) fn record_task_event(tx: &Connection, task_id: TaskId, time: DateTime<Utc>, event: &TaskEvent)
+                                    -> DbResult<()> {
+        let (kind, from, to) = match event {
+            TaskEvent::Created => ("created", None, None),
+            TaskEvent::Edited => ("edited", None, None),
+            TaskEvent::Finished => ("finished", None, None),
+            TaskEvent::Unfinished => ("unfinished", None, None),
+            TaskEvent::TagChanged { from, to } => ("tag_changed", *from, *to),
+        };
+        tx.execute(&format!(
+            "INSERT INTO {} (task_id, time, kind, from_tag, to_tag) values (?1, ?2, ?3, ?4, ?5);", Db::TASK_EVENT_TABLE
+        ), (task_id, MyDateTime::from(time), kind, from, to))?;
+        Ok(())
+    }
+
+    fn event_from_row(row: &Row) -> rusqlite::Result<Activity> {
+        let kind: String = row.get("kind")?;
+        let event = match kind.as_str() {
+            "created" => TaskEvent::Created,
+            "edited" => TaskEvent::Edited,
+            "finished" => TaskEvent::Finished,
+            "unfinished" => TaskEvent::Unfinished,
+            "tag_changed" => TaskEvent::TagChanged {
+                from: row.get("from_tag")?,
+                to: row.get("to_tag")?,
+            },
+            other => return Err(rusqlite::Error::FromSqlConversionFailure(
+                3, Type::Text, format!("Unknown task event {other}").into(),
+            )),
+        };
+        Ok(Activity {
+            time: row.get::<_, MyDateTime>("time")?.into(),
+            kind: ActivityKind::Event { event },
+        })
+    }
+
+    fn comment_from_row(row: &Row) -> rusqlite::Result<Comment> {
+        Ok(Comment {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            author: row.get("author")?,
+            text: row.get("text")?,
+            create_time: row.get::<_, MyDateTime>("create_time")?.into(),
+            edit_time: row.get::<_, Option<MyDateTime>>("edit_time")?.map(DateTime::from),
+        })
+    }
+}
@@ -0,0 +1,185 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use rusqlite::{OptionalExtension, Row};
+use crate::{Attachment, AttachmentId, Db, DbError, DbResult, MyDateTime, TaskId, content_hash, guess_mime};
+
+impl From<io::Error> for DbError {
+    fn from(value: io::Error) -> Self {
+        DbError::AttachmentFileError { message: value.to_string() }
+    }
+}
+
+impl Db {
+    /// Attaches a copy of the file at `source` to a task. The contents are stored in the attachment
+    /// directory next to the database under their hash, so attaching identical files stores them once.
+    ///
+    /// Returns the new attachment.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    /// Returns `DbError::AttachmentFileError` if the file can't be read or stored.
+    pub fn add_attachment(&mut self, task_id: TaskId, source: &Path) -> DbResult<Attachment> {
+        if self.task_by_id(task_id)?.is_none() {
+            return Err(DbError::TaskDoesNotExistError { id: task_id });
+        }
+        let contents = fs::read(source)?;
+        let hash = content_hash(&contents);
+        let blob = self.attachment_blob_path(&hash);
+        if !blob.exists() {
+            fs::create_dir_all(&self.attachment_directory)?;
+            // written whole before it appears under its hash
+            let partial = blob.with_extension("partial");
+            fs::write(&partial, &contents)?;
+            fs::rename(&partial, &blob)?;
+        }
+        let name = source.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let mime = guess_mime(&name);
+        let now = MyDateTime::now();
+        self.conn.execute(&format!(r#"
+            INSERT INTO {} (task_id, name, size, mime, hash, create_time) values (?1, ?2, ?3, ?4, ?5, ?6);
+        "#, Db::ATTACHMENT_TABLE), (task_id, &name, contents.len() as i64, mime, &hash, &now))?;
+        Ok(Attachment {
+            id: self.conn.last_insert_rowid(),
+            task_id,
+            name,
+            size: contents.len() as i64,
+            mime: String::from(mime),
+            hash,
+            create_time: now.into(),
+        })
+    }
+
+    /// Retrieve the attachment with this id, or `None` if it doesn't exist in the database.
+    pub fn attachment_by_id(&self, id: AttachmentId) -> DbResult<Option<Attachment>> {
+        Ok(self.conn.query_row(&format!(
+            "SELECT * FROM {} WHERE id = ?1", Db::ATTACHMENT_TABLE
+        ), (id,), Db::attachment_from_row).optional()?)
+    }
+
+    /// Retrieves the attachments of a task, in order of insertion.
+    pub fn attachments_for_task(&self, task_id: TaskId) -> DbResult<Vec<Attachment>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT * FROM {} WHERE task_id = ?1 ORDER BY id", Db::ATTACHMENT_TABLE
+        )).unwrap();
+        let iter = stmt.query_map((task_id,), Db::attachment_from_row)?;
+        Ok(iter.map(|attachment| attachment.unwrap()).collect())
+    }
+
+    /// The path of the stored contents of an attachment, to be opened read-only.
+    ///
+    /// # Failure
+    /// Returns `DbError::AttachmentDoesNotExistError` if the attachment doesn't exist in the database.
+    pub fn attachment_path(&self, id: AttachmentId) -> DbResult<PathBuf> {
+        let attachment = self.attachment_by_id(id)?.ok_or(DbError::AttachmentDoesNotExistError { id })?;
+        Ok(self.attachment_blob_path(&attachment.hash))
+    }
+
+    /// Delete an attachment by its id in the database, along with its stored contents
+    /// if no other attachment has them.
+    ///
+    /// # Failure
+    /// Returns `DbError::AttachmentDoesNotExistError` if the attachment doesn't exist in the database.
+    pub fn delete_attachment(&mut self, id: AttachmentId) -> DbResult<()> {
+        let rows = self.conn.execute(&format!(
+            "DELETE FROM {} WHERE id = ?1;", Db::ATTACHMENT_TABLE
+        ), (id,))?;
+        if rows == 0 {
+            return Err(DbError::AttachmentDoesNotExistError { id });
+        }
+        // the attachment is deleted either way; see collect_attachment_garbage
+        _ = self.collect_attachment_garbage();
+        Ok(())
+    }
+
+    /// Copies the database and the stored contents of every attachment into `directory`,
+    /// laid out the same way as the originals so the copy can be opened with `Db::connect`.
+    /// Each file is written under a temporary name and then renamed over any earlier backup.
+    ///
+    /// # Failure
+    /// Returns `DbError::BackupDirectoryError` if `directory` is the directory of the database,
+    /// or the attachment directory or inside it.
+    /// Returns `DbError::AttachmentFileError` if the files can't be copied.
+    pub fn backup(&self, directory: &Path) -> DbResult<()> {
+        let database_path = self.conn.path().filter(|path| !path.is_empty()).map(Path::new);
+        let name = database_path
+            .and_then(|path| path.file_name())
+            .unwrap_or_else(|| "db.sqlite".as_ref());
+        fs::create_dir_all(directory)?;
+        let directory = directory.canonicalize()?;
+        let database_directory = match database_path {
+            Some(path) => Some(path.canonicalize()?.parent().map(Path::to_path_buf).unwrap_or_default()),
+            None => None,
+        };
+        let own_attachment_directory = self.attachment_directory.canonicalize().ok();
+        if database_directory.as_ref() == Some(&directory)
+            || own_attachment_directory.map_or(false, |own| directory.starts_with(own)) {
+            return Err(DbError::BackupDirectoryError);
+        }
+
+        let database_file = directory.join(name);
+        let partial = database_file.with_extension("partial");
+        if partial.exists() {
+            fs::remove_file(&partial)?;
+        }
+        self.conn.execute("VACUUM INTO ?1", (partial.to_string_lossy(),))?;
+        fs::rename(&partial, &database_file)?;
+        let attachment_directory = directory.join(Db::ATTACHMENT_DIRECTORY_NAME);
+        fs::create_dir_all(&attachment_directory)?;
+        for hash in self.attachment_hashes()? {
+            let blob = attachment_directory.join(&hash);
+            let partial = blob.with_extension("partial");
+            fs::copy(self.attachment_blob_path(&hash), &partial)?;
+            fs::rename(&partial, &blob)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the stored contents that no attachment has any more.
+    /// Deleting attachments and tasks does so once the deletions are committed, but leaves the files
+    /// for a later collection if that fails, so this can be run again to retry and report the failure.
+    ///
+    /// # Failure
+    /// Returns `DbError::AttachmentFileError` if the attachment directory can't be read
+    /// or a file in it removed.
+    pub fn collect_attachment_garbage(&self) -> DbResult<()> {
+        let entries = match fs::read_dir(&self.attachment_directory) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(error.into()),
+        };
+        let hashes = self.attachment_hashes()?;
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if entry.file_type()?.is_file() && !hashes.contains(&name) {
+                fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn attachment_hashes(&self) -> DbResult<Vec<String>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT DISTINCT hash FROM {}", Db::ATTACHMENT_TABLE
+        )).unwrap();
+        let iter = stmt.query_map([], |row| row.get(0))?;
+        Ok(iter.map(|hash| hash.unwrap()).collect())
+    }
+
+    fn attachment_blob_path(&self, hash: &str) -> PathBuf {
+        self.attachment_directory.join(hash)
+    }
+
+    fn attachment_from_row(row: &Row) -> rusqlite::Result<Attachment> {
+        Ok(Attachment {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            name: row.get("name")?,
+            size: row.get("size")?,
+            mime: row.get("mime")?,
+            hash: row.get("hash")?,
+            create_time: row.get::<_, MyDateTime>("create_time")?.into(),
+        })
+    }
+}
@@ -0,0 +1,320 @@
+use rusqlite::{Connection, OptionalExtension, Row};
+use crate::db::savepoint::DbSavepoint;
+use crate::{Board, BoardColumn, BoardId, BoardView, ColumnCards, ColumnId, Db, DbError, DbResult, EditableBoardData, EditableColumnData, FinishedTaskData, GeneratedBoardData, GeneratedColumnData, Task, TaskId};
+
+impl Db {
+    /// Add a new board without columns to the database. Returns the fields generated for this board.
+    pub fn add_new_board(&mut self, data: &EditableBoardData) -> DbResult<GeneratedBoardData> {
+        self.conn.execute(&format!(
+            "INSERT INTO {} (name) values (?1);", Db::BOARD_TABLE
+        ), (&data.name,))?;
+        Ok(GeneratedBoardData {
+            id: self.conn.last_insert_rowid(),
+        })
+    }
+
+    /// Retrieves all boards stored in this database in order of insertion.
+    pub fn all_boards(&self) -> DbResult<Vec<Board>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT * FROM {} ORDER BY id", Db::BOARD_TABLE
+        )).unwrap();
+        let iter = stmt.query_map([], Db::board_from_row)?;
+        Ok(iter.map(|board| board.unwrap()).collect())
+    }
+
+    /// Retrieve the board with this id, or `None` if the board doesn't exist in the database.
+    pub fn board_by_id(&self, id: BoardId) -> DbResult<Option<Board>> {
+        Ok(self.conn.query_row(&format!(
+            "SELECT * FROM {} WHERE id = ?1", Db::BOARD_TABLE
+        ), (id,), Db::board_from_row).optional()?)
+    }
+
+    /// Modifies an existing board in the database.
+    ///
+    /// # Failure
+    /// Returns `DbError::BoardDoesNotExistError` if the board doesn't exist in the database.
+    pub fn modify_board(&mut self, id: BoardId, data: &EditableBoardData) -> DbResult<()> {
+        let rows = self.conn.execute(&format!(
+            "UPDATE {} SET name = ?2 WHERE id = ?1;", Db::BOARD_TABLE
+        ), (id, &data.name))?;
+        match rows {
+            0 => Err(DbError::BoardDoesNotExistError { id }),
+            1 => Ok(()),
+            other => panic!("Modify board changed {} rows!", other),
+        }
+    }
+
+    /// Delete a board by its id in the database, along with its columns.
+    /// Its tasks are kept, no longer on any board.
+    ///
+    /// # Failure
+    /// Returns `DbError::BoardDoesNotExistError` if the board doesn't exist in the database.
+    pub fn delete_board(&mut self, id: BoardId) -> DbResult<()> {
+        let tx = self.conn.savepoint()?;
+        let rows = tx.execute(&format!(
+            "DELETE FROM {} WHERE id = ?1;", Db::BOARD_TABLE
+        ), (id,))?;
+        if rows == 0 {
+            return Err(DbError::BoardDoesNotExistError { id });
+        } else if rows > 1 {
+            panic!("Delete board changed {} rows!", rows);
+        }
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE board_id = ?1;", Db::BOARD_CARD_TABLE
+        ), (id,))?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE board_id = ?1;", Db::BOARD_COLUMN_TABLE
+        ), (id,))?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Add a new column at the end of a board. Returns the fields generated for this column.
+    ///
+    /// # Failure
+    /// Returns `DbError::BoardDoesNotExistError` if the board doesn't exist in the database.
+    pub fn add_column(&mut self, board_id: BoardId, data: &EditableColumnData) -> DbResult<GeneratedColumnData> {
+        if self.board_by_id(board_id)?.is_none() {
+            return Err(DbError::BoardDoesNotExistError { id: board_id });
+        }
+        self.conn.execute(&format!(r#"
+            INSERT INTO {0} (board_id, name, wip_limit, terminal, position) values
+            (?1, ?2, ?3, ?4, (SELECT count(*) FROM {0} WHERE board_id = ?1));
+        "#, Db::BOARD_COLUMN_TABLE), (board_id, &data.name, data.wip_limit, data.terminal))?;
+        Ok(GeneratedColumnData {
+            id: self.conn.last_insert_rowid(),
+        })
+    }
+
+    /// Retrieve the column with this id, or `None` if the column doesn't exist in the database.
+    pub fn column_by_id(&self, id: ColumnId) -> DbResult<Option<BoardColumn>> {
+        Ok(self.conn.query_row(&format!(
+            "SELECT * FROM {} WHERE id = ?1", Db::BOARD_COLUMN_TABLE
+        ), (id,), Db::column_from_row).optional()?)
+    }
+
+    /// Modifies an existing column in the database. Tasks already in the column are not moved,
+    /// even if it now holds more than its WIP limit or became terminal.
+    ///
+    /// # Failure
+    /// Returns `DbError::ColumnDoesNotExistError` if the column doesn't exist in the database.
+    pub fn modify_column(&mut self, id: ColumnId, data: &EditableColumnData) -> DbResult<()> {
+        let rows = self.conn.execute(&format!(
+            "UPDATE {} SET name = ?2, wip_limit = ?3, terminal = ?4 WHERE id = ?1;", Db::BOARD_COLUMN_TABLE
+        ), (id, &data.name, data.wip_limit, data.terminal))?;
+        match rows {
+            0 => Err(DbError::ColumnDoesNotExistError { id }),
+            1 => Ok(()),
+            other => panic!("Modify column changed {} rows!", other),
+        }
+    }
+
+    /// Moves a column to `position` in the order of its board, or to the end if past the end.
+    ///
+    /// # Failure
+    /// Returns `DbError::ColumnDoesNotExistError` if the column doesn't exist in the database.
+    pub fn move_column(&mut self, id: ColumnId, position: usize) -> DbResult<()> {
+        let column = self.column_by_id(id)?.ok_or(DbError::ColumnDoesNotExistError { id })?;
+        let mut column_ids: Vec<ColumnId> = self.board_columns(column.board_id)?.iter()
+            .map(|column| column.id)
+            .filter(|column_id| *column_id != id)
+            .collect();
+        column_ids.insert(position.min(column_ids.len()), id);
+        let tx = self.conn.savepoint()?;
+        Db::set_column_positions(&tx, &column_ids)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Delete a column by its id in the database. Its tasks are kept, no longer on the board.
+    ///
+    /// # Failure
+    /// Returns `DbError::ColumnDoesNotExistError` if the column doesn't exist in the database.
+    pub fn delete_column(&mut self, id: ColumnId) -> DbResult<()> {
+        let column = self.column_by_id(id)?.ok_or(DbError::ColumnDoesNotExistError { id })?;
+        let column_ids: Vec<ColumnId> = self.board_columns(column.board_id)?.iter()
+            .map(|column| column.id)
+            .filter(|column_id| *column_id != id)
+            .collect();
+        let tx = self.conn.savepoint()?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE id = ?1;", Db::BOARD_COLUMN_TABLE
+        ), (id,))?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE column_id = ?1;", Db::BOARD_CARD_TABLE
+        ), (id,))?;
+        Db::set_column_positions(&tx, &column_ids)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Retrieves a board with its columns in order, each with its tasks in order.
+    ///
+    /// # Failure
+    /// Returns `DbError::BoardDoesNotExistError` if the board doesn't exist in the database.
+    pub fn board_view(&self, id: BoardId) -> DbResult<BoardView> {
+        let board = self.board_by_id(id)?.ok_or(DbError::BoardDoesNotExistError { id })?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {0}.* FROM {0} JOIN {1} ON id = task_id WHERE column_id = ?1 ORDER BY position",
+            Db::TASK_TABLE, Db::BOARD_CARD_TABLE
+        )).unwrap();
+        let mut columns = vec![];
+        for column in self.board_columns(id)? {
+            let tasks: Vec<Task> = stmt.query_map((column.id,), |row| self.task_from_row(row))?
+                .map(|task| task.unwrap())
+                .collect();
+            columns.push(ColumnCards { column, tasks });
+        }
+        Ok(BoardView { board, columns })
+    }
+
+    /// The column the task with `id` is in on a board, or `None` if it is not on that board.
+    pub fn task_column(&self, board_id: BoardId, id: TaskId) -> DbResult<Option<BoardColumn>> {
+        Ok(self.conn.query_row(&format!(
+            "SELECT {0}.* FROM {0} JOIN {1} ON id = column_id WHERE {1}.board_id = ?1 AND task_id = ?2",
+            Db::BOARD_COLUMN_TABLE, Db::BOARD_CARD_TABLE
+        ), (board_id, id), Db::column_from_row).optional()?)
+    }
+
+    /// The columns the task with `id` is in, one for each board it is on, in order of board.
+    pub fn task_columns(&self, id: TaskId) -> DbResult<Vec<BoardColumn>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {0}.* FROM {0} JOIN {1} ON id = column_id WHERE task_id = ?1 ORDER BY {1}.board_id",
+            Db::BOARD_COLUMN_TABLE, Db::BOARD_CARD_TABLE
+        )).unwrap();
+        let iter = stmt.query_map((id,), Db::column_from_row)?;
+        Ok(iter.map(|column| column.unwrap()).collect())
+    }
+
+    /// Moves a task to `position` in a column, or to the end if `position` is `None` or past the end,
+    /// taking it out of the column it was in on the same board. This may be the same column to reorder it.
+    /// A task can be on any number of boards, in one column of each.
+    ///
+    /// A task moved into a terminal column is marked as done (see `finish_task`),
+    /// and a done task moved into any other column is marked as not done.
+    /// The done status and the cards change together, or not at all if the move fails.
+    /// Returns the done time of the task after the move.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    /// Returns `DbError::ColumnDoesNotExistError` if the column doesn't exist in the database.
+    /// Returns `DbError::WipLimitError` if moving into another column that already holds its WIP limit.
+    /// Otherwise fails the same way as `finish_task` or `unfinish_task` when changing the done status.
+    pub fn move_card(&mut self, task_id: TaskId, column_id: ColumnId, position: Option<usize>)
+                     -> DbResult<FinishedTaskData> {
+        let task = self.task_by_id(task_id)?.ok_or(DbError::TaskDoesNotExistError { id: task_id })?;
+        let column = self.column_by_id(column_id)?.ok_or(DbError::ColumnDoesNotExistError { id: column_id })?;
+        let old_column = self.task_column(column.board_id, task_id)?;
+        let mut task_ids = self.column_task_ids(column_id)?;
+        task_ids.retain(|id| *id != task_id);
+        if let Some(limit) = column.wip_limit {
+            let same_column = matches!(&old_column, Some(old_column) if old_column.id == column_id);
+            if !same_column && task_ids.len() >= limit as usize {
+                return Err(DbError::WipLimitError { id: column_id, limit });
+            }
+        }
+        task_ids.insert(position.unwrap_or(task_ids.len()).min(task_ids.len()), task_id);
+        let old_task_ids = match &old_column {
+            Some(old_column) if old_column.id != column_id => {
+                let mut old_task_ids = self.column_task_ids(old_column.id)?;
+                old_task_ids.retain(|id| *id != task_id);
+                Some((old_column.id, old_task_ids))
+            },
+            _ => None,
+        };
+
+        let mut savepoint = DbSavepoint::new(self, "move_card")?;
+        let done_time = if column.terminal && !task.is_done() {
+            savepoint.finish_task(task_id)?.done_time
+        } else if !column.terminal && task.is_done() {
+            savepoint.unfinish_task(task_id)?.done_time
+        } else {
+            task.done_time
+        };
+        if let Some((old_column_id, old_task_ids)) = old_task_ids {
+            Db::set_column_cards(&savepoint.conn, column.board_id, old_column_id, &old_task_ids)?;
+        }
+        Db::set_column_cards(&savepoint.conn, column.board_id, column_id, &task_ids)?;
+        savepoint.commit()?;
+        Ok(FinishedTaskData {
+            done_time,
+        })
+    }
+
+    /// Takes a task off a board, if it is on it. Its done status is kept.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    pub fn remove_card(&mut self, board_id: BoardId, task_id: TaskId) -> DbResult<()> {
+        if self.task_by_id(task_id)?.is_none() {
+            return Err(DbError::TaskDoesNotExistError { id: task_id });
+        }
+        let column = match self.task_column(board_id, task_id)? {
+            Some(column) => column,
+            None => return Ok(()),
+        };
+        let mut task_ids = self.column_task_ids(column.id)?;
+        task_ids.retain(|id| *id != task_id);
+        let tx = self.conn.savepoint()?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE board_id = ?1 AND task_id = ?2;", Db::BOARD_CARD_TABLE
+        ), (board_id, task_id))?;
+        Db::set_column_cards(&tx, board_id, column.id, &task_ids)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn board_columns(&self, board_id: BoardId) -> DbResult<Vec<BoardColumn>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT * FROM {} WHERE board_id = ?1 ORDER BY position", Db::BOARD_COLUMN_TABLE
+        )).unwrap();
+        let iter = stmt.query_map((board_id,), Db::column_from_row)?;
+        Ok(iter.map(|column| column.unwrap()).collect())
+    }
+
+    fn column_task_ids(&self, column_id: ColumnId) -> DbResult<Vec<TaskId>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT task_id FROM {} WHERE column_id = ?1 ORDER BY position", Db::BOARD_CARD_TABLE
+        )).unwrap();
+        let iter = stmt.query_map((column_id,), |row| row.get(0))?;
+        Ok(iter.map(|id| id.unwrap()).collect())
+    }
+
+    /// Puts the columns of a board in the order of `column_ids`.
+    fn set_column_positions(tx: &Connection, column_ids: &[ColumnId]) -> DbResult<()> {
+        for (position, column_id) in column_ids.iter().enumerate() {
+            tx.execute(&format!(
+                "UPDATE {} SET position = ?2 WHERE id = ?1;", Db::BOARD_COLUMN_TABLE
+            ), (column_id, position))?;
+        }
+        Ok(())
+    }
+
+    /// Puts `task_ids` in a column of a board in this order, renumbering their positions.
+    fn set_column_cards(tx: &Connection, board_id: BoardId, column_id: ColumnId, task_ids: &[TaskId]) -> DbResult<()> {
+        for (position, task_id) in task_ids.iter().enumerate() {
+            tx.execute(&format!(r#"
+                INSERT INTO {} (board_id, task_id, column_id, position) values (?1, ?2, ?3, ?4)
+                ON CONFLICT (board_id, task_id) DO UPDATE SET column_id = excluded.column_id, position = excluded.position;
+            "#, Db::BOARD_CARD_TABLE), (board_id, task_id, column_id, position))?;
+        }
+        Ok(())
+    }
+
+    fn board_from_row(row: &Row) -> rusqlite::Result<Board> {
+        Ok(Board {
+            id: row.get("id")?,
+            name: row.get("name")?,
+        })
+    }
+
+    fn column_from_row(row: &Row) -> rusqlite::Result<BoardColumn> {
+        Ok(BoardColumn {
+            id: row.get("id")?,
+            board_id: row.get("board_id")?,
+            name: row.get("name")?,
+            wip_limit: row.get("wip_limit")?,
+            terminal: row.get("terminal")?,
+        })
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{ApplyFilter, BulkAction, BulkItemResult, BulkOutcome, BulkTarget, Db, DbError, DbResult, TaskId, checked_seconds};
+use crate::db::savepoint::DbSavepoint;
+
+impl Db {
+    /// Applies an action to each of the target tasks, all in one transaction.
+    /// The action failing for a task leaves that task as it was and moves on to the next one,
+    /// unless `rollback_on_failure`, in which case nothing is changed if it fails for any of them.
+    ///
+    /// Returns the result for each task, in order. An id appearing more than once is acted on once.
+    ///
+    /// # Failure
+    /// Fails only if the transaction itself can't be made; failures for a task are in its result.
+    /// Shifting a due time out of the range of times fails with `DbError::DurationOutOfRangeError`,
+    /// and shifting an all-day due date by other than whole days with `DbError::AllDayShiftError`.
+    pub fn bulk_update(&mut self, target: &BulkTarget, action: &BulkAction, rollback_on_failure: bool)
+                       -> DbResult<BulkOutcome> {
+        let ids = match target {
+            BulkTarget::Ids { ids } => {
+                let mut distinct: Vec<TaskId> = vec![];
+                for id in ids {
+                    if !distinct.contains(id) {
+                        distinct.push(*id);
+                    }
+                }
+                distinct
+            }
+            BulkTarget::Filter { filter } => self.filter_tasks(|task| filter.passes(task))?
+                .into_iter()
+                .map(|task| task.id)
+                .collect(),
+        };
+
+        // the single-task operations each run in a savepoint, which nests inside this one
+        let mut savepoint = DbSavepoint::new(self, "bulk_update")?;
+        let results: Vec<BulkItemResult> = ids.into_iter()
+            .map(|id| BulkItemResult { id, result: savepoint.apply_bulk_action(id, action) })
+            .collect();
+        let mut outcome = BulkOutcome { results, rolled_back: false };
+        if rollback_on_failure && outcome.has_failures() {
+            savepoint.rollback()?;
+            outcome.rolled_back = true;
+        } else {
+            savepoint.commit()?;
+        }
+
+        // stored attachment contents are only removed once the deletions can't be rolled back
+        if *action == BulkAction::Delete && !outcome.rolled_back {
+            // the tasks are deleted either way; see collect_attachment_garbage
+            _ = self.collect_attachment_garbage();
+        }
+        Ok(outcome)
+    }
+
+    fn apply_bulk_action(&mut self, id: TaskId, action: &BulkAction) -> DbResult<()> {
+        match action {
+            BulkAction::Finish => return self.finish_task(id).map(|_| ()),
+            BulkAction::Unfinish => return self.unfinish_task(id).map(|_| ()),
+            BulkAction::Delete => {
+                let tx = self.conn.savepoint()?;
+                Db::delete_task_rows(&tx, id)?;
+                tx.commit()?;
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let task = self.task_by_id(id)?.ok_or(DbError::TaskDoesNotExistError { id })?;
+        let mut data = Db::editable_task_data(&task);
+        match action {
+            BulkAction::Pause => data.paused = true,
+            BulkAction::Unpause => data.paused = false,
+            BulkAction::Retag { tag } => data.tag = *tag,
+            BulkAction::ShiftDue { seconds } => match data.due_time {
+                Some(_) if data.due_all_day && seconds % (24 * 60 * 60) != 0 =>
+                    return Err(DbError::AllDayShiftError { id }),
+                Some(due_time) => data.due_time = Some(checked_seconds(*seconds)
+                    .and_then(|shift| due_time.checked_add_signed(shift))
+                    .ok_or(DbError::DurationOutOfRangeError)?),
+                None => return Ok(()),
+            },
+            BulkAction::SetDifficulty { difficulty } => data.difficulty = *difficulty,
+            BulkAction::Finish | BulkAction::Unfinish | BulkAction::Delete => unreachable!(),
+        }
+        self.modify_task(id, &data).map(|_| ())
+    }
+}
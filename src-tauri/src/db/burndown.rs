@@ -0,0 +1,91 @@
+use chrono::NaiveDate;
+use crate::{BurndownForecast, BurndownPoint, BurndownReport, Db, DbError, DbResult, StatsPeriod, TaskFilterOptions, VelocityPoint, local_date};
+
+impl Db {
+    /// Builds the burndown, burnup and velocity series from `first` to `last` inclusive of the tasks
+    /// that pass `filter`, with velocity grouped by `period`. Days are in the timezone of the user.
+    /// Deferred tasks and tasks of archived projects count unless filtered out explicitly.
+    ///
+    /// The forecast is based on the average velocity over the periods of the range.
+    ///
+    /// # Failure
+    /// Returns `DbError::InvalidDateRangeError` if `first` is after `last`, or the range is longer than `MAX_RANGE_DAYS`.
+    /// Returns `DbError::DateOutOfRangeError` if the range reaches the ends of the range of dates.
+    pub fn burndown_report(&self, filter: &TaskFilterOptions, period: StatsPeriod, first: NaiveDate, last: NaiveDate)
+        -> DbResult<BurndownReport> {
+        let end = Db::check_date_range(first, last)?;
+        let ranges = period.ranges(first, last).ok_or(DbError::DateOutOfRangeError)?;
+        let timezone = self.user_timezone()?;
+        // the days each task was created and completed, with its difficulty and estimate
+        let tasks: Vec<(NaiveDate, Option<NaiveDate>, i64, i64)> = self.filter_tasks(|task| filter.passes_including_hidden(task))?
+            .iter()
+            .map(|task| (
+                local_date(&timezone, &task.create_time),
+                task.done_time.map(|time| local_date(&timezone, &time)),
+                task.difficulty as i64,
+                task.estimate_minutes.unwrap_or_default(),
+            ))
+            .collect();
+        let difficulty_by = |date: NaiveDate| {
+            let total = tasks.iter().filter(|(created, _, _, _)| *created <= date)
+                .map(|(_, _, difficulty, _)| difficulty).sum::<i64>();
+            let completed = tasks.iter().filter(|(_, done, _, _)| matches!(done, Some(done) if *done <= date))
+                .map(|(_, _, difficulty, _)| difficulty).sum::<i64>();
+            (total, completed)
+        };
+        let remaining_estimate_by = |date: NaiveDate| tasks.iter()
+            .filter(|(created, done, _, _)| *created <= date && !matches!(done, Some(done) if *done <= date))
+            .map(|(_, _, _, estimate)| estimate)
+            .fold(0i64, |total, estimate| total.saturating_add(*estimate));
+
+        let mut burndown = vec![];
+        let mut date = first;
+        while date < end {
+            let (total, completed) = difficulty_by(date);
+            burndown.push(BurndownPoint {
+                date,
+                total,
+                completed,
+                remaining: total - completed,
+                remaining_estimate_minutes: remaining_estimate_by(date),
+            });
+            // end has a previous day, so every date before it has a next one
+            date = date.succ_opt().unwrap();
+        }
+
+        let velocity: Vec<VelocityPoint> = ranges.into_iter()
+            .map(|(start, from, to)| VelocityPoint {
+                start,
+                completed: tasks.iter()
+                    .filter(|(_, done, _, _)| matches!(done, Some(done) if from <= *done && *done < to))
+                    .map(|(_, _, difficulty, _)| difficulty)
+                    .sum(),
+            })
+            .collect();
+        let average = if velocity.is_empty() {
+            0.0
+        } else {
+            velocity.iter().map(|point| point.completed).sum::<i64>() as f64 / velocity.len() as f64
+        };
+        let (total, completed) = difficulty_by(last);
+        let remaining = total - completed;
+        let completion_date = if remaining <= 0 {
+            Some(last)
+        } else if average > 0.0 {
+            // a forecast too far ahead for a date is no forecast
+            period.advance(last, (remaining as f64 / average).ceil() as u32)
+        } else {
+            None
+        };
+
+        Ok(BurndownReport {
+            burndown,
+            velocity,
+            forecast: BurndownForecast {
+                velocity: average,
+                remaining,
+                completion_date,
+            },
+        })
+    }
+}
@@ -0,0 +1,149 @@
+use rusqlite::{Connection, OptionalExtension, Row};
+use crate::{ChecklistItem, ChecklistItemId, ChecklistProgress, Db, DbError, DbResult, EditableChecklistItemData, GeneratedChecklistItemData, MyDateTime, TaskId, extract_checklist};
+
+impl Db {
+    const REQUIRE_COMPLETE_CHECKLIST_SETTING: &'static str = "require_complete_checklist";
+
+    /// Add a new item at the end of the checklist of a task. Returns the fields generated for this item.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    pub fn add_checklist_item(&mut self, task_id: TaskId, data: &EditableChecklistItemData)
+                              -> DbResult<GeneratedChecklistItemData> {
+        if self.task_by_id(task_id)?.is_none() {
+            return Err(DbError::TaskDoesNotExistError { id: task_id });
+        }
+        Db::insert_checklist_item(&self.conn, task_id, data)
+    }
+
+    /// Retrieve the checklist item with this id, or `None` if it doesn't exist in the database.
+    pub fn checklist_item_by_id(&self, id: ChecklistItemId) -> DbResult<Option<ChecklistItem>> {
+        Ok(self.conn.query_row(&format!(
+            "SELECT * FROM {} WHERE id = ?1", Db::CHECKLIST_TABLE
+        ), (id,), Db::checklist_item_from_row).optional()?)
+    }
+
+    /// Retrieves the checklist of a task in order.
+    pub fn checklist_items(&self, task_id: TaskId) -> DbResult<Vec<ChecklistItem>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT * FROM {} WHERE task_id = ?1 ORDER BY position", Db::CHECKLIST_TABLE
+        )).unwrap();
+        let iter = stmt.query_map((task_id,), Db::checklist_item_from_row)?;
+        Ok(iter.map(|item| item.unwrap()).collect())
+    }
+
+    /// Modifies an existing checklist item in the database, such as to check it.
+    ///
+    /// # Failure
+    /// Returns `DbError::ChecklistItemDoesNotExistError` if the item doesn't exist in the database.
+    pub fn modify_checklist_item(&mut self, id: ChecklistItemId, data: &EditableChecklistItemData) -> DbResult<()> {
+        let rows = self.conn.execute(&format!(
+            "UPDATE {} SET text = ?2, checked = ?3 WHERE id = ?1;", Db::CHECKLIST_TABLE
+        ), (id, &data.text, data.checked))?;
+        match rows {
+            0 => Err(DbError::ChecklistItemDoesNotExistError { id }),
+            1 => Ok(()),
+            other => panic!("Modify checklist item changed {} rows!", other),
+        }
+    }
+
+    /// Moves a checklist item to `position` in the checklist of its task, or to the end if past the end.
+    ///
+    /// # Failure
+    /// Returns `DbError::ChecklistItemDoesNotExistError` if the item doesn't exist in the database.
+    pub fn move_checklist_item(&mut self, id: ChecklistItemId, position: usize) -> DbResult<()> {
+        let item = self.checklist_item_by_id(id)?.ok_or(DbError::ChecklistItemDoesNotExistError { id })?;
+        let mut item_ids: Vec<ChecklistItemId> = self.checklist_items(item.task_id)?.iter()
+            .map(|item| item.id)
+            .filter(|item_id| *item_id != id)
+            .collect();
+        item_ids.insert(position.min(item_ids.len()), id);
+        self.set_checklist_positions(&item_ids)
+    }
+
+    /// Delete a checklist item by its id in the database.
+    ///
+    /// # Failure
+    /// Returns `DbError::ChecklistItemDoesNotExistError` if the item doesn't exist in the database.
+    pub fn delete_checklist_item(&mut self, id: ChecklistItemId) -> DbResult<()> {
+        let item = self.checklist_item_by_id(id)?.ok_or(DbError::ChecklistItemDoesNotExistError { id })?;
+        self.conn.execute(&format!(
+            "DELETE FROM {} WHERE id = ?1;", Db::CHECKLIST_TABLE
+        ), (id,))?;
+        let item_ids: Vec<ChecklistItemId> = self.checklist_items(item.task_id)?.iter().map(|item| item.id).collect();
+        self.set_checklist_positions(&item_ids)
+    }
+
+    /// How many of the checklist items of a task are checked.
+    pub fn checklist_progress(&self, task_id: TaskId) -> DbResult<ChecklistProgress> {
+        Ok(self.conn.query_row(&format!(
+            "SELECT coalesce(sum(checked), 0), count(*) FROM {} WHERE task_id = ?1", Db::CHECKLIST_TABLE
+        ), (task_id,), |row| Ok(ChecklistProgress { checked: row.get(0)?, total: row.get(1)? }))?)
+    }
+
+    /// Moves the markdown task list lines of the body of a task into its checklist
+    /// (see `extract_checklist`), after any items it already has. If the body changed, it is
+    /// recorded as an edit and the links of the task follow the new body.
+    ///
+    /// Returns the new items.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    pub fn convert_body_checklist(&mut self, task_id: TaskId) -> DbResult<Vec<ChecklistItem>> {
+        let task = self.task_by_id(task_id)?.ok_or(DbError::TaskDoesNotExistError { id: task_id })?;
+        let (body, items) = extract_checklist(&task.body);
+        if items.is_empty() {
+            return Ok(vec![]);
+        }
+        let tx = self.conn.savepoint()?;
+        let mut added = vec![];
+        for item in &items {
+            let generated = Db::insert_checklist_item(&tx, task_id, item)?;
+            added.push(ChecklistItem::from_parts(task_id, item, &generated));
+        }
+        Db::set_task_body(&tx, task_id, &body, &MyDateTime::now())?;
+        tx.commit()?;
+        Ok(added)
+    }
+
+    /// Whether `finish_task` refuses to finish a task while items of its checklist are unchecked.
+    pub fn require_complete_checklist(&self) -> DbResult<bool> {
+        Ok(self.parsed_setting(Db::REQUIRE_COMPLETE_CHECKLIST_SETTING)?.unwrap_or(false))
+    }
+
+    /// Sets whether `finish_task` refuses to finish a task while items of its checklist are unchecked.
+    pub fn set_require_complete_checklist(&mut self, require: bool) -> DbResult<()> {
+        self.set_setting(Db::REQUIRE_COMPLETE_CHECKLIST_SETTING, &require.to_string())
+    }
+
+    fn insert_checklist_item(tx: &Connection, task_id: TaskId, data: &EditableChecklistItemData)
+                             -> DbResult<GeneratedChecklistItemData> {
+        tx.execute(&format!(r#"
+            INSERT INTO {0} (task_id, text, checked, position) values
+            (?1, ?2, ?3, (SELECT count(*) FROM {0} WHERE task_id = ?1));
+        "#, Db::CHECKLIST_TABLE), (task_id, &data.text, data.checked))?;
+        Ok(GeneratedChecklistItemData {
+            id: tx.last_insert_rowid(),
+        })
+    }
+
+    fn set_checklist_positions(&mut self, item_ids: &[ChecklistItemId]) -> DbResult<()> {
+        let tx = self.conn.savepoint()?;
+        for (position, item_id) in item_ids.iter().enumerate() {
+            tx.execute(&format!(
+                "UPDATE {} SET position = ?2 WHERE id = ?1;", Db::CHECKLIST_TABLE
+            ), (item_id, position))?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn checklist_item_from_row(row: &Row) -> rusqlite::Result<ChecklistItem> {
+        Ok(ChecklistItem {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            text: row.get("text")?,
+            checked: row.get("checked")?,
+        })
+    }
+}
@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, Utc};
+use crate::{Db, DbError, DbResult, EstimateComparison, EstimateTotals, TagEstimateTotals, Task, TaskEstimate, TaskId};
+
+impl Db {
+    /// Sets how long a task is expected to take and how much of that is left,
+    /// or clears them with `None`. Its last edit time is unchanged.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    /// Returns `DbError::InvalidEstimateError` if the estimate is not valid (see `TaskEstimate::is_valid`).
+    pub fn set_task_estimate(&mut self, id: TaskId, estimate: &TaskEstimate) -> DbResult<()> {
+        if !estimate.is_valid() {
+            return Err(DbError::InvalidEstimateError);
+        }
+        let rows = self.conn.execute(&format!(
+            "UPDATE {} SET estimate_minutes = ?2, remaining_minutes = ?3 WHERE id = ?1;", Db::TASK_TABLE
+        ), (id, estimate.estimate_minutes, estimate.remaining_minutes))?;
+        if rows == 0 {
+            return Err(DbError::TaskDoesNotExistError { id });
+        } else if rows > 1 {
+            panic!("Set task estimate changed {} rows!", rows);
+        }
+        Ok(())
+    }
+
+    /// Adds up the estimates of the tasks that pass a filter function, along with the time tracked
+    /// on them, counting the running timer up to `now`.
+    pub fn estimate_totals<P>(&self, predicate: P, now: DateTime<Utc>) -> DbResult<EstimateTotals>
+        where P: Fn(&Task) -> bool {
+        let mut totals = EstimateTotals::default();
+        for task in self.filter_tasks(predicate)? {
+            totals.add(&task, self.task_time_spent(task.id, now)?);
+        }
+        Ok(totals)
+    }
+
+    /// Adds up the estimates of all tasks per tag like `estimate_totals`,
+    /// in order of tag id with untagged tasks first. Tags without tasks are left out.
+    pub fn tag_estimate_totals(&self, now: DateTime<Utc>) -> DbResult<Vec<TagEstimateTotals>> {
+        let mut tags = BTreeMap::new();
+        for task in self.all_tasks()? {
+            let tracked_seconds = self.task_time_spent(task.id, now)?;
+            tags.entry(task.tag).or_insert_with(EstimateTotals::default).add(&task, tracked_seconds);
+        }
+        Ok(tags.into_iter()
+            .map(|(tag_id, totals)| TagEstimateTotals { tag_id, totals })
+            .collect())
+    }
+
+    /// The estimate of a finished task against the time tracked on it,
+    /// or `None` if the task is not finished or has no estimate.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    pub fn estimate_comparison(&self, id: TaskId) -> DbResult<Option<EstimateComparison>> {
+        let task = self.task_by_id(id)?.ok_or(DbError::TaskDoesNotExistError { id })?;
+        match (task.done_time, task.estimate_minutes) {
+            (Some(done_time), Some(estimate)) => Ok(Some(
+                EstimateComparison::new(id, estimate, self.task_time_spent(id, done_time)?)
+            )),
+            _ => Ok(None),
+        }
+    }
+}
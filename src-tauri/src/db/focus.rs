@@ -0,0 +1,154 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{Connection, OptionalExtension, Row};
+use rusqlite::types::Type;
+use crate::{Db, DbError, DbResult, FocusCount, FocusPhase, FocusSession, FocusSettings, FocusStats, MyDateTime, TaskId, day_bounds};
+
+impl Db {
+    const FOCUS_WORK_SETTING: &'static str = "focus_work_seconds";
+    const FOCUS_SHORT_BREAK_SETTING: &'static str = "focus_short_break_seconds";
+    const FOCUS_LONG_BREAK_SETTING: &'static str = "focus_long_break_seconds";
+    const FOCUS_LONG_BREAK_EVERY_SETTING: &'static str = "focus_long_break_every";
+
+    /// The lengths of focus session phases, with defaults for those never set.
+    pub fn focus_settings(&self) -> DbResult<FocusSettings> {
+        let defaults = FocusSettings::default();
+        Ok(FocusSettings {
+            work_seconds: self.parsed_setting(Db::FOCUS_WORK_SETTING)?.unwrap_or(defaults.work_seconds),
+            short_break_seconds: self.parsed_setting(Db::FOCUS_SHORT_BREAK_SETTING)?
+                .unwrap_or(defaults.short_break_seconds),
+            long_break_seconds: self.parsed_setting(Db::FOCUS_LONG_BREAK_SETTING)?
+                .unwrap_or(defaults.long_break_seconds),
+            long_break_every: self.parsed_setting(Db::FOCUS_LONG_BREAK_EVERY_SETTING)?
+                .unwrap_or(defaults.long_break_every),
+        })
+    }
+
+    /// Sets the lengths of focus session phases, taking effect from the next phase.
+    ///
+    /// # Failure
+    /// Returns `DbError::InvalidFocusSettingsError` if the settings are not valid (see `FocusSettings::is_valid`).
+    pub fn set_focus_settings(&mut self, settings: &FocusSettings) -> DbResult<()> {
+        if !settings.is_valid() {
+            return Err(DbError::InvalidFocusSettingsError);
+        }
+        self.set_setting(Db::FOCUS_WORK_SETTING, &settings.work_seconds.to_string())?;
+        self.set_setting(Db::FOCUS_SHORT_BREAK_SETTING, &settings.short_break_seconds.to_string())?;
+        self.set_setting(Db::FOCUS_LONG_BREAK_SETTING, &settings.long_break_seconds.to_string())?;
+        self.set_setting(Db::FOCUS_LONG_BREAK_EVERY_SETTING, &settings.long_break_every.to_string())
+    }
+
+    /// Retrieves the focus session in progress, or `None` if there is none.
+    pub fn focus_session(&self) -> DbResult<Option<FocusSession>> {
+        Ok(self.conn.query_row(&format!(
+            "SELECT * FROM {}", Db::FOCUS_STATE_TABLE
+        ), (), Db::focus_session_from_row).optional()?)
+    }
+
+    /// Replaces the focus session in progress, or clears it if `session` is `None`.
+    /// See `FocusTimer` for running sessions.
+    pub fn save_focus_session(&mut self, session: Option<&FocusSession>) -> DbResult<()> {
+        let tx = self.conn.savepoint()?;
+        Db::write_focus_session(&tx, session)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Records a completed work phase of a focus session on a task.
+    pub fn record_focus_session(&mut self, task_id: TaskId, start_time: DateTime<Utc>, end_time: DateTime<Utc>) -> DbResult<()> {
+        Db::insert_focus_session(&self.conn, task_id, start_time, end_time)
+    }
+
+    /// Ends the current phase of `session`, recording it if it was a completed work phase,
+    /// and replaces the session in progress with `next`, or clears it if `next` is `None`.
+    pub fn end_focus_phase(&mut self, session: &FocusSession, completed: bool, next: Option<&FocusSession>) -> DbResult<()> {
+        let tx = self.conn.savepoint()?;
+        if completed && session.phase == FocusPhase::Work {
+            Db::insert_focus_session(&tx, session.task_id, session.phase_start, session.phase_end)?;
+        }
+        Db::write_focus_session(&tx, next)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Counts the work phases of focus sessions completed on `date` in the timezone of the user,
+    /// in total and per task and tag.
+    pub fn focus_stats(&self, date: NaiveDate) -> DbResult<FocusStats> {
+        let (start, end) = day_bounds(&self.user_timezone()?, date).ok_or(DbError::DateOutOfRangeError)?;
+        let range = (MyDateTime::from(start), MyDateTime::from(end));
+        let tasks = self.focus_counts(&format!(r#"
+            SELECT task_id AS id, count(*) AS sessions, sum(end_time - start_time) AS seconds
+            FROM {} WHERE end_time >= ?1 AND end_time < ?2
+            GROUP BY task_id ORDER BY task_id
+        "#, Db::FOCUS_SESSION_TABLE), range)?;
+        let tags = self.focus_counts(&format!(r#"
+            SELECT tag_id AS id, count(*) AS sessions, sum(end_time - start_time) AS seconds
+            FROM {} INNER JOIN {} USING (task_id) WHERE end_time >= ?1 AND end_time < ?2
+            GROUP BY tag_id ORDER BY tag_id
+        "#, Db::FOCUS_SESSION_TABLE, Db::TAG_TASK_TABLE), range)?;
+        Ok(FocusStats {
+            date,
+            sessions: tasks.iter().map(|count| count.sessions).sum(),
+            seconds: tasks.iter().map(|count| count.seconds).sum(),
+            tasks,
+            tags,
+        })
+    }
+
+    fn write_focus_session(tx: &Connection, session: Option<&FocusSession>) -> DbResult<()> {
+        tx.execute(&format!("DELETE FROM {};", Db::FOCUS_STATE_TABLE), ())?;
+        if let Some(session) = session {
+            tx.execute(&format!(r#"
+                INSERT INTO {} (id, task_id, phase, phase_start, phase_end, completed_in_cycle)
+                values (0, ?1, ?2, ?3, ?4, ?5);
+            "#, Db::FOCUS_STATE_TABLE), (
+                session.task_id, Db::focus_phase_name(session.phase), MyDateTime::from(session.phase_start),
+                MyDateTime::from(session.phase_end), session.completed_in_cycle,
+            ))?;
+        }
+        Ok(())
+    }
+
+    fn insert_focus_session(tx: &Connection, task_id: TaskId, start_time: DateTime<Utc>, end_time: DateTime<Utc>) -> DbResult<()> {
+        tx.execute(&format!(
+            "INSERT INTO {} (task_id, start_time, end_time) values (?1, ?2, ?3);", Db::FOCUS_SESSION_TABLE
+        ), (task_id, MyDateTime::from(start_time), MyDateTime::from(end_time)))?;
+        Ok(())
+    }
+
+    fn focus_counts(&self, query: &str, range: (MyDateTime, MyDateTime)) -> DbResult<Vec<FocusCount>> {
+        let mut stmt = self.conn.prepare(query).unwrap();
+        let iter = stmt.query_map(range, |row| Ok(FocusCount {
+            id: row.get("id")?,
+            sessions: row.get("sessions")?,
+            seconds: row.get("seconds")?,
+        }))?;
+        Ok(iter.map(|count| count.unwrap()).collect())
+    }
+
+    fn focus_phase_name(phase: FocusPhase) -> &'static str {
+        match phase {
+            FocusPhase::Work => "work",
+            FocusPhase::ShortBreak => "short_break",
+            FocusPhase::LongBreak => "long_break",
+        }
+    }
+
+    fn focus_session_from_row(row: &Row) -> rusqlite::Result<FocusSession> {
+        let phase: String = row.get("phase")?;
+        let phase = match phase.as_str() {
+            "work" => FocusPhase::Work,
+            "short_break" => FocusPhase::ShortBreak,
+            "long_break" => FocusPhase::LongBreak,
+            other => return Err(rusqlite::Error::FromSqlConversionFailure(
+                2, Type::Text, format!("Unknown focus phase {other}").into(),
+            )),
+        };
+        Ok(FocusSession {
+            task_id: row.get("task_id")?,
+            phase,
+            phase_start: row.get::<_, MyDateTime>("phase_start")?.into(),
+            phase_end: row.get::<_, MyDateTime>("phase_end")?.into(),
+            completed_in_cycle: row.get("completed_in_cycle")?,
+        })
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{OptionalExtension, Row};
+use crate::{Db, DbError, DbResult, Habit, HabitStreaks, HeatmapDay, TaskId, local_date};
+
+impl Db {
+    /// Makes a task a habit, or changes how often an existing habit should be done.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    /// Returns `DbError::TaskStatusError` if the task is finished, since a habit is never done.
+    /// Returns `DbError::InvalidHabitError` if `every_days` is zero.
+    pub fn set_habit(&mut self, habit: &Habit) -> DbResult<()> {
+        match self.task_by_id(habit.task_id)? {
+            None => return Err(DbError::TaskDoesNotExistError { id: habit.task_id }),
+            Some(task) if task.is_done() => return Err(DbError::TaskStatusError { id: habit.task_id, actual_status: true }),
+            Some(_) => {}
+        }
+        if !habit.is_valid() {
+            return Err(DbError::InvalidHabitError);
+        }
+        self.conn.execute(&format!(r#"
+            INSERT INTO {} (task_id, every_days, grace_days) values (?1, ?2, ?3)
+            ON CONFLICT (task_id) DO UPDATE SET every_days = excluded.every_days, grace_days = excluded.grace_days;
+        "#, Db::HABIT_TABLE), (habit.task_id, habit.every_days, habit.grace_days))?;
+        Ok(())
+    }
+
+    /// Makes a habit a regular task again, deleting its check-ins.
+    ///
+    /// # Failure
+    /// Returns `DbError::NotAHabitError` if the task is not a habit.
+    pub fn remove_habit(&mut self, task_id: TaskId) -> DbResult<()> {
+        let tx = self.conn.savepoint()?;
+        let rows = tx.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1;", Db::HABIT_TABLE
+        ), (task_id,))?;
+        if rows == 0 {
+            return Err(DbError::NotAHabitError { id: task_id });
+        }
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1;", Db::HABIT_CHECK_IN_TABLE
+        ), (task_id,))?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Retrieve the habit of a task, or `None` if the task is not a habit.
+    pub fn habit(&self, task_id: TaskId) -> DbResult<Option<Habit>> {
+        Ok(self.conn.query_row(&format!(
+            "SELECT * FROM {} WHERE task_id = ?1", Db::HABIT_TABLE
+        ), (task_id,), Db::habit_from_row).optional()?)
+    }
+
+    /// Retrieves every habit, in order of task id.
+    pub fn all_habits(&self) -> DbResult<Vec<Habit>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT * FROM {} ORDER BY task_id", Db::HABIT_TABLE
+        )).unwrap();
+        let iter = stmt.query_map((), Db::habit_from_row)?;
+        Ok(iter.map(|habit| habit.unwrap()).collect())
+    }
+
+    /// Checks in a habit on `date`. `finish_task` checks in habits for the current day.
+    ///
+    /// # Failure
+    /// Returns `DbError::NotAHabitError` if the task is not a habit.
+    /// Returns `DbError::TaskStatusError` if the habit is already checked in on `date`.
+    pub fn check_in_habit(&mut self, task_id: TaskId, date: NaiveDate) -> DbResult<()> {
+        if self.habit(task_id)?.is_none() {
+            return Err(DbError::NotAHabitError { id: task_id });
+        }
+        let rows = self.conn.execute(&format!(
+            "INSERT OR IGNORE INTO {} (task_id, date) values (?1, ?2);", Db::HABIT_CHECK_IN_TABLE
+        ), (task_id, date))?;
+        match rows {
+            0 => Err(DbError::TaskStatusError { id: task_id, actual_status: true }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Removes the check-in of a habit on `date`. `unfinish_task` does so for the current day.
+    ///
+    /// # Failure
+    /// Returns `DbError::NotAHabitError` if the task is not a habit.
+    /// Returns `DbError::TaskStatusError` if the habit is not checked in on `date`.
+    pub fn undo_habit_check_in(&mut self, task_id: TaskId, date: NaiveDate) -> DbResult<()> {
+        if self.habit(task_id)?.is_none() {
+            return Err(DbError::NotAHabitError { id: task_id });
+        }
+        let rows = self.conn.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1 AND date = ?2;", Db::HABIT_CHECK_IN_TABLE
+        ), (task_id, date))?;
+        match rows {
+            0 => Err(DbError::TaskStatusError { id: task_id, actual_status: false }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Retrieves the days a habit was checked in on, in ascending order.
+    pub fn habit_check_ins(&self, task_id: TaskId) -> DbResult<Vec<NaiveDate>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT date FROM {} WHERE task_id = ?1 ORDER BY date", Db::HABIT_CHECK_IN_TABLE
+        )).unwrap();
+        let iter = stmt.query_map((task_id,), |row| row.get(0))?;
+        Ok(iter.map(|date| date.unwrap()).collect())
+    }
+
+    /// The current and longest streaks of a habit as of `today`.
+    ///
+    /// # Failure
+    /// Returns `DbError::NotAHabitError` if the task is not a habit.
+    pub fn habit_streaks(&self, task_id: TaskId, today: NaiveDate) -> DbResult<HabitStreaks> {
+        let habit = match self.habit(task_id)? {
+            Some(habit) => habit,
+            None => return Err(DbError::NotAHabitError { id: task_id }),
+        };
+        Ok(habit.streaks(&self.habit_check_ins(task_id)?, today))
+    }
+
+    /// Counts the check-ins on each day from `first` to `last` inclusive, of one habit
+    /// or of every habit if `task_id` is `None`.
+    ///
+    /// # Failure
+    /// Returns `DbError::InvalidDateRangeError` if `first` is after `last`, or the range is longer than `MAX_RANGE_DAYS`.
+    /// Returns `DbError::DateOutOfRangeError` if the range reaches the ends of the range of dates.
+    pub fn habit_heatmap(&self, task_id: Option<TaskId>, first: NaiveDate, last: NaiveDate) -> DbResult<Vec<HeatmapDay>> {
+        let end = Db::check_date_range(first, last)?;
+        let mut stmt = self.conn.prepare(&format!(r#"
+            SELECT date, count(*) FROM {}
+            WHERE date >= ?1 AND date <= ?2 AND (?3 IS NULL OR task_id = ?3)
+            GROUP BY date
+        "#, Db::HABIT_CHECK_IN_TABLE)).unwrap();
+        let counts: HashMap<NaiveDate, i64> = stmt
+            .query_map((first, last, task_id), |row| Ok((row.get(0)?, row.get(1)?)))?
+            .map(|count| count.unwrap())
+            .collect();
+        let mut days = vec![];
+        let mut date = first;
+        while date < end {
+            days.push(HeatmapDay { date, check_ins: counts.get(&date).copied().unwrap_or(0) });
+            // end has a previous day, so every date before it has a next one
+            date = date.succ_opt().unwrap();
+        }
+        Ok(days)
+    }
+
+    /// The day of `now` in the timezone of the user, which habits are checked in on.
+    pub(super) fn habit_today(&self, now: &DateTime<Utc>) -> DbResult<NaiveDate> {
+        Ok(local_date(&self.user_timezone()?, now))
+    }
+
+    fn habit_from_row(row: &Row) -> rusqlite::Result<Habit> {
+        Ok(Habit {
+            task_id: row.get("task_id")?,
+            every_days: row.get("every_days")?,
+            grace_days: row.get("grace_days")?,
+        })
+    }
+}
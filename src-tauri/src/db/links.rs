@@ -0,0 +1,151 @@
+use rusqlite::{Connection, Row};
+use crate::{Db, DbError, DbResult, TaskId, TaskLink, TaskReference, extract_references, replace_id_references, replace_title_references};
+
+impl Db {
+    /// The links written in the body of a task, in the order they first appear in it.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    pub fn task_links(&self, task_id: TaskId) -> DbResult<Vec<TaskLink>> {
+        if self.task_by_id(task_id)?.is_none() {
+            return Err(DbError::TaskDoesNotExistError { id: task_id });
+        }
+        self.query_task_links("links.source_id = ?1 ORDER BY links.position", (task_id,))
+    }
+
+    /// The links to a task from the bodies of other tasks (or its own), in order of the linking task.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    pub fn backlinks(&self, task_id: TaskId) -> DbResult<Vec<TaskLink>> {
+        if self.task_by_id(task_id)?.is_none() {
+            return Err(DbError::TaskDoesNotExistError { id: task_id });
+        }
+        self.query_task_links("links.target_id = ?1 ORDER BY links.source_id, links.position", (task_id,))
+    }
+
+    /// Every link that doesn't refer to a task, either because no task has its title
+    /// or because the task was deleted.
+    pub fn broken_links(&self) -> DbResult<Vec<TaskLink>> {
+        self.query_task_links("targets.id IS NULL ORDER BY links.source_id, links.position", ())
+    }
+
+    /// Replaces the links of a task with the references in its `body`. A title refers to
+    /// the oldest task with exactly that title.
+    pub(super) fn update_task_links(tx: &Connection, task_id: TaskId, body: &str) -> rusqlite::Result<()> {
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE source_id = ?1;", Db::TASK_LINK_TABLE
+        ), (task_id,))?;
+        for (position, reference) in extract_references(body).into_iter().enumerate() {
+            let (title, target) = match reference {
+                TaskReference::Title { title } => {
+                    let target: Option<TaskId> = tx.query_row(&format!(
+                        "SELECT min(id) FROM {} WHERE title = ?1", Db::TASK_TABLE
+                    ), (&title,), |row| row.get(0))?;
+                    (Some(title), target)
+                },
+                TaskReference::Id { id } => (None, Some(id)),
+            };
+            tx.execute(&format!(
+                "INSERT INTO {} (source_id, position, title, target_id) values (?1, ?2, ?3, ?4);", Db::TASK_LINK_TABLE
+            ), (task_id, position as i64, title, target))?;
+        }
+        Ok(())
+    }
+
+    /// Updates the links after the title of a task changed from `old` to `new`: `[[old]]` in the bodies
+    /// of other tasks linking to it is rewritten to `[[new]]`, and links to `[[new]]` that
+    /// referred to no task now refer to it.
+    pub(super) fn rename_task_links(tx: &Connection, task_id: TaskId, old: &str, new: &str) -> rusqlite::Result<()> {
+        let sources = {
+            let mut stmt = tx.prepare(&format!(
+                "SELECT DISTINCT source_id FROM {} WHERE target_id = ?1 AND title = ?2 AND source_id != ?1", Db::TASK_LINK_TABLE
+            ))?;
+            let iter = stmt.query_map((task_id, old), |row| row.get::<_, TaskId>(0))?;
+            iter.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        for source in sources {
+            let body: String = tx.query_row(&format!(
+                "SELECT body FROM {} WHERE id = ?1", Db::TASK_TABLE
+            ), (source,), |row| row.get(0))?;
+            tx.execute(&format!(
+                "UPDATE {} SET body = ?2 WHERE id = ?1;", Db::TASK_TABLE
+            ), (source, replace_title_references(&body, old, new)))?;
+            Db::invalidate_rendered_body(tx, source)?;
+        }
+        tx.execute(&format!(
+            "UPDATE {} SET title = ?3 WHERE target_id = ?1 AND title = ?2 AND source_id != ?1;", Db::TASK_LINK_TABLE
+        ), (task_id, old, new))?;
+        Db::resolve_task_links(tx, task_id, new)
+    }
+
+    /// Points the links to the id of a task being merged into `into` at `into` instead: `#task_id`
+    /// in the bodies of other tasks linking to it is rewritten to `#into`.
+    /// Links to its title are left to `delete_task_links`.
+    pub(super) fn redirect_task_links(tx: &Connection, task_id: TaskId, into: TaskId) -> rusqlite::Result<()> {
+        let sources = {
+            let mut stmt = tx.prepare(&format!(
+                "SELECT DISTINCT source_id FROM {} WHERE target_id = ?1 AND title IS NULL AND source_id != ?1", Db::TASK_LINK_TABLE
+            ))?;
+            let iter = stmt.query_map((task_id,), |row| row.get::<_, TaskId>(0))?;
+            iter.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        for source in sources {
+            let body: String = tx.query_row(&format!(
+                "SELECT body FROM {} WHERE id = ?1", Db::TASK_TABLE
+            ), (source,), |row| row.get(0))?;
+            tx.execute(&format!(
+                "UPDATE {} SET body = ?2 WHERE id = ?1;", Db::TASK_TABLE
+            ), (source, replace_id_references(&body, task_id, into)))?;
+            Db::invalidate_rendered_body(tx, source)?;
+        }
+        tx.execute(&format!(
+            "UPDATE {} SET target_id = ?2 WHERE target_id = ?1 AND title IS NULL AND source_id != ?1;", Db::TASK_LINK_TABLE
+        ), (task_id, into))?;
+        Ok(())
+    }
+
+    /// Points the links to `[[title]]` that refer to no task at the task with `task_id`.
+    pub(super) fn resolve_task_links(tx: &Connection, task_id: TaskId, title: &str) -> rusqlite::Result<()> {
+        tx.execute(&format!(
+            "UPDATE {} SET target_id = ?1 WHERE target_id IS NULL AND title = ?2;", Db::TASK_LINK_TABLE
+        ), (task_id, title))?;
+        Ok(())
+    }
+
+    /// Removes the links in a deleted task, and points the links to its title at the first other task
+    /// with that title, or lets them refer to the next task given that title. Links to its id stay broken.
+    pub(super) fn delete_task_links(tx: &Connection, task_id: TaskId) -> rusqlite::Result<()> {
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE source_id = ?1;", Db::TASK_LINK_TABLE
+        ), (task_id,))?;
+        tx.execute(&format!(r#"
+            UPDATE {} SET target_id = (SELECT min(id) FROM {} WHERE title = {0}.title AND id != ?1)
+            WHERE target_id = ?1 AND title IS NOT NULL;
+        "#, Db::TASK_LINK_TABLE, Db::TASK_TABLE), (task_id,))?;
+        Ok(())
+    }
+
+    fn query_task_links<P: rusqlite::Params>(&self, condition: &str, params: P) -> DbResult<Vec<TaskLink>> {
+        let mut stmt = self.conn.prepare(&format!(r#"
+            SELECT links.source_id, links.title, links.target_id, targets.id AS existing_id
+            FROM {} links LEFT JOIN {} targets ON targets.id = links.target_id
+            WHERE {}
+        "#, Db::TASK_LINK_TABLE, Db::TASK_TABLE, condition)).unwrap();
+        let iter = stmt.query_map(params, Db::task_link_from_row)?;
+        Ok(iter.map(|link| link.unwrap()).collect())
+    }
+
+    fn task_link_from_row(row: &Row) -> rusqlite::Result<TaskLink> {
+        let title: Option<String> = row.get("title")?;
+        let reference = match title {
+            Some(title) => TaskReference::Title { title },
+            None => TaskReference::Id { id: row.get("target_id")? },
+        };
+        Ok(TaskLink {
+            source: row.get("source_id")?,
+            target: row.get("existing_id")?,
+            reference,
+        })
+    }
+}
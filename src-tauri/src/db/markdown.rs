@@ -0,0 +1,47 @@
+use rusqlite::{Connection, OptionalExtension};
+use crate::{BodyOutline, Db, DbError, DbResult, RenderedBody, TaskId, outline_body, render_body};
+
+impl Db {
+    /// The body of a task rendered from markdown (see `render_body`).
+    /// The result is kept until the body changes.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    pub fn rendered_body(&self, task_id: TaskId) -> DbResult<RenderedBody> {
+        let cached = self.conn.query_row(&format!(
+            "SELECT html, preview FROM {} WHERE task_id = ?1", Db::RENDERED_BODY_TABLE
+        ), (task_id,), |row| Ok(RenderedBody {
+            html: row.get(0)?,
+            preview: row.get(1)?,
+        })).optional()?;
+        if let Some(rendered) = cached {
+            return Ok(rendered);
+        }
+
+        let task = self.task_by_id(task_id)?.ok_or(DbError::TaskDoesNotExistError { id: task_id })?;
+        let rendered = render_body(&task.body);
+        self.conn.execute(&format!(
+            "INSERT OR REPLACE INTO {} (task_id, html, preview) values (?1, ?2, ?3);", Db::RENDERED_BODY_TABLE
+        ), (task_id, &rendered.html, &rendered.preview))?;
+        Ok(rendered)
+    }
+
+    /// The headings, links and due mentions in the body of a task (see `outline_body`),
+    /// with relative dates resolved against its last edit time in the user's timezone.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    pub fn body_outline(&self, task_id: TaskId) -> DbResult<BodyOutline> {
+        let task = self.task_by_id(task_id)?.ok_or(DbError::TaskDoesNotExistError { id: task_id })?;
+        let written = task.last_edit_time.with_timezone(&self.user_timezone()?);
+        Ok(outline_body(&task.body, &written))
+    }
+
+    /// Forgets the rendered body of a task, after its body changed.
+    pub(super) fn invalidate_rendered_body(conn: &Connection, task_id: TaskId) -> rusqlite::Result<()> {
+        conn.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1;", Db::RENDERED_BODY_TABLE
+        ), (task_id,))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{Connection, OptionalExtension, Row};
+use crate::{Db, DbError, DbResult, DayPlan, EditableTimeBlockData, GeneratedTimeBlockData, MyDateTime, PlannerSettings, TimeBlock, TimeBlockId, auto_schedule, day_bounds, find_conflicts, resolve_local};
+
+impl Db {
+    const PLANNER_WORK_START_SETTING: &'static str = "planner_work_start";
+    const PLANNER_WORK_END_SETTING: &'static str = "planner_work_end";
+    const PLANNER_MINUTES_PER_DIFFICULTY_SETTING: &'static str = "planner_minutes_per_difficulty";
+
+    /// How the auto-scheduler plans a day, with defaults for settings never set.
+    pub fn planner_settings(&self) -> DbResult<PlannerSettings> {
+        let defaults = PlannerSettings::default();
+        Ok(PlannerSettings {
+            work_start: self.parsed_setting(Db::PLANNER_WORK_START_SETTING)?.unwrap_or(defaults.work_start),
+            work_end: self.parsed_setting(Db::PLANNER_WORK_END_SETTING)?.unwrap_or(defaults.work_end),
+            minutes_per_difficulty: self.parsed_setting(Db::PLANNER_MINUTES_PER_DIFFICULTY_SETTING)?
+                .unwrap_or(defaults.minutes_per_difficulty),
+        })
+    }
+
+    /// Sets how the auto-scheduler plans a day.
+    ///
+    /// # Failure
+    /// Returns `DbError::InvalidPlannerSettingsError` if working hours end before they start,
+    /// or `minutes_per_difficulty` is not positive.
+    pub fn set_planner_settings(&mut self, settings: &PlannerSettings) -> DbResult<()> {
+        if !settings.is_valid() {
+            return Err(DbError::InvalidPlannerSettingsError);
+        }
+        self.set_setting(Db::PLANNER_WORK_START_SETTING, &settings.work_start.to_string())?;
+        self.set_setting(Db::PLANNER_WORK_END_SETTING, &settings.work_end.to_string())?;
+        self.set_setting(Db::PLANNER_MINUTES_PER_DIFFICULTY_SETTING, &settings.minutes_per_difficulty.to_string())
+    }
+
+    /// Add a new time block to the database. Returns the fields generated for this block.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task of the block doesn't exist.
+    /// Returns `DbError::InvalidTimeBlockError` if the block doesn't end after it starts.
+    pub fn add_time_block(&mut self, data: &EditableTimeBlockData) -> DbResult<GeneratedTimeBlockData> {
+        self.check_time_block(data)?;
+        let tx = self.conn.savepoint()?;
+        let new_id = Db::insert_time_block(&tx, data, false)?;
+        tx.commit()?;
+        Ok(GeneratedTimeBlockData {
+            id: new_id,
+        })
+    }
+
+    /// Retrieve the time block with this id, or `None` if it doesn't exist in the database.
+    pub fn time_block_by_id(&self, id: TimeBlockId) -> DbResult<Option<TimeBlock>> {
+        Ok(self.conn.query_row(&format!(
+            "SELECT * FROM {} WHERE id = ?1", Db::TIME_BLOCK_TABLE
+        ), (id,), Db::time_block_from_row).optional()?)
+    }
+
+    /// Modifies an existing time block in the database, such as to move or resize it.
+    /// The block is no longer replaced by the auto-scheduler afterwards.
+    ///
+    /// # Failure
+    /// Returns `DbError::TimeBlockDoesNotExistError` if the block doesn't exist in the database.
+    /// Otherwise fails the same way as `add_time_block`.
+    pub fn modify_time_block(&mut self, id: TimeBlockId, data: &EditableTimeBlockData) -> DbResult<()> {
+        self.check_time_block(data)?;
+        let rows = self.conn.execute(&format!(r#"
+                UPDATE {} SET
+                    task_id = ?2,
+                    title = ?3,
+                    start_time = ?4,
+                    end_time = ?5,
+                    fixed = ?6,
+                    auto_scheduled = 0
+                WHERE id = ?1;
+            "#, Db::TIME_BLOCK_TABLE),
+            (id, data.task_id, &data.title, MyDateTime::from(data.start_time), MyDateTime::from(data.end_time), data.fixed))?;
+        match rows {
+            0 => Err(DbError::TimeBlockDoesNotExistError { id }),
+            1 => Ok(()),
+            other => panic!("Modify time block changed {} rows!", other),
+        }
+    }
+
+    /// Delete a time block by its id in the database.
+    ///
+    /// # Failure
+    /// Returns `DbError::TimeBlockDoesNotExistError` if the block doesn't exist in the database.
+    pub fn delete_time_block(&mut self, id: TimeBlockId) -> DbResult<()> {
+        let rows = self.conn.execute(&format!(
+            "DELETE FROM {} WHERE id = ?1;", Db::TIME_BLOCK_TABLE
+        ), (id,))?;
+        match rows {
+            0 => Err(DbError::TimeBlockDoesNotExistError { id }),
+            1 => Ok(()),
+            other => panic!("Delete time block changed {} rows!", other),
+        }
+    }
+
+    /// Retrieves the time blocks overlapping `date` in the timezone of the user, along with their conflicts.
+    pub fn day_plan(&self, date: NaiveDate) -> DbResult<DayPlan> {
+        let (start, end) = day_bounds(&self.user_timezone()?, date).ok_or(DbError::DateOutOfRangeError)?;
+        let blocks = self.time_blocks_between(start, end)?;
+        Ok(DayPlan {
+            date,
+            conflicts: find_conflicts(&blocks),
+            blocks,
+        })
+    }
+
+    /// Plans the working hours of `date` left after `now` with the open tasks (see `auto_schedule`),
+    /// replacing the blocks previously made by the auto-scheduler and working around all other blocks,
+    /// fixed or not. The old blocks are only replaced once the new ones are all stored.
+    /// Tasks that already have a block that day are not scheduled again, and tasks deferred
+    /// past the end of the working hours or in an archived project are left out.
+    ///
+    /// Returns the new plan of the day.
+    pub fn auto_schedule_day(&mut self, date: NaiveDate, now: DateTime<Utc>) -> DbResult<DayPlan> {
+        let timezone = self.user_timezone()?;
+        let settings = self.planner_settings()?;
+        let (day_start, day_end) = day_bounds(&timezone, date).ok_or(DbError::DateOutOfRangeError)?;
+        let work_start = resolve_local(&timezone, date.and_time(settings.work_start)).max(now);
+        let work_end = resolve_local(&timezone, date.and_time(settings.work_end));
+
+        let replaced = |block: &TimeBlock| block.auto_scheduled
+            && day_start <= block.start_time && block.start_time < day_end;
+        let busy: Vec<TimeBlock> = self.time_blocks_between(day_start, day_end)?.into_iter()
+            .filter(|block| !replaced(block))
+            .collect();
+        let tasks = self.filter_tasks(|task| !task.is_done() && !task.paused && !task.archived && !task.is_deferred_at(&work_end)
+            && busy.iter().all(|block| block.task_id != Some(task.id)))?;
+        let scheduled = auto_schedule(&tasks, &busy, work_start, work_end, &settings);
+
+        let tx = self.conn.savepoint()?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE auto_scheduled = 1 AND start_time >= ?1 AND start_time < ?2;", Db::TIME_BLOCK_TABLE
+        ), (MyDateTime::from(day_start), MyDateTime::from(day_end)))?;
+        for block in &scheduled {
+            Db::insert_time_block(&tx, block, true)?;
+        }
+        tx.commit()?;
+        self.day_plan(date)
+    }
+
+    fn insert_time_block(tx: &Connection, data: &EditableTimeBlockData, auto_scheduled: bool) -> DbResult<TimeBlockId> {
+        tx.execute(&format!(r#"
+            INSERT INTO {} (task_id, title, start_time, end_time, fixed, auto_scheduled)
+            values (?1, ?2, ?3, ?4, ?5, ?6);
+        "#, Db::TIME_BLOCK_TABLE), (
+            data.task_id, &data.title, MyDateTime::from(data.start_time), MyDateTime::from(data.end_time),
+            data.fixed, auto_scheduled,
+        ))?;
+        Ok(tx.last_insert_rowid())
+    }
+
+    fn time_blocks_between(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> DbResult<Vec<TimeBlock>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT * FROM {} WHERE start_time < ?2 AND end_time > ?1 ORDER BY start_time, id", Db::TIME_BLOCK_TABLE
+        )).unwrap();
+        let iter = stmt.query_map((MyDateTime::from(start), MyDateTime::from(end)), Db::time_block_from_row)?;
+        Ok(iter.map(|block| block.unwrap()).collect())
+    }
+
+    fn check_time_block(&self, data: &EditableTimeBlockData) -> DbResult<()> {
+        if let Some(task_id) = data.task_id {
+            if self.task_by_id(task_id)?.is_none() {
+                return Err(DbError::TaskDoesNotExistError { id: task_id });
+            }
+        }
+        if data.end_time <= data.start_time {
+            return Err(DbError::InvalidTimeBlockError);
+        }
+        Ok(())
+    }
+
+    fn time_block_from_row(row: &Row) -> rusqlite::Result<TimeBlock> {
+        Ok(TimeBlock {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            title: row.get("title")?,
+            start_time: row.get::<_, MyDateTime>("start_time")?.into(),
+            end_time: row.get::<_, MyDateTime>("end_time")?.into(),
+            fixed: row.get("fixed")?,
+            auto_scheduled: row.get("auto_scheduled")?,
+        })
+    }
+}
+
@@ -0,0 +1,208 @@
+use chrono::DateTime;
+use rusqlite::{OptionalExtension, Row};
+use crate::{Db, DbError, DbResult, EditableProjectData, GeneratedProjectData, MyDateTime, Project, ProjectId, ProjectProgress, Task, TaskId};
+use crate::stats::ratio;
+
+impl Db {
+    /// Add a new project to the database, initializing its id and create time.
+    /// New projects are not archived.
+    ///
+    /// Returns the fields generated for this project.
+    pub fn add_new_project(&mut self, data: &EditableProjectData) -> DbResult<GeneratedProjectData> {
+        let now = MyDateTime::now();
+        let tx = self.conn.savepoint()?;
+        tx.execute(&format!(r#"
+            INSERT INTO {} (name, description, status, deadline, archived, create_time) values
+            (?1, ?2, ?3, ?4, 0, ?5);
+        "#, Db::PROJECT_TABLE), (&data.name, &data.description, data.status,
+                                 data.deadline.map(MyDateTime::from), &now))?;
+        let new_id = tx.last_insert_rowid();
+        tx.commit()?;
+        Ok(GeneratedProjectData {
+            id: new_id,
+            create_time: now.into(),
+        })
+    }
+
+    /// Retrieves all projects stored in this database in order of insertion, including archived ones.
+    pub fn all_projects(&self) -> DbResult<Vec<Project>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT * FROM {} ORDER BY id", Db::PROJECT_TABLE
+        )).unwrap();
+        let iter = stmt.query_map([], Db::project_from_row)?;
+        Ok(iter.map(|project| project.unwrap()).collect())
+    }
+
+    /// Retrieve the project with this id, or `None` if the project doesn't exist in the database.
+    pub fn project_by_id(&self, id: ProjectId) -> DbResult<Option<Project>> {
+        Ok(self.conn.query_row(&format!(
+            "SELECT * FROM {} WHERE id = ?1", Db::PROJECT_TABLE
+        ), (id,), Db::project_from_row).optional()?)
+    }
+
+    /// Modifies an existing project in the database.
+    ///
+    /// # Failure
+    /// Returns `DbError::ProjectDoesNotExistError` if the project doesn't exist in the database.
+    pub fn modify_project(&mut self, id: ProjectId, data: &EditableProjectData) -> DbResult<()> {
+        let rows = self.conn.execute(&format!(r#"
+                UPDATE {} SET
+                    name = ?2,
+                    description = ?3,
+                    status = ?4,
+                    deadline = ?5
+                WHERE id = ?1;
+            "#, Db::PROJECT_TABLE),
+            (id, &data.name, &data.description, data.status, data.deadline.map(MyDateTime::from)))?;
+        match rows {
+            0 => Err(DbError::ProjectDoesNotExistError { id }),
+            1 => Ok(()),
+            other => panic!("Modify project changed {} rows!", other),
+        }
+    }
+
+    /// Archives or restores a project. The tasks of an archived project stay in it,
+    /// but are hidden by `TaskFilterOptions` unless asked for.
+    ///
+    /// # Failure
+    /// Returns `DbError::ProjectDoesNotExistError` if the project doesn't exist in the database.
+    pub fn set_project_archived(&mut self, id: ProjectId, archived: bool) -> DbResult<()> {
+        let rows = self.conn.execute(&format!(
+            "UPDATE {} SET archived = ?2 WHERE id = ?1;", Db::PROJECT_TABLE
+        ), (id, archived))?;
+        match rows {
+            0 => Err(DbError::ProjectDoesNotExistError { id }),
+            1 => Ok(()),
+            other => panic!("Archive project changed {} rows!", other),
+        }
+    }
+
+    /// Delete a project by its id in the database. Its tasks are kept, no longer in any project.
+    ///
+    /// # Failure
+    /// Returns `DbError::ProjectDoesNotExistError` if the project doesn't exist in the database.
+    pub fn delete_project(&mut self, id: ProjectId) -> DbResult<()> {
+        let tx = self.conn.savepoint()?;
+        let rows = tx.execute(&format!(
+            "DELETE FROM {} WHERE id = ?1;", Db::PROJECT_TABLE
+        ), (id,))?;
+        if rows == 0 {
+            return Err(DbError::ProjectDoesNotExistError { id });
+        } else if rows > 1 {
+            panic!("Delete project changed {} rows!", rows);
+        }
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE project_id = ?1;", Db::PROJECT_TASK_TABLE
+        ), (id,))?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Retrieves the tasks of a project in their order within it.
+    ///
+    /// # Failure
+    /// Returns `DbError::ProjectDoesNotExistError` if the project doesn't exist in the database.
+    pub fn project_tasks(&self, id: ProjectId) -> DbResult<Vec<Task>> {
+        self.check_project(id)?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {0}.* FROM {0} JOIN {1} ON id = task_id WHERE project_id = ?1 ORDER BY position",
+            Db::TASK_TABLE, Db::PROJECT_TASK_TABLE
+        )).unwrap();
+        let iter = stmt.query_map((id,), |row| self.task_from_row(row))?;
+        Ok(iter.map(|task| task.unwrap()).collect())
+    }
+
+    /// Moves a task to `position` in the order of a project, or to the end if `position` is `None`
+    /// or past the end. The task is taken out of the project it was in, which may be the same project
+    /// to reorder it. A `project_id` of `None` takes the task out of any project.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    /// Returns `DbError::ProjectDoesNotExistError` if the project doesn't exist in the database.
+    pub fn move_task_to_project(&mut self, task_id: TaskId, project_id: Option<ProjectId>,
+                                position: Option<usize>) -> DbResult<()> {
+        if self.task_by_id(task_id)?.is_none() {
+            return Err(DbError::TaskDoesNotExistError { id: task_id });
+        }
+        if let Some(project_id) = project_id {
+            self.check_project(project_id)?;
+        }
+        let old_project = self.get_task_project(task_id).0;
+
+        let tx = self.conn.savepoint()?;
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE task_id = ?1;", Db::PROJECT_TASK_TABLE
+        ), (task_id,))?;
+        let order = |project_id: ProjectId| -> DbResult<Vec<TaskId>> {
+            let mut stmt = tx.prepare(&format!(
+                "SELECT task_id FROM {} WHERE project_id = ?1 ORDER BY position", Db::PROJECT_TASK_TABLE
+            ))?;
+            let iter = stmt.query_map((project_id,), |row| row.get(0))?;
+            Ok(iter.map(|id| id.unwrap()).collect())
+        };
+        let renumber = |project_id: ProjectId, task_ids: &[TaskId]| -> DbResult<()> {
+            for (position, task_id) in task_ids.iter().enumerate() {
+                tx.execute(&format!(r#"
+                    INSERT INTO {} (task_id, project_id, position) values (?1, ?2, ?3)
+                    ON CONFLICT (task_id) DO UPDATE SET position = excluded.position;
+                "#, Db::PROJECT_TASK_TABLE), (task_id, project_id, position))?;
+            }
+            Ok(())
+        };
+        if let Some(old_project) = old_project {
+            renumber(old_project, &order(old_project)?)?;
+        }
+        if let Some(project_id) = project_id {
+            let mut task_ids = order(project_id)?;
+            let position = position.unwrap_or(task_ids.len()).min(task_ids.len());
+            task_ids.insert(position, task_id);
+            renumber(project_id, &task_ids)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Summarizes how many of the tasks of a project are completed.
+    ///
+    /// # Failure
+    /// Returns `DbError::ProjectDoesNotExistError` if the project doesn't exist in the database.
+    pub fn project_progress(&self, id: ProjectId) -> DbResult<ProjectProgress> {
+        self.check_project(id)?;
+        let (total, completed, difficulty_total, difficulty_completed) = self.conn.query_row(&format!(r#"
+            SELECT
+                count(*),
+                coalesce(sum(done_time IS NOT NULL), 0),
+                coalesce(sum(difficulty), 0),
+                coalesce(sum(CASE WHEN done_time IS NOT NULL THEN difficulty ELSE 0 END), 0)
+            FROM {} JOIN {} ON id = task_id WHERE project_id = ?1
+        "#, Db::TASK_TABLE, Db::PROJECT_TASK_TABLE), (id,),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?;
+        Ok(ProjectProgress {
+            project_id: id,
+            total,
+            completed,
+            difficulty_total,
+            difficulty_completed,
+            completion_rate: ratio(completed, total),
+        })
+    }
+
+    fn check_project(&self, id: ProjectId) -> DbResult<()> {
+        match self.project_by_id(id)? {
+            Some(_) => Ok(()),
+            None => Err(DbError::ProjectDoesNotExistError { id }),
+        }
+    }
+
+    fn project_from_row(row: &Row) -> rusqlite::Result<Project> {
+        Ok(Project {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            description: row.get("description")?,
+            status: row.get("status")?,
+            deadline: row.get::<_, Option<MyDateTime>>("deadline")?.map(DateTime::from),
+            archived: row.get("archived")?,
+            create_time: row.get::<_, MyDateTime>("create_time")?.into(),
+        })
+    }
+}
@@ -0,0 +1,75 @@
+use rusqlite::{Connection, OptionalExtension};
+use crate::{Db, DbError, DbResult, TaskId, rank_between, rank_sequence};
+
+impl Db {
+    /// Rank keys are rebalanced (see `rebalance_task_ranks`) when moving or adding a task
+    /// would make a key longer than this.
+    pub(super) const MAX_RANK_LENGTH: usize = 16;
+
+    /// Moves a task to just before the task `before` in the manual order of tasks.
+    /// Only the moved task is changed, unless its rank key grows too long and all keys are rebalanced.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if either task doesn't exist in the database.
+    pub fn move_task_before(&mut self, id: TaskId, before: TaskId) -> DbResult<()> {
+        let next = self.task_rank(before)?;
+        self.task_rank(id)?;
+        let previous: Option<String> = self.conn.query_row(&format!(
+            "SELECT rank FROM {} WHERE rank < ?1 AND id != ?2 ORDER BY rank DESC LIMIT 1", Db::TASK_TABLE
+        ), (&next, id), |row| row.get(0)).optional()?;
+        self.set_task_rank(id, rank_between(previous.as_deref(), Some(&next)))
+    }
+
+    /// Moves a task to just after the task `after` in the manual order of tasks.
+    /// Only the moved task is changed, unless its rank key grows too long and all keys are rebalanced.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if either task doesn't exist in the database.
+    pub fn move_task_after(&mut self, id: TaskId, after: TaskId) -> DbResult<()> {
+        let previous = self.task_rank(after)?;
+        self.task_rank(id)?;
+        let next: Option<String> = self.conn.query_row(&format!(
+            "SELECT rank FROM {} WHERE rank > ?1 AND id != ?2 ORDER BY rank LIMIT 1", Db::TASK_TABLE
+        ), (&previous, id), |row| row.get(0)).optional()?;
+        self.set_task_rank(id, rank_between(Some(&previous), next.as_deref()))
+    }
+
+    /// Gives every task an evenly spaced rank key of the same length, keeping their order,
+    /// so that tasks can be moved again without keys growing long.
+    pub fn rebalance_task_ranks(&mut self) -> DbResult<()> {
+        let tx = self.conn.savepoint()?;
+        Db::rebalance_ranks(&tx)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Like `rebalance_task_ranks`, as part of the transaction that made a key too long.
+    pub(super) fn rebalance_ranks(tx: &Connection) -> DbResult<()> {
+        let ids: Vec<TaskId> = tx.prepare(&format!("SELECT id FROM {} ORDER BY rank, id", Db::TASK_TABLE))?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        for (id, rank) in ids.iter().zip(rank_sequence(ids.len())) {
+            tx.execute(&format!(
+                "UPDATE {} SET rank = ?2 WHERE id = ?1;", Db::TASK_TABLE
+            ), (id, rank))?;
+        }
+        Ok(())
+    }
+
+    fn task_rank(&self, id: TaskId) -> DbResult<String> {
+        self.conn.query_row(&format!(
+            "SELECT rank FROM {} WHERE id = ?1", Db::TASK_TABLE
+        ), (id,), |row| row.get(0)).optional()?
+            .ok_or(DbError::TaskDoesNotExistError { id })
+    }
+
+    fn set_task_rank(&mut self, id: TaskId, rank: String) -> DbResult<()> {
+        self.conn.execute(&format!(
+            "UPDATE {} SET rank = ?2 WHERE id = ?1;", Db::TASK_TABLE
+        ), (id, &rank))?;
+        if rank.len() > Db::MAX_RANK_LENGTH {
+            self.rebalance_task_ranks()?;
+        }
+        Ok(())
+    }
+}
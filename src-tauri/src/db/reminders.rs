@@ -0,0 +1,151 @@
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::types::Type;
+use rusqlite::{OptionalExtension, Row};
+use crate::{Db, DbError, DbResult, EditableReminderData, GeneratedReminderData, MyDateTime, Reminder, ReminderId, ReminderTrigger, ScheduledReminder, TaskId};
+
+impl Db {
+    /// Add a new reminder of a task to the database. Returns the fields generated for this reminder.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task of the reminder doesn't exist.
+    /// Returns `DbError::DurationOutOfRangeError` if the offset of the trigger is out of range
+    /// (see `ReminderTrigger::is_valid`).
+    pub fn add_new_reminder(&mut self, data: &EditableReminderData) -> DbResult<GeneratedReminderData> {
+        if self.task_by_id(data.task_id)?.is_none() {
+            return Err(DbError::TaskDoesNotExistError { id: data.task_id });
+        }
+        if !data.trigger.is_valid() {
+            return Err(DbError::DurationOutOfRangeError);
+        }
+        let (kind, value) = match &data.trigger {
+            ReminderTrigger::At { time } => ("at", MyDateTime::from(*time).0.timestamp()),
+            ReminderTrigger::BeforeDue { offset_seconds } => ("before_due", *offset_seconds),
+            ReminderTrigger::BeforeTarget { offset_seconds } => ("before_target", *offset_seconds),
+        };
+        let tx = self.conn.savepoint()?;
+        tx.execute(&format!(
+            "INSERT INTO {} (task_id, kind, value, dismissed) values (?1, ?2, ?3, ?4);", Db::REMINDER_TABLE
+        ), (data.task_id, kind, value, false))?;
+        let new_id = tx.last_insert_rowid();
+        tx.commit()?;
+        Ok(GeneratedReminderData {
+            id: new_id,
+        })
+    }
+
+    /// Retrieve the reminder with this id, or `None` if it doesn't exist in the database.
+    pub fn reminder_by_id(&self, id: ReminderId) -> DbResult<Option<Reminder>> {
+        Ok(self.conn.query_row(&format!(
+            "SELECT * FROM {} WHERE id = ?1", Db::REMINDER_TABLE
+        ), (id,), Db::reminder_from_row).optional()?)
+    }
+
+    /// Retrieves the reminders of a task, in order of insertion.
+    pub fn reminders_for_task(&self, task_id: TaskId) -> DbResult<Vec<Reminder>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT * FROM {} WHERE task_id = ?1", Db::REMINDER_TABLE
+        )).unwrap();
+        let iter = stmt.query_map((task_id,), Db::reminder_from_row)?;
+        Ok(iter.map(|reminder| reminder.unwrap()).collect())
+    }
+
+    /// Delete a reminder by its id in the database.
+    ///
+    /// # Failure
+    /// Returns `DbError::ReminderDoesNotExistError` if the reminder doesn't exist in the database.
+    pub fn delete_reminder(&mut self, id: ReminderId) -> DbResult<()> {
+        let rows = self.conn.execute(&format!(
+            "DELETE FROM {} WHERE id = ?1;", Db::REMINDER_TABLE
+        ), (id,))?;
+        Db::check_reminder_rows(id, rows)
+    }
+
+    /// Makes a reminder fire `duration` after `now` instead of at its trigger.
+    ///
+    /// # Failure
+    /// Returns `DbError::ReminderDoesNotExistError` if the reminder doesn't exist in the database.
+    /// Returns `DbError::DurationOutOfRangeError` if `duration` after `now` is out of the range of times.
+    pub fn snooze_reminder(&mut self, id: ReminderId, duration: Duration, now: DateTime<Utc>) -> DbResult<()> {
+        let until = now.checked_add_signed(duration).ok_or(DbError::DurationOutOfRangeError)?;
+        let rows = self.conn.execute(&format!(
+            "UPDATE {} SET snoozed_until = ?2 WHERE id = ?1;", Db::REMINDER_TABLE
+        ), (id, MyDateTime::from(until)))?;
+        Db::check_reminder_rows(id, rows)
+    }
+
+    /// Stops a reminder from ever firing again.
+    ///
+    /// # Failure
+    /// Returns `DbError::ReminderDoesNotExistError` if the reminder doesn't exist in the database.
+    pub fn dismiss_reminder(&mut self, id: ReminderId) -> DbResult<()> {
+        let rows = self.conn.execute(&format!(
+            "UPDATE {} SET dismissed = ?2 WHERE id = ?1;", Db::REMINDER_TABLE
+        ), (id, true))?;
+        Db::check_reminder_rows(id, rows)
+    }
+
+    /// Records that a reminder fired at `time`, so it does not fire again for the same fire time,
+    /// and ends any snooze so it fires at its trigger from then on.
+    ///
+    /// # Failure
+    /// Returns `DbError::ReminderDoesNotExistError` if the reminder doesn't exist in the database.
+    pub fn mark_reminder_fired(&mut self, id: ReminderId, time: DateTime<Utc>) -> DbResult<()> {
+        let rows = self.conn.execute(&format!(
+            "UPDATE {} SET last_fired_time = ?2, snoozed_until = NULL WHERE id = ?1;", Db::REMINDER_TABLE
+        ), (id, MyDateTime::from(time)))?;
+        Db::check_reminder_rows(id, rows)
+    }
+
+    /// Retrieves every reminder that will still fire (see `Reminder::fire_time`) in the timezone of the user,
+    /// including ones whose time has already passed, in order of fire time.
+    pub fn scheduled_reminders(&self) -> DbResult<Vec<ScheduledReminder>> {
+        let timezone = self.user_timezone()?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT * FROM {} WHERE dismissed = 0", Db::REMINDER_TABLE
+        )).unwrap();
+        let reminders: Vec<Reminder> = stmt.query_map((), Db::reminder_from_row)?
+            .map(|reminder| reminder.unwrap())
+            .collect();
+        let mut scheduled = vec![];
+        for reminder in reminders {
+            let task = match self.task_by_id(reminder.task_id)? {
+                Some(task) => task,
+                None => continue,
+            };
+            if let Some(fire_time) = reminder.fire_time(&task, &timezone) {
+                scheduled.push(ScheduledReminder { reminder, task, fire_time });
+            }
+        }
+        scheduled.sort_by_key(|scheduled| scheduled.fire_time);
+        Ok(scheduled)
+    }
+
+    fn check_reminder_rows(id: ReminderId, rows: usize) -> DbResult<()> {
+        match rows {
+            0 => Err(DbError::ReminderDoesNotExistError { id }),
+            1 => Ok(()),
+            other => panic!("Reminder command changed {} rows!", other),
+        }
+    }
+
+    fn reminder_from_row(row: &Row) -> rusqlite::Result<Reminder> {
+        let value: i64 = row.get("value")?;
+        let kind: String = row.get("kind")?;
+        let trigger = match kind.as_str() {
+            "at" => ReminderTrigger::At { time: row.get::<_, MyDateTime>("value")?.into() },
+            "before_due" => ReminderTrigger::BeforeDue { offset_seconds: value },
+            "before_target" => ReminderTrigger::BeforeTarget { offset_seconds: value },
+            other => return Err(rusqlite::Error::FromSqlConversionFailure(
+                2, Type::Text, format!("Unknown reminder kind {other}").into(),
+            )),
+        };
+        Ok(Reminder {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            trigger,
+            snoozed_until: row.get::<_, Option<MyDateTime>>("snoozed_until")?.map(DateTime::from),
+            dismissed: row.get("dismissed")?,
+            last_fired_time: row.get::<_, Option<MyDateTime>>("last_fired_time")?.map(DateTime::from),
+        })
+    }
+}
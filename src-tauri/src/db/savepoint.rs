@@ -0,0 +1,64 @@
+use std::ops::{Deref, DerefMut};
+use crate::{Db, DbResult};
+
+/// A savepoint opened with plain SQL, so that methods of `Db` opening their own savepoints
+/// can be called inside it through `Deref`.
+///
+/// Unless `commit` succeeds, the savepoint is rolled back and released when dropped,
+/// whichever way the caller returns, so the connection is never left inside it.
+pub(super) struct DbSavepoint<'a> {
+    db: &'a mut Db,
+    name: &'static str,
+    finished: bool,
+}
+
+impl<'a> DbSavepoint<'a> {
+    pub(super) fn new(db: &'a mut Db, name: &'static str) -> DbResult<Self> {
+        db.conn.execute_batch(&format!("SAVEPOINT {name};"))?;
+        Ok(Self { db, name, finished: false })
+    }
+
+    /// Keeps the changes made inside the savepoint.
+    pub(super) fn commit(mut self) -> DbResult<()> {
+        self.db.conn.execute_batch(&format!("RELEASE {};", self.name))?;
+        self.finished = true;
+        Ok(())
+    }
+
+    /// Undoes the changes made inside the savepoint.
+    pub(super) fn rollback(mut self) -> DbResult<()> {
+        self.finish_rollback()
+    }
+
+    /// Rolls back to the savepoint, then releases it even if the rollback failed.
+    fn finish_rollback(&mut self) -> DbResult<()> {
+        self.finished = true;
+        let rollback = self.db.conn.execute_batch(&format!("ROLLBACK TO {};", self.name));
+        let release = self.db.conn.execute_batch(&format!("RELEASE {};", self.name));
+        rollback?;
+        release?;
+        Ok(())
+    }
+}
+
+impl Deref for DbSavepoint<'_> {
+    type Target = Db;
+
+    fn deref(&self) -> &Db {
+        self.db
+    }
+}
+
+impl DerefMut for DbSavepoint<'_> {
+    fn deref_mut(&mut self) -> &mut Db {
+        self.db
+    }
+}
+
+impl Drop for DbSavepoint<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            _ = self.finish_rollback();
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use chrono::NaiveDate;
+use crate::{Db, DbError, DbResult, MAX_RANGE_DAYS, MyDateTime, OnTimeStats, PeriodStats, StatsPeriod, SummaryStats, TagStats, all_day_date, day_start, local_date};
+use crate::stats::ratio;
+
+impl Db {
+    /// Counts the tasks created and completed in each period overlapping `first` to `last` inclusive,
+    /// in the timezone of the user. Periods are cut off at the ends of the range.
+    ///
+    /// # Failure
+    /// Returns `DbError::InvalidDateRangeError` if `first` is after `last`, or the range is longer than `MAX_RANGE_DAYS`.
+    /// Returns `DbError::DateOutOfRangeError` if the range reaches the ends of the range of dates.
+    pub fn period_stats(&self, period: StatsPeriod, first: NaiveDate, last: NaiveDate) -> DbResult<Vec<PeriodStats>> {
+        Db::check_date_range(first, last)?;
+        let ranges = period.ranges(first, last).ok_or(DbError::DateOutOfRangeError)?;
+        let timezone = self.user_timezone()?;
+        let mut stmt = self.conn.prepare(&format!(r#"
+            SELECT
+                (SELECT count(*) FROM {0} WHERE create_time >= ?1 AND create_time < ?2) AS created,
+                count(*) AS completed,
+                coalesce(sum(difficulty), 0) AS difficulty_completed,
+                coalesce(sum(estimate_minutes), 0) AS estimate_minutes_completed
+            FROM {0} WHERE done_time >= ?1 AND done_time < ?2
+        "#, Db::TASK_TABLE)).unwrap();
+        let mut stats = vec![];
+        for (start, from, to) in ranges {
+            let range = (MyDateTime::from(day_start(&timezone, from)), MyDateTime::from(day_start(&timezone, to)));
+            stats.push(stmt.query_row(range, |row| Ok(PeriodStats {
+                start,
+                created: row.get("created")?,
+                completed: row.get("completed")?,
+                difficulty_completed: row.get("difficulty_completed")?,
+                estimate_minutes_completed: row.get("estimate_minutes_completed")?,
+            }))?);
+        }
+        Ok(stats)
+    }
+
+    /// Summarizes the tasks created and completed from `first` to `last` inclusive,
+    /// in the timezone of the user.
+    ///
+    /// # Failure
+    /// Returns `DbError::InvalidDateRangeError` if `first` is after `last`, or the range is longer than `MAX_RANGE_DAYS`.
+    /// Returns `DbError::DateOutOfRangeError` if the range reaches the ends of the range of dates.
+    pub fn summary_stats(&self, first: NaiveDate, last: NaiveDate) -> DbResult<SummaryStats> {
+        let end = Db::check_date_range(first, last)?;
+        let timezone = self.user_timezone()?;
+        let range = (
+            MyDateTime::from(day_start(&timezone, first)),
+            MyDateTime::from(day_start(&timezone, end)),
+        );
+        let created: i64 = self.conn.query_row(&format!(
+            "SELECT count(*) FROM {} WHERE create_time >= ?1 AND create_time < ?2", Db::TASK_TABLE
+        ), range, |row| row.get(0))?;
+        let (completed, average_lead_seconds): (i64, Option<f64>) = self.conn.query_row(&format!(
+            "SELECT count(*), avg(done_time - create_time) FROM {} WHERE done_time >= ?1 AND done_time < ?2",
+            Db::TASK_TABLE
+        ), range, |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+        let on_time = |deadline: &str, condition: &str| -> DbResult<(i64, i64)> {
+            Ok(self.conn.query_row(&format!(r#"
+                SELECT count(*), coalesce(sum(done_time <= {1}), 0) FROM {0}
+                WHERE done_time >= ?1 AND done_time < ?2 AND {1} IS NOT NULL {2}
+            "#, Db::TASK_TABLE, deadline, condition), range, |row| Ok((row.get(0)?, row.get(1)?)))?)
+        };
+        let (due_finished, due_on_time) = on_time("due_time", "AND due_all_day = 0")?;
+        let (target_finished, target_on_time) = on_time("target_time", "")?;
+        // all-day due dates depend on the timezone of the user, so are compared as dates here
+        let mut stmt = self.conn.prepare(&format!(r#"
+            SELECT done_time, due_time FROM {}
+            WHERE done_time >= ?1 AND done_time < ?2 AND due_time IS NOT NULL AND due_all_day = 1
+        "#, Db::TASK_TABLE)).unwrap();
+        let all_day: Vec<(MyDateTime, MyDateTime)> = stmt
+            .query_map(range, |row| Ok((row.get(0)?, row.get(1)?)))?
+            .map(|times| times.unwrap())
+            .collect();
+        let all_day_on_time = all_day.iter()
+            .filter(|(done_time, due_time)| local_date(&timezone, &done_time.0) <= all_day_date(&due_time.0))
+            .count() as i64;
+
+        Ok(SummaryStats {
+            created,
+            completed,
+            completion_rate: ratio(completed, created),
+            average_lead_seconds,
+            due: OnTimeStats::new(due_finished + all_day.len() as i64, due_on_time + all_day_on_time),
+            target: OnTimeStats::new(target_finished, target_on_time),
+        })
+    }
+
+    /// Counts the tasks completed from `first` to `last` inclusive per tag, in the timezone of the user,
+    /// in order of tag id with untagged tasks first.
+    ///
+    /// # Failure
+    /// Returns `DbError::InvalidDateRangeError` if `first` is after `last`, or the range is longer than `MAX_RANGE_DAYS`.
+    /// Returns `DbError::DateOutOfRangeError` if the range reaches the ends of the range of dates.
+    pub fn tag_stats(&self, first: NaiveDate, last: NaiveDate) -> DbResult<Vec<TagStats>> {
+        let end = Db::check_date_range(first, last)?;
+        let timezone = self.user_timezone()?;
+        let range = (
+            MyDateTime::from(day_start(&timezone, first)),
+            MyDateTime::from(day_start(&timezone, end)),
+        );
+        let mut stmt = self.conn.prepare(&format!(r#"
+            SELECT tag_id, count(*) AS completed, sum(difficulty) AS difficulty_completed,
+                coalesce(sum(estimate_minutes), 0) AS estimate_minutes_completed
+            FROM {} LEFT JOIN {} ON id = task_id
+            WHERE done_time >= ?1 AND done_time < ?2
+            GROUP BY tag_id ORDER BY tag_id
+        "#, Db::TASK_TABLE, Db::TAG_TASK_TABLE)).unwrap();
+        let iter = stmt.query_map(range, |row| Ok(TagStats {
+            tag_id: row.get("tag_id")?,
+            completed: row.get("completed")?,
+            difficulty_completed: row.get("difficulty_completed")?,
+            estimate_minutes_completed: row.get("estimate_minutes_completed")?,
+        }))?;
+        Ok(iter.map(|stats| stats.unwrap()).collect())
+    }
+
+    /// Checks that `first` to `last` inclusive is a range of days statistics can be computed over,
+    /// returning the day after `last`.
+    ///
+    /// # Failure
+    /// Returns `DbError::InvalidDateRangeError` if `first` is after `last`,
+    /// or the range is longer than `MAX_RANGE_DAYS`.
+    /// Returns `DbError::DateOutOfRangeError` if `last` is the last date there is.
+    pub(super) fn check_date_range(first: NaiveDate, last: NaiveDate) -> DbResult<NaiveDate> {
+        if first > last || (last - first).num_days() >= MAX_RANGE_DAYS {
+            return Err(DbError::InvalidDateRangeError);
+        }
+        last.succ_opt().ok_or(DbError::DateOutOfRangeError)
+    }
+}
@@ -0,0 +1,206 @@
+use rusqlite::{Connection, OptionalExtension};
+use crate::{Db, DbError, DbResult, EditableTaskData, GeneratedTaskData, MyDateTime, Task, TaskEvent, TaskId, merge_bodies, rank_after, rank_between, replace_id_references, split_body};
+
+impl Db {
+    /// Adds a copy of a task just after it in the manual order, created now and not done,
+    /// with a copy of its checklist with every item unchecked. The copy has the same tag only if `keep_tag`.
+    ///
+    /// Returns the fields generated for the copy.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    pub fn duplicate_task(&mut self, id: TaskId, keep_tag: bool) -> DbResult<GeneratedTaskData> {
+        let task = self.task_by_id(id)?.ok_or(DbError::TaskDoesNotExistError { id })?;
+        let mut data = Db::editable_task_data(&task);
+        if !keep_tag {
+            data.tag = None;
+        }
+        let now = MyDateTime::now();
+        let tx = self.conn.savepoint()?;
+        let ranks = Db::ranks_after(&tx, id, 1)?;
+        let new_id = Db::insert_task(&tx, &data, &now)?;
+        Db::set_task_ranks(&tx, &[new_id], &ranks)?;
+        tx.execute(&format!(r#"
+            INSERT INTO {0} (task_id, text, checked, position)
+            SELECT ?2, text, 0, position FROM {0} WHERE task_id = ?1;
+        "#, Db::CHECKLIST_TABLE), (id, new_id))?;
+        tx.commit()?;
+        Ok(GeneratedTaskData {
+            id: new_id,
+            create_time: now.into(),
+            last_edit_time: now.into(),
+            done_time: None,
+        })
+    }
+
+    /// Splits lines out of the body of a task into new tasks (see `split_body`), which have its tag
+    /// and difficulty and follow it in the manual order. The task is edited to the rest of its body.
+    ///
+    /// Returns the fields generated for the new tasks, in order of their lines.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    /// Returns `DbError::LineDoesNotExistError` if one of `lines` is past the end of its body.
+    pub fn split_task(&mut self, id: TaskId, lines: &[usize]) -> DbResult<Vec<GeneratedTaskData>> {
+        let task = self.task_by_id(id)?.ok_or(DbError::TaskDoesNotExistError { id })?;
+        let (body, titles) = match split_body(&task.body, lines) {
+            Some(split) => split,
+            None => return Err(DbError::LineDoesNotExistError {
+                id,
+                line: lines.iter().copied().max().unwrap_or_default(),
+            }),
+        };
+        let now = MyDateTime::now();
+        let tx = self.conn.savepoint()?;
+        let ranks = Db::ranks_after(&tx, id, titles.len())?;
+        let mut new_ids = vec![];
+        for title in titles {
+            let data = EditableTaskData {
+                title,
+                tag: task.tag,
+                body: String::new(),
+                difficulty: task.difficulty,
+                due_time: None,
+                due_all_day: false,
+                target_time: None,
+                paused: false,
+            };
+            new_ids.push(Db::insert_task(&tx, &data, &now)?);
+        }
+        Db::set_task_ranks(&tx, &new_ids, &ranks)?;
+        Db::set_task_body(&tx, id, &body, &now)?;
+        tx.commit()?;
+        Ok(new_ids.into_iter()
+            .map(|new_id| GeneratedTaskData {
+                id: new_id,
+                create_time: now.into(),
+                last_edit_time: now.into(),
+                done_time: None,
+            })
+            .collect())
+    }
+
+    /// Merges tasks into the task `into`, which is edited to have:
+    /// * Its body followed by the titles and bodies of the others (see `merge_bodies`)
+    /// * The earliest create time and the latest due time of all of them
+    /// * Its own tag, or if it has none the first tag of the others, since a task has one tag
+    /// * The checklist items, comments, attachments, time entries, reminders, time blocks
+    ///   and focus sessions of all of them
+    /// * Its own project and board cards, or those of the others where it has none
+    ///
+    /// The other tasks are then deleted. Links to their ids now point at `into`,
+    /// while links to their titles are broken. Returns the merged task.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if one of the tasks doesn't exist in the database.
+    pub fn merge_tasks(&mut self, into: TaskId, others: &[TaskId]) -> DbResult<Task> {
+        let task = self.task_by_id(into)?.ok_or(DbError::TaskDoesNotExistError { id: into })?;
+        let mut merged = vec![];
+        for id in others {
+            if *id != into && !merged.iter().any(|other: &Task| other.id == *id) {
+                merged.push(self.task_by_id(*id)?.ok_or(DbError::TaskDoesNotExistError { id: *id })?);
+            }
+        }
+        let body = merged.iter().fold(
+            merge_bodies(&task.body, &merged.iter()
+                .map(|other| (other.title.as_str(), other.body.as_str()))
+                .collect::<Vec<_>>()),
+            |body, other| replace_id_references(&body, other.id, into));
+        let create_time = merged.iter().map(|other| other.create_time).fold(task.create_time, |earliest, time| earliest.min(time));
+        let latest_due = std::iter::once(&task).chain(merged.iter())
+            .filter(|task| task.due_time.is_some())
+            .max_by_key(|task| task.due_time);
+        let tag = task.tag.or_else(|| merged.iter().find_map(|other| other.tag));
+
+        let now = MyDateTime::now();
+        let tx = self.conn.savepoint()?;
+        Db::set_task_body(&tx, into, &body, &now)?;
+        tx.execute(&format!(
+            "UPDATE {} SET create_time = ?2, due_time = ?3, due_all_day = ?4 WHERE id = ?1;", Db::TASK_TABLE
+        ), (into, MyDateTime::from(create_time),
+            latest_due.and_then(|task| task.due_time).map(MyDateTime::from),
+            matches!(latest_due, Some(task) if task.due_all_day)))?;
+        if tag != task.tag {
+            tx.execute(&format!(
+                "INSERT INTO {} (task_id, tag_id) values (?1, ?2);", Db::TAG_TASK_TABLE
+            ), (into, tag))?;
+            Db::record_task_event(&tx, into, now.into(), &TaskEvent::TagChanged { from: task.tag, to: tag })?;
+        }
+        for other in &merged {
+            let checklist_length: i64 = tx.query_row(&format!(
+                "SELECT count(*) FROM {} WHERE task_id = ?1", Db::CHECKLIST_TABLE
+            ), (into,), |row| row.get(0))?;
+            tx.execute(&format!(
+                "UPDATE {} SET task_id = ?2, position = position + ?3 WHERE task_id = ?1;", Db::CHECKLIST_TABLE
+            ), (other.id, into, checklist_length))?;
+            for table in [Db::COMMENT_TABLE, Db::ATTACHMENT_TABLE, Db::TIME_ENTRY_TABLE, Db::REMINDER_TABLE,
+                          Db::TIME_BLOCK_TABLE, Db::FOCUS_SESSION_TABLE, Db::FOCUS_STATE_TABLE] {
+                tx.execute(&format!(
+                    "UPDATE {} SET task_id = ?2 WHERE task_id = ?1;", table
+                ), (other.id, into))?;
+            }
+            // a task is in one project and has one card per board, so those of `into` are kept
+            for table in [Db::PROJECT_TASK_TABLE, Db::BOARD_CARD_TABLE] {
+                tx.execute(&format!(
+                    "UPDATE OR IGNORE {} SET task_id = ?2 WHERE task_id = ?1;", table
+                ), (other.id, into))?;
+            }
+            Db::redirect_task_links(&tx, other.id, into)?;
+            Db::delete_task_rows(&tx, other.id)?;
+        }
+        tx.commit()?;
+        Ok(self.task_by_id(into)?.unwrap())
+    }
+
+    pub(super) fn editable_task_data(task: &Task) -> EditableTaskData {
+        EditableTaskData {
+            title: task.title.clone(),
+            tag: task.tag,
+            body: task.body.clone(),
+            difficulty: task.difficulty,
+            due_time: task.due_time,
+            due_all_day: task.due_all_day,
+            target_time: task.target_time,
+            paused: task.paused,
+        }
+    }
+
+    /// `count` rank keys in order between the task `after` and the task following it in the manual order.
+    fn ranks_after(tx: &Connection, after: TaskId, count: usize) -> rusqlite::Result<Vec<String>> {
+        let mut previous: String = tx.query_row(&format!(
+            "SELECT rank FROM {} WHERE id = ?1", Db::TASK_TABLE
+        ), (after,), |row| row.get(0))?;
+        let next: Option<String> = tx.query_row(&format!(
+            "SELECT rank FROM {} WHERE rank > ?1 ORDER BY rank LIMIT 1", Db::TASK_TABLE
+        ), (&previous,), |row| row.get(0)).optional()?;
+        let mut ranks = vec![];
+        for _ in 0..count {
+            previous = match &next {
+                Some(next) => rank_between(Some(&previous), Some(next)),
+                None => rank_after(Some(&previous)),
+            };
+            ranks.push(previous.clone());
+        }
+        Ok(ranks)
+    }
+
+    /// Gives new tasks the rank keys from `ranks_after`.
+    fn set_task_ranks(tx: &Connection, ids: &[TaskId], ranks: &[String]) -> rusqlite::Result<()> {
+        for (id, rank) in ids.iter().zip(ranks) {
+            tx.execute(&format!(
+                "UPDATE {} SET rank = ?2 WHERE id = ?1;", Db::TASK_TABLE
+            ), (id, rank))?;
+        }
+        Ok(())
+    }
+
+    /// Sets the body of a task as edited at `now`, keeping its links and rendered body up to date.
+    pub(super) fn set_task_body(tx: &Connection, id: TaskId, body: &str, now: &MyDateTime) -> DbResult<()> {
+        tx.execute(&format!(
+            "UPDATE {} SET body = ?2, last_edit_time = ?3 WHERE id = ?1;", Db::TASK_TABLE
+        ), (id, body, now))?;
+        Db::update_task_links(tx, id, body)?;
+        Db::invalidate_rendered_body(tx, id)?;
+        Db::record_task_event(tx, id, (*now).into(), &TaskEvent::Edited)
+    }
+}
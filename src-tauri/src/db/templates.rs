@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use chrono::{DateTime, TimeZone};
+use rusqlite::{Connection, OptionalExtension, Row};
+use crate::{Db, DbError, DbResult, EditableTemplateData, GeneratedTaskData, GeneratedTemplateData, Template, TemplateId, TemplateInstance};
+use crate::db::savepoint::DbSavepoint;
+
+impl Db {
+    /// Add a new template to the database, initializing its id.
+    ///
+    /// Returns the fields generated for this template.
+    ///
+    /// # Failure
+    /// Returns `DbError::TagDoesNotExistError` if the default tag doesn't exist in the database.
+    /// Returns `DbError::DurationOutOfRangeError` if an offset is not valid (see `EditableTemplateData::is_valid`).
+    pub fn add_new_template(&mut self, data: &EditableTemplateData) -> DbResult<GeneratedTemplateData> {
+        self.check_template_data(data)?;
+        let tx = self.conn.savepoint()?;
+        tx.execute(&format!(r#"
+            INSERT INTO {}
+            (name, title, body, tag_id, difficulty, due_offset, due_all_day, target_offset) values
+            (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8);
+        "#, Db::TEMPLATE_TABLE), (&data.name, &data.title, &data.body, data.tag, data.difficulty,
+                                  data.due_offset_seconds, data.due_all_day, data.target_offset_seconds))?;
+        let new_id = tx.last_insert_rowid();
+        Db::insert_template_checklist(&tx, new_id, &data.checklist)?;
+        tx.commit()?;
+        Ok(GeneratedTemplateData {
+            id: new_id,
+        })
+    }
+
+    /// Retrieves all templates stored in this database, in order of insertion.
+    pub fn all_templates(&self) -> DbResult<Vec<Template>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT * FROM {} ORDER BY id", Db::TEMPLATE_TABLE
+        )).unwrap();
+        let iter = stmt.query_map([], Db::template_from_row)?;
+        iter.map(|template| self.with_template_checklist(template.unwrap())).collect()
+    }
+
+    /// Retrieve the template with this id, or `None` if the template doesn't exist in the database.
+    pub fn template_by_id(&self, id: TemplateId) -> DbResult<Option<Template>> {
+        let template = self.conn.query_row(&format!(
+            "SELECT * FROM {} WHERE id = ?1", Db::TEMPLATE_TABLE
+        ), (id,), Db::template_from_row).optional()?;
+        template.map(|template| self.with_template_checklist(template)).transpose()
+    }
+
+    /// Modifies an existing template in the database. Tasks made from it are unaffected.
+    ///
+    /// # Failure
+    /// Returns `DbError::TemplateDoesNotExistError` if the template doesn't exist in the database.
+    /// Returns `DbError::TagDoesNotExistError` if the default tag doesn't exist in the database.
+    /// Returns `DbError::DurationOutOfRangeError` if an offset is not valid (see `EditableTemplateData::is_valid`).
+    pub fn modify_template(&mut self, id: TemplateId, data: &EditableTemplateData) -> DbResult<()> {
+        self.check_template_data(data)?;
+        let tx = self.conn.savepoint()?;
+        let rows = tx.execute(&format!(r#"
+                UPDATE {} SET
+                    name = ?2,
+                    title = ?3,
+                    body = ?4,
+                    tag_id = ?5,
+                    difficulty = ?6,
+                    due_offset = ?7,
+                    due_all_day = ?8,
+                    target_offset = ?9
+                WHERE id = ?1;
+            "#, Db::TEMPLATE_TABLE),
+            (id, &data.name, &data.title, &data.body, data.tag, data.difficulty,
+             data.due_offset_seconds, data.due_all_day, data.target_offset_seconds))?;
+        if rows == 0 {
+            return Err(DbError::TemplateDoesNotExistError { id });
+        } else if rows > 1 {
+            panic!("Modify template changed {} rows!", rows);
+        }
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE template_id = ?1;", Db::TEMPLATE_CHECKLIST_TABLE
+        ), (id,))?;
+        Db::insert_template_checklist(&tx, id, &data.checklist)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Delete a template by its id in the database. Tasks made from it are kept.
+    ///
+    /// # Failure
+    /// Returns `DbError::TemplateDoesNotExistError` if the template doesn't exist in the database.
+    pub fn delete_template(&mut self, id: TemplateId) -> DbResult<()> {
+        let tx = self.conn.savepoint()?;
+        let rows = tx.execute(&format!(
+            "DELETE FROM {} WHERE id = ?1;", Db::TEMPLATE_TABLE
+        ), (id,))?;
+        if rows == 0 {
+            return Err(DbError::TemplateDoesNotExistError { id });
+        } else if rows > 1 {
+            panic!("Delete template changed {} rows!", rows);
+        }
+        tx.execute(&format!(
+            "DELETE FROM {} WHERE template_id = ?1;", Db::TEMPLATE_CHECKLIST_TABLE
+        ), (id,))?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// The task a template makes at `now`, with its placeholders filled from `values`
+    /// (see `Template::instantiate`). Nothing is added to the database.
+    ///
+    /// # Failure
+    /// Returns `DbError::TemplateDoesNotExistError` if the template doesn't exist in the database.
+    /// Returns `DbError::DurationOutOfRangeError` if an offset moves a time out of the range of times.
+    pub fn instantiate_template<Tz: TimeZone>(&self, id: TemplateId, values: &HashMap<String, String>, now: &DateTime<Tz>)
+                                              -> DbResult<TemplateInstance> {
+        let template = self.template_by_id(id)?.ok_or(DbError::TemplateDoesNotExistError { id })?;
+        template.instantiate(now, values).ok_or(DbError::DurationOutOfRangeError)
+    }
+
+    /// Adds the task a template makes at `now` along with its checklist, all or nothing.
+    ///
+    /// Returns the fields generated for this task.
+    ///
+    /// # Failure
+    /// Returns `DbError::TemplateDoesNotExistError` if the template doesn't exist in the database.
+    /// Returns `DbError::DurationOutOfRangeError` if an offset moves a time out of the range of times.
+    pub fn add_task_from_template<Tz: TimeZone>(&mut self, id: TemplateId, values: &HashMap<String, String>, now: &DateTime<Tz>)
+                                                -> DbResult<GeneratedTaskData> {
+        let instance = self.instantiate_template(id, values, now)?;
+        let mut savepoint = DbSavepoint::new(self, "add_task_from_template")?;
+        let generated = savepoint.add_new_task(&instance.data)?;
+        for item in &instance.checklist {
+            savepoint.add_checklist_item(generated.id, item)?;
+        }
+        savepoint.commit()?;
+        Ok(generated)
+    }
+
+    fn check_template_data(&self, data: &EditableTemplateData) -> DbResult<()> {
+        if !data.is_valid() {
+            return Err(DbError::DurationOutOfRangeError);
+        }
+        if let Some(tag_id) = data.tag {
+            if self.tag_by_id(tag_id)?.is_none() {
+                return Err(DbError::TagDoesNotExistError { id: tag_id });
+            }
+        }
+        Ok(())
+    }
+
+    fn insert_template_checklist(tx: &Connection, id: TemplateId, checklist: &[String]) -> rusqlite::Result<()> {
+        for (position, text) in checklist.iter().enumerate() {
+            tx.execute(&format!(
+                "INSERT INTO {} (template_id, position, text) values (?1, ?2, ?3);", Db::TEMPLATE_CHECKLIST_TABLE
+            ), (id, position as i64, text))?;
+        }
+        Ok(())
+    }
+
+    fn with_template_checklist(&self, mut template: Template) -> DbResult<Template> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT text FROM {} WHERE template_id = ?1 ORDER BY position", Db::TEMPLATE_CHECKLIST_TABLE
+        )).unwrap();
+        let iter = stmt.query_map((template.id,), |row| row.get(0))?;
+        template.checklist = iter.map(|text| text.unwrap()).collect();
+        Ok(template)
+    }
+
+    fn template_from_row(row: &Row) -> rusqlite::Result<Template> {
+        Ok(Template {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            title: row.get("title")?,
+            body: row.get("body")?,
+            tag: row.get("tag_id")?,
+            difficulty: row.get("difficulty")?,
+            due_offset_seconds: row.get("due_offset")?,
+            due_all_day: row.get("due_all_day")?,
+            target_offset_seconds: row.get("target_offset")?,
+            checklist: vec![],
+        })
+    }
+}
@@ -0,0 +1,202 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{Connection, OptionalExtension, Params, Row};
+use crate::{Db, DbError, DbResult, DayTimeSpent, EditableTimeEntryData, GeneratedTimeEntryData, MyDateTime, TagId, TaskId, TimeEntry, TimeEntryId, day_bounds};
+
+impl Db {
+    /// Starts a timer on a task at `now`, stopping the running timer first if there is one.
+    /// A running timer that starts after `now` is stopped at its start instead.
+    /// Returns the new running entry.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    pub fn start_timer(&mut self, task_id: TaskId, now: DateTime<Utc>) -> DbResult<TimeEntry> {
+        if self.task_by_id(task_id)?.is_none() {
+            return Err(DbError::TaskDoesNotExistError { id: task_id });
+        }
+        let now = MyDateTime::from(now);
+        let tx = self.conn.savepoint()?;
+        tx.execute(&format!(
+            "UPDATE {} SET end_time = max(start_time, ?1) WHERE end_time IS NULL;", Db::TIME_ENTRY_TABLE
+        ), (&now,))?;
+        tx.execute(&format!(
+            "INSERT INTO {} (task_id, start_time) values (?1, ?2);", Db::TIME_ENTRY_TABLE
+        ), (task_id, &now))?;
+        let new_id = tx.last_insert_rowid();
+        tx.commit()?;
+        Ok(TimeEntry {
+            id: new_id,
+            task_id,
+            start_time: now.into(),
+            end_time: None,
+        })
+    }
+
+    /// Stops the running timer at `now`. Returns the stopped entry, or `None` if no timer was running.
+    pub fn stop_timer(&mut self, now: DateTime<Utc>) -> DbResult<Option<TimeEntry>> {
+        let running = match self.running_timer()? {
+            Some(running) => running,
+            None => return Ok(None),
+        };
+        let end_time = MyDateTime::from(now.max(running.start_time));
+        self.conn.execute(&format!(
+            "UPDATE {} SET end_time = ?2 WHERE id = ?1;", Db::TIME_ENTRY_TABLE
+        ), (running.id, &end_time))?;
+        Ok(Some(TimeEntry {
+            end_time: Some(end_time.into()),
+            ..running
+        }))
+    }
+
+    /// Stops the timer of a task at `time` if it is running, as part of the transaction finishing the task.
+    pub(super) fn stop_task_timer(tx: &Connection, task_id: TaskId, time: DateTime<Utc>) -> DbResult<()> {
+        tx.execute(&format!(
+            "UPDATE {} SET end_time = max(start_time, ?2) WHERE task_id = ?1 AND end_time IS NULL;", Db::TIME_ENTRY_TABLE
+        ), (task_id, MyDateTime::from(time)))?;
+        Ok(())
+    }
+
+    /// Retrieves the running timer, or `None` if no timer is running.
+    pub fn running_timer(&self) -> DbResult<Option<TimeEntry>> {
+        Ok(self.conn.query_row(&format!(
+            "SELECT * FROM {} WHERE end_time IS NULL", Db::TIME_ENTRY_TABLE
+        ), (), Db::time_entry_from_row).optional()?)
+    }
+
+    /// Add a time entry to the database, such as time worked on a task away from the application.
+    /// Returns the fields generated for this entry.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task of the entry doesn't exist.
+    /// Returns `DbError::InvalidTimeEntryError` if the entry ends before it starts.
+    /// Returns `DbError::TimerRunningError` if the entry has no end while another timer is running.
+    pub fn add_time_entry(&mut self, data: &EditableTimeEntryData) -> DbResult<GeneratedTimeEntryData> {
+        self.check_time_entry(None, data)?;
+        let tx = self.conn.savepoint()?;
+        tx.execute(&format!(
+            "INSERT INTO {} (task_id, start_time, end_time) values (?1, ?2, ?3);", Db::TIME_ENTRY_TABLE
+        ), (data.task_id, MyDateTime::from(data.start_time), data.end_time.map(MyDateTime::from)))?;
+        let new_id = tx.last_insert_rowid();
+        tx.commit()?;
+        Ok(GeneratedTimeEntryData {
+            id: new_id,
+        })
+    }
+
+    /// Retrieve the time entry with this id, or `None` if it doesn't exist in the database.
+    pub fn time_entry_by_id(&self, id: TimeEntryId) -> DbResult<Option<TimeEntry>> {
+        Ok(self.conn.query_row(&format!(
+            "SELECT * FROM {} WHERE id = ?1", Db::TIME_ENTRY_TABLE
+        ), (id,), Db::time_entry_from_row).optional()?)
+    }
+
+    /// Retrieves the time entries of a task, in order of start time.
+    pub fn time_entries_for_task(&self, task_id: TaskId) -> DbResult<Vec<TimeEntry>> {
+        self.time_entries_where("task_id = ?1", (task_id,))
+    }
+
+    /// Modifies an existing time entry in the database.
+    ///
+    /// # Failure
+    /// Returns `DbError::TimeEntryDoesNotExistError` if the entry doesn't exist in the database.
+    /// Otherwise fails the same way as `add_time_entry`.
+    pub fn modify_time_entry(&mut self, id: TimeEntryId, data: &EditableTimeEntryData) -> DbResult<()> {
+        if self.time_entry_by_id(id)?.is_none() {
+            return Err(DbError::TimeEntryDoesNotExistError { id });
+        }
+        self.check_time_entry(Some(id), data)?;
+        self.conn.execute(&format!(r#"
+                UPDATE {} SET
+                    task_id = ?2,
+                    start_time = ?3,
+                    end_time = ?4
+                WHERE id = ?1;
+            "#, Db::TIME_ENTRY_TABLE),
+            (id, data.task_id, MyDateTime::from(data.start_time), data.end_time.map(MyDateTime::from)))?;
+        Ok(())
+    }
+
+    /// Delete a time entry by its id in the database.
+    ///
+    /// # Failure
+    /// Returns `DbError::TimeEntryDoesNotExistError` if the entry doesn't exist in the database.
+    pub fn delete_time_entry(&mut self, id: TimeEntryId) -> DbResult<()> {
+        let rows = self.conn.execute(&format!(
+            "DELETE FROM {} WHERE id = ?1;", Db::TIME_ENTRY_TABLE
+        ), (id,))?;
+        match rows {
+            0 => Err(DbError::TimeEntryDoesNotExistError { id }),
+            1 => Ok(()),
+            other => panic!("Delete time entry changed {} rows!", other),
+        }
+    }
+
+    /// The total seconds spent on a task, counting the running timer up to `now`.
+    pub fn task_time_spent(&self, task_id: TaskId, now: DateTime<Utc>) -> DbResult<i64> {
+        Ok(self.time_entries_for_task(task_id)?.iter().map(|entry| entry.seconds(now)).sum())
+    }
+
+    /// The total seconds spent on the tasks with a tag, counting the running timer up to `now`.
+    pub fn tag_time_spent(&self, tag_id: TagId, now: DateTime<Utc>) -> DbResult<i64> {
+        let entries = self.time_entries_where(&format!(
+            "task_id IN (SELECT task_id FROM {} WHERE tag_id = ?1)", Db::TAG_TASK_TABLE
+        ), (tag_id,))?;
+        Ok(entries.iter().map(|entry| entry.seconds(now)).sum())
+    }
+
+    /// The total seconds spent on all tasks for each day from `first` to `last` inclusive,
+    /// in the timezone of the user. Entries spanning midnight are split between their days,
+    /// and the running timer is counted up to `now`.
+    pub fn time_spent_by_day(&self, first: NaiveDate, last: NaiveDate, now: DateTime<Utc>) -> DbResult<Vec<DayTimeSpent>> {
+        let timezone = self.user_timezone()?;
+        let (from, _) = day_bounds(&timezone, first).ok_or(DbError::DateOutOfRangeError)?;
+        let (_, to) = day_bounds(&timezone, last).ok_or(DbError::DateOutOfRangeError)?;
+        let entries = self.time_entries_where(
+            "start_time < ?2 AND (end_time IS NULL OR end_time > ?1)",
+            (MyDateTime::from(from), MyDateTime::from(to)),
+        )?;
+        let mut days = vec![];
+        let mut date = first;
+        while date <= last {
+            let (start, end) = day_bounds(&timezone, date).ok_or(DbError::DateOutOfRangeError)?;
+            days.push(DayTimeSpent {
+                date,
+                seconds: entries.iter().map(|entry| entry.seconds_between(start, end, now)).sum(),
+            });
+            // `last` has a next day, so does every date before it
+            date = date.succ_opt().unwrap();
+        }
+        Ok(days)
+    }
+
+    /// Checks `data` is valid as the entry with `id`, or a new entry if `id` is `None`.
+    fn check_time_entry(&self, id: Option<TimeEntryId>, data: &EditableTimeEntryData) -> DbResult<()> {
+        if self.task_by_id(data.task_id)?.is_none() {
+            return Err(DbError::TaskDoesNotExistError { id: data.task_id });
+        }
+        match data.end_time {
+            Some(end_time) if end_time < data.start_time => Err(DbError::InvalidTimeEntryError),
+            Some(_) => Ok(()),
+            None => match self.running_timer()? {
+                Some(running) if Some(running.id) != id => Err(DbError::TimerRunningError { id: running.id }),
+                _ => Ok(()),
+            },
+        }
+    }
+
+    fn time_entries_where<P: Params>(&self, condition: &str, params: P) -> DbResult<Vec<TimeEntry>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT * FROM {} WHERE {} ORDER BY start_time, id", Db::TIME_ENTRY_TABLE, condition
+        )).unwrap();
+        let iter = stmt.query_map(params, Db::time_entry_from_row)?;
+        Ok(iter.map(|entry| entry.unwrap()).collect())
+    }
+
+    fn time_entry_from_row(row: &Row) -> rusqlite::Result<TimeEntry> {
+        Ok(TimeEntry {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            start_time: row.get::<_, MyDateTime>("start_time")?.into(),
+            end_time: row.get::<_, Option<MyDateTime>>("end_time")?.map(DateTime::from),
+        })
+    }
+}
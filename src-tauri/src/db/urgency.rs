@@ -0,0 +1,106 @@
+use std::collections::{HashMap, HashSet};
+use chrono::{DateTime, Utc};
+use crate::{Db, DbError, DbResult, TagId, Task, TaskId, UrgencyCoefficients, UrgentTask, sort_by_urgency};
+
+impl Db {
+    const URGENCY_DUE_SETTING: &'static str = "urgency_due";
+    const URGENCY_TARGET_SETTING: &'static str = "urgency_target";
+    const URGENCY_DIFFICULTY_SETTING: &'static str = "urgency_difficulty";
+    const URGENCY_AGE_SETTING: &'static str = "urgency_age";
+    const URGENCY_PAUSED_SETTING: &'static str = "urgency_paused";
+    const URGENCY_BLOCKED_SETTING: &'static str = "urgency_blocked";
+    const URGENCY_TAG_SETTING: &'static str = "urgency_tag";
+    /// Followed by the id of the tag.
+    pub(super) const URGENCY_TAG_WEIGHT_SETTING_PREFIX: &'static str = "urgency_tag_weight_";
+
+    /// The coefficients of the urgency score, with defaults for those never set.
+    pub fn urgency_coefficients(&self) -> DbResult<UrgencyCoefficients> {
+        let defaults = UrgencyCoefficients::default();
+        Ok(UrgencyCoefficients {
+            due: self.parsed_setting(Db::URGENCY_DUE_SETTING)?.unwrap_or(defaults.due),
+            target: self.parsed_setting(Db::URGENCY_TARGET_SETTING)?.unwrap_or(defaults.target),
+            difficulty: self.parsed_setting(Db::URGENCY_DIFFICULTY_SETTING)?.unwrap_or(defaults.difficulty),
+            age: self.parsed_setting(Db::URGENCY_AGE_SETTING)?.unwrap_or(defaults.age),
+            paused: self.parsed_setting(Db::URGENCY_PAUSED_SETTING)?.unwrap_or(defaults.paused),
+            blocked: self.parsed_setting(Db::URGENCY_BLOCKED_SETTING)?.unwrap_or(defaults.blocked),
+            tag: self.parsed_setting(Db::URGENCY_TAG_SETTING)?.unwrap_or(defaults.tag),
+        })
+    }
+
+    /// Sets the coefficients of the urgency score.
+    pub fn set_urgency_coefficients(&mut self, coefficients: &UrgencyCoefficients) -> DbResult<()> {
+        self.set_setting(Db::URGENCY_DUE_SETTING, &coefficients.due.to_string())?;
+        self.set_setting(Db::URGENCY_TARGET_SETTING, &coefficients.target.to_string())?;
+        self.set_setting(Db::URGENCY_DIFFICULTY_SETTING, &coefficients.difficulty.to_string())?;
+        self.set_setting(Db::URGENCY_AGE_SETTING, &coefficients.age.to_string())?;
+        self.set_setting(Db::URGENCY_PAUSED_SETTING, &coefficients.paused.to_string())?;
+        self.set_setting(Db::URGENCY_BLOCKED_SETTING, &coefficients.blocked.to_string())?;
+        self.set_setting(Db::URGENCY_TAG_SETTING, &coefficients.tag.to_string())
+    }
+
+    /// The urgency weight of every tag it was set for.
+    pub fn tag_urgency_weights(&self) -> DbResult<HashMap<TagId, f64>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT key, value FROM {} WHERE key LIKE ?1", Db::SETTINGS_TABLE
+        )).unwrap();
+        let iter = stmt.query_map((format!("{}%", Db::URGENCY_TAG_WEIGHT_SETTING_PREFIX),), |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        Ok(iter
+            .map(|setting| setting.unwrap())
+            .filter_map(|(key, value)| Some((
+                key.strip_prefix(Db::URGENCY_TAG_WEIGHT_SETTING_PREFIX)?.parse().ok()?,
+                value.parse().ok()?,
+            )))
+            .collect())
+    }
+
+    /// Sets how much a tag adds to the urgency of its tasks. Tags default to a weight of 0.
+    ///
+    /// # Failure
+    /// Returns `DbError::TagDoesNotExistError` if the tag doesn't exist in the database.
+    pub fn set_tag_urgency_weight(&mut self, tag_id: TagId, weight: f64) -> DbResult<()> {
+        if self.tag_by_id(tag_id)?.is_none() {
+            return Err(DbError::TagDoesNotExistError { id: tag_id });
+        }
+        self.set_setting(&format!("{}{}", Db::URGENCY_TAG_WEIGHT_SETTING_PREFIX, tag_id), &weight.to_string())
+    }
+
+    /// The tasks that can't be worked on yet: those linking to another task that is not done.
+    /// Links to habits don't count, since a habit is never done.
+    pub fn blocked_tasks(&self) -> DbResult<HashSet<TaskId>> {
+        let mut stmt = self.conn.prepare(&format!(r#"
+            SELECT DISTINCT links.source_id FROM {} links INNER JOIN {} targets ON targets.id = links.target_id
+            WHERE links.target_id != links.source_id AND targets.done_time IS NULL
+                AND links.target_id NOT IN (SELECT task_id FROM {})
+        "#, Db::TASK_LINK_TABLE, Db::TASK_TABLE, Db::HABIT_TABLE)).unwrap();
+        let iter = stmt.query_map((), |row| row.get(0))?;
+        Ok(iter.map(|id| id.unwrap()).collect())
+    }
+
+    /// Applies a filter function to the tasks in this database, returning the tasks that pass it
+    /// along with their urgency at `now`, in their manual order. See `blocked_tasks` for the tasks
+    /// the `blocked` coefficient applies to.
+    pub fn filter_urgent_tasks<P>(&self, predicate: P, now: DateTime<Utc>) -> DbResult<Vec<UrgentTask>>
+        where P: Fn(&Task) -> bool {
+        let coefficients = self.urgency_coefficients()?;
+        let weights = self.tag_urgency_weights()?;
+        let blocked = self.blocked_tasks()?;
+        Ok(self.filter_tasks(predicate)?.into_iter()
+            .map(|task| {
+                let tag_weight = task.tag.and_then(|tag| weights.get(&tag)).copied().unwrap_or(0.0);
+                let urgency = coefficients.score(&task, now, tag_weight, blocked.contains(&task.id));
+                UrgentTask { task, urgency }
+            })
+            .collect())
+    }
+
+    /// Like `filter_urgent_tasks`, but in order from most to least urgent,
+    /// keeping the manual order of equally urgent tasks.
+    pub fn filter_tasks_by_urgency<P>(&self, predicate: P, now: DateTime<Utc>) -> DbResult<Vec<UrgentTask>>
+        where P: Fn(&Task) -> bool {
+        let mut tasks = self.filter_urgent_tasks(predicate, now)?;
+        sort_by_urgency(&mut tasks);
+        Ok(tasks)
+    }
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use crate::{TagId, Task, TaskId};
+use crate::stats::ratio;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+/// How long a task is expected to take, set by `Db::set_task_estimate`.
+pub struct TaskEstimate {
+    pub estimate_minutes: Option<i64>,
+    /// The part of the estimate still left to do, as the task is worked on.
+    pub remaining_minutes: Option<i64>,
+}
+
+impl TaskEstimate {
+    /// The most minutes an estimate or remaining effort can be: a year.
+    pub const MAX_MINUTES: i64 = 366 * 24 * 60;
+
+    /// An estimate must be positive, and remaining effort can't be negative or set without an estimate.
+    /// Neither can be more than `MAX_MINUTES`.
+    pub fn is_valid(&self) -> bool {
+        let estimate_valid = match self.estimate_minutes {
+            Some(estimate) => (1..=TaskEstimate::MAX_MINUTES).contains(&estimate),
+            None => true,
+        };
+        let remaining_valid = match self.remaining_minutes {
+            Some(remaining) => (0..=TaskEstimate::MAX_MINUTES).contains(&remaining) && self.estimate_minutes.is_some(),
+            None => true,
+        };
+        estimate_valid && remaining_valid
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+/// Estimates and tracked time added up over a set of tasks.
+pub struct EstimateTotals {
+    pub tasks: i64,
+    /// How many of the tasks have an estimate.
+    pub estimated: i64,
+    pub estimate_minutes: i64,
+    /// Remaining effort of the tasks; see `Task::remaining_effort_minutes`.
+    pub remaining_minutes: i64,
+    pub tracked_seconds: i64,
+}
+
+impl EstimateTotals {
+    /// Adds a task that has had `tracked_seconds` spent on it.
+    pub fn add(&mut self, task: &Task, tracked_seconds: i64) {
+        self.tasks += 1;
+        if let Some(estimate) = task.estimate_minutes {
+            self.estimated += 1;
+            self.estimate_minutes = self.estimate_minutes.saturating_add(estimate);
+        }
+        self.remaining_minutes = self.remaining_minutes.saturating_add(task.remaining_effort_minutes().unwrap_or_default());
+        self.tracked_seconds = self.tracked_seconds.saturating_add(tracked_seconds);
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// `EstimateTotals` of the tasks with a tag; `tag_id` is `None` for tasks without a tag.
+pub struct TagEstimateTotals {
+    pub tag_id: Option<TagId>,
+    pub totals: EstimateTotals,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// The estimate of a finished task against the time tracked on it.
+pub struct EstimateComparison {
+    pub task_id: TaskId,
+    pub estimate_minutes: i64,
+    pub tracked_seconds: i64,
+    /// `tracked_seconds` minus the estimate in seconds; positive if the task took longer than estimated.
+    pub difference_seconds: i64,
+    /// `tracked_seconds` over the estimate in seconds.
+    pub ratio: f64,
+}
+
+impl EstimateComparison {
+    pub fn new(task_id: TaskId, estimate_minutes: i64, tracked_seconds: i64) -> Self {
+        let estimate_seconds = estimate_minutes.saturating_mul(60);
+        Self {
+            task_id,
+            estimate_minutes,
+            tracked_seconds,
+            difference_seconds: tracked_seconds.saturating_sub(estimate_seconds),
+            ratio: ratio(tracked_seconds, estimate_seconds).unwrap_or_default(),
+        }
+    }
+}
@@ -1,6 +1,10 @@
 use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
 use std::hash::Hash;
+use regex::Regex;
 use serde::{Serialize, Deserialize};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
 
 /// Generic filter on a type.
 pub trait ApplyFilter<T> {
@@ -17,6 +21,26 @@ impl<T: PartialEq> ApplyFilter<T> for ExactlyFilter<T> {
     }
 }
 
+#[derive(Debug, PartialEq, Clone)]
+/// Errors that can occur when constructing a filter from invalid options.
+pub enum FilterError {
+    /// The `pattern` of a regular expression filter failed to compile.
+    InvalidRegexError { pattern: String, message: String },
+    /// The minimum score of a fuzzy filter was not within `0.0..=1.0`.
+    InvalidScoreError { min_score: f64 },
+}
+
+impl Display for FilterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilterError::InvalidRegexError { pattern, message } =>
+                write!(f, "Invalid regular expression `{pattern}`: {message}"),
+            FilterError::InvalidScoreError { min_score } =>
+                write!(f, "Fuzzy filter minimum score {min_score} is not between 0 and 1"),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ContainsStringFilter {
     pub pattern: String
@@ -27,6 +51,214 @@ impl ApplyFilter<String> for ContainsStringFilter {
     }
 }
 
+/// Like `ContainsStringFilter`, but ignores both case and accents,
+/// so `"cafe"` matches `"Café"`.
+#[derive(Serialize, Deserialize)]
+pub struct InsensitiveContainsStringFilter {
+    pub pattern: String
+}
+impl ApplyFilter<String> for InsensitiveContainsStringFilter {
+    fn passes(&self, string: &String) -> bool {
+        fold_string(string).contains(&fold_string(&self.pattern))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrefixStringFilter {
+    pub pattern: String,
+    #[serde(default)]
+    pub ignore_case: bool,
+}
+impl ApplyFilter<String> for PrefixStringFilter {
+    fn passes(&self, string: &String) -> bool {
+        if self.ignore_case {
+            fold_string(string).starts_with(&fold_string(&self.pattern))
+        } else {
+            string.starts_with(&self.pattern)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuffixStringFilter {
+    pub pattern: String,
+    #[serde(default)]
+    pub ignore_case: bool,
+}
+impl ApplyFilter<String> for SuffixStringFilter {
+    fn passes(&self, string: &String) -> bool {
+        if self.ignore_case {
+            fold_string(string).ends_with(&fold_string(&self.pattern))
+        } else {
+            string.ends_with(&self.pattern)
+        }
+    }
+}
+
+/// Passes strings containing a match of a regular expression.
+/// The expression is compiled once on construction (or deserialization),
+/// so an invalid pattern is reported before any task is filtered.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(try_from = "RegexStringFilterData", into = "RegexStringFilterData")]
+pub struct RegexStringFilter {
+    regex: Regex,
+}
+impl RegexStringFilter {
+    /// # Failure
+    /// Returns `FilterError::InvalidRegexError` if `pattern` is not a valid regular expression.
+    pub fn new(pattern: &str) -> Result<Self, FilterError> {
+        match Regex::new(pattern) {
+            Ok(regex) => Ok(Self { regex }),
+            Err(error) => Err(FilterError::InvalidRegexError {
+                pattern: pattern.to_string(),
+                message: error.to_string(),
+            }),
+        }
+    }
+    pub fn pattern(&self) -> &str {
+        self.regex.as_str()
+    }
+}
+impl ApplyFilter<String> for RegexStringFilter {
+    fn passes(&self, string: &String) -> bool {
+        self.regex.is_match(string)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct RegexStringFilterData {
+    pattern: String,
+}
+impl TryFrom<RegexStringFilterData> for RegexStringFilter {
+    type Error = FilterError;
+    fn try_from(value: RegexStringFilterData) -> Result<Self, Self::Error> {
+        RegexStringFilter::new(&value.pattern)
+    }
+}
+impl From<RegexStringFilter> for RegexStringFilterData {
+    fn from(value: RegexStringFilter) -> Self {
+        RegexStringFilterData { pattern: value.pattern().to_string() }
+    }
+}
+
+/// Passes strings which contain the characters of `pattern` in order, ignoring case and accents,
+/// with a `fuzzy_score` of at least `min_score`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(try_from = "FuzzyStringFilterData", into = "FuzzyStringFilterData")]
+pub struct FuzzyStringFilter {
+    pattern: String,
+    min_score: f64,
+}
+impl FuzzyStringFilter {
+    /// # Failure
+    /// Returns `FilterError::InvalidScoreError` if `min_score` is not within `0.0..=1.0`.
+    pub fn new(pattern: &str, min_score: f64) -> Result<Self, FilterError> {
+        if !(0.0..=1.0).contains(&min_score) {
+            return Err(FilterError::InvalidScoreError { min_score });
+        }
+        Ok(Self { pattern: pattern.to_string(), min_score })
+    }
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+    pub fn min_score(&self) -> f64 {
+        self.min_score
+    }
+}
+impl ApplyFilter<String> for FuzzyStringFilter {
+    fn passes(&self, string: &String) -> bool {
+        fuzzy_score(&self.pattern, string).map_or(false, |score| score >= self.min_score)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FuzzyStringFilterData {
+    pattern: String,
+    min_score: f64,
+}
+impl TryFrom<FuzzyStringFilterData> for FuzzyStringFilter {
+    type Error = FilterError;
+    fn try_from(value: FuzzyStringFilterData) -> Result<Self, Self::Error> {
+        FuzzyStringFilter::new(&value.pattern, value.min_score)
+    }
+}
+impl From<FuzzyStringFilter> for FuzzyStringFilterData {
+    fn from(value: FuzzyStringFilter) -> Self {
+        FuzzyStringFilterData { pattern: value.pattern, min_score: value.min_score }
+    }
+}
+
+/// Any of the supported ways to filter a string field.
+#[derive(Serialize, Deserialize)]
+pub enum StringFilter {
+    Contains(ContainsStringFilter),
+    InsensitiveContains(InsensitiveContainsStringFilter),
+    Prefix(PrefixStringFilter),
+    Suffix(SuffixStringFilter),
+    Regex(RegexStringFilter),
+    Fuzzy(FuzzyStringFilter),
+}
+impl ApplyFilter<String> for StringFilter {
+    fn passes(&self, value: &String) -> bool {
+        match self {
+            StringFilter::Contains(filter) => filter.passes(value),
+            StringFilter::InsensitiveContains(filter) => filter.passes(value),
+            StringFilter::Prefix(filter) => filter.passes(value),
+            StringFilter::Suffix(filter) => filter.passes(value),
+            StringFilter::Regex(filter) => filter.passes(value),
+            StringFilter::Fuzzy(filter) => filter.passes(value),
+        }
+    }
+}
+
+/// Lowercases a string and strips its accents, for case and accent insensitive comparisons.
+pub fn fold_string(string: &str) -> String {
+    string.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Scores how well `pattern` fuzzily matches `string`, ignoring case and accents.
+///
+/// Returns `None` if the characters of `pattern` do not all appear in `string` in order.
+/// Otherwise, returns the length of `pattern` divided by the length of the shortest
+/// substring of `string` containing them, so a contiguous match scores `1.0`.
+pub fn fuzzy_score(pattern: &str, string: &str) -> Option<f64> {
+    let pattern: Vec<char> = fold_string(pattern).chars().collect();
+    let string: Vec<char> = fold_string(string).chars().collect();
+    if pattern.is_empty() {
+        return Some(1.0);
+    }
+    let mut shortest_span: Option<usize> = None;
+    for start in 0..string.len() {
+        if string[start] != pattern[0] {
+            continue;
+        }
+        let mut matched = 0;
+        let mut end = start;
+        while end < string.len() {
+            if string[end] == pattern[matched] {
+                matched += 1;
+                if matched == pattern.len() {
+                    break;
+                }
+            }
+            end += 1;
+        }
+        if matched < pattern.len() {
+            // no later start can match either
+            break;
+        }
+        let span = end - start + 1;
+        shortest_span = Some(shortest_span.map_or(span, |shortest| shortest.min(span)));
+    }
+    shortest_span.map(|span| pattern.len() as f64 / span as f64)
+}
+
 #[derive(Serialize, Deserialize)]
 pub enum OptionalFilter<T> {
     OnlySome(T),
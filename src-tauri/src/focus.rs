@@ -0,0 +1,211 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc, serde::ts_seconds};
+use serde::{Deserialize, Serialize};
+use crate::{Clock, Db, DbError, DbResult, MyDateTime, TagId, TaskId};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Lengths of the phases of focus sessions.
+pub struct FocusSettings {
+    pub work_seconds: i64,
+    pub short_break_seconds: i64,
+    pub long_break_seconds: i64,
+    /// A long break is taken instead of a short one after this many completed work phases.
+    pub long_break_every: u32,
+}
+
+impl Default for FocusSettings {
+    fn default() -> Self {
+        Self {
+            work_seconds: 25 * 60,
+            short_break_seconds: 5 * 60,
+            long_break_seconds: 15 * 60,
+            long_break_every: 4,
+        }
+    }
+}
+
+impl FocusSettings {
+    /// The longest a phase can be.
+    pub const MAX_PHASE_SECONDS: i64 = 24 * 60 * 60;
+
+    /// Whether every phase length is positive and at most `MAX_PHASE_SECONDS`,
+    /// and `long_break_every` is positive.
+    pub fn is_valid(&self) -> bool {
+        let valid_length = |seconds: i64| (1..=FocusSettings::MAX_PHASE_SECONDS).contains(&seconds);
+        valid_length(self.work_seconds) && valid_length(self.short_break_seconds)
+            && valid_length(self.long_break_seconds) && self.long_break_every > 0
+    }
+    /// The length of `phase`.
+    pub fn length(&self, phase: FocusPhase) -> Duration {
+        Duration::seconds(match phase {
+            FocusPhase::Work => self.work_seconds,
+            FocusPhase::ShortBreak => self.short_break_seconds,
+            FocusPhase::LongBreak => self.long_break_seconds,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum FocusPhase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// The focus session in progress, working on a task and taking breaks in turn.
+pub struct FocusSession {
+    pub task_id: TaskId,
+    pub phase: FocusPhase,
+    #[serde(with = "ts_seconds")]
+    pub phase_start: DateTime<Utc>,
+    #[serde(with = "ts_seconds")]
+    pub phase_end: DateTime<Utc>,
+    /// Work phases completed since the last long break.
+    pub completed_in_cycle: u32,
+}
+
+impl FocusSession {
+    /// A session starting with a work phase at `now`.
+    pub fn start(task_id: TaskId, settings: &FocusSettings, now: DateTime<Utc>) -> Self {
+        Self {
+            task_id,
+            phase: FocusPhase::Work,
+            phase_start: now,
+            phase_end: now + settings.length(FocusPhase::Work),
+            completed_in_cycle: 0,
+        }
+    }
+
+    /// The session after the current phase ends at `time`. A work phase is only counted
+    /// towards a long break if it was `completed` rather than skipped.
+    pub fn next(&self, settings: &FocusSettings, time: DateTime<Utc>, completed: bool) -> Self {
+        let (phase, completed_in_cycle) = match self.phase {
+            FocusPhase::Work if completed && self.completed_in_cycle + 1 >= settings.long_break_every =>
+                (FocusPhase::LongBreak, 0),
+            FocusPhase::Work if completed => (FocusPhase::ShortBreak, self.completed_in_cycle + 1),
+            FocusPhase::Work => (FocusPhase::ShortBreak, self.completed_in_cycle),
+            FocusPhase::ShortBreak | FocusPhase::LongBreak => (FocusPhase::Work, self.completed_in_cycle),
+        };
+        Self {
+            task_id: self.task_id,
+            phase,
+            phase_start: time,
+            phase_end: time + settings.length(phase),
+            completed_in_cycle,
+        }
+    }
+
+    /// The seconds left in the current phase at `now`.
+    pub fn remaining_seconds(&self, now: DateTime<Utc>) -> i64 {
+        (self.phase_end - now).num_seconds().max(0)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Completed work phases of focus sessions, of a task or tag.
+pub struct FocusCount {
+    pub id: i64,
+    pub sessions: i64,
+    pub seconds: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Completed work phases of focus sessions during a day in the timezone of the user.
+pub struct FocusStats {
+    pub date: NaiveDate,
+    pub sessions: i64,
+    pub seconds: i64,
+    /// Counts per `TaskId`, in order of task id.
+    pub tasks: Vec<FocusCount>,
+    /// Counts per `TagId`, in order of tag id.
+    pub tags: Vec<FocusCount>,
+}
+
+impl FocusStats {
+    pub fn for_task(&self, task_id: TaskId) -> Option<&FocusCount> {
+        self.tasks.iter().find(|count| count.id == task_id)
+    }
+    pub fn for_tag(&self, tag_id: TagId) -> Option<&FocusCount> {
+        self.tags.iter().find(|count| count.id == tag_id)
+    }
+}
+
+/// The most seconds a phase can have ended before a tick of `FocusTimer` and still be completed.
+pub const MISSED_PHASE_SECONDS: i64 = 60;
+
+/// Runs the focus session stored in a `Db`, moving between phases as their time comes.
+pub struct FocusTimer<C: Clock> {
+    pub clock: C,
+}
+
+impl<C: Clock> FocusTimer<C> {
+    pub fn new(clock: C) -> Self {
+        Self { clock }
+    }
+
+    /// Starts a focus session on a task, replacing the session in progress if any.
+    ///
+    /// # Failure
+    /// Returns `DbError::TaskDoesNotExistError` if the task doesn't exist in the database.
+    pub fn start(&self, db: &mut Db, task_id: TaskId) -> DbResult<FocusSession> {
+        if db.task_by_id(task_id)?.is_none() {
+            return Err(DbError::TaskDoesNotExistError { id: task_id });
+        }
+        let session = FocusSession::start(task_id, &db.focus_settings()?, self.now());
+        db.save_focus_session(Some(&session))?;
+        Ok(session)
+    }
+
+    /// Ends the session in progress without counting its current phase.
+    /// Returns the ended session, or `None` if there was none.
+    pub fn stop(&self, db: &mut Db) -> DbResult<Option<FocusSession>> {
+        let session = db.focus_session()?;
+        db.save_focus_session(None)?;
+        Ok(session)
+    }
+
+    /// Ends the current phase now and moves on to the next, without counting a skipped work phase.
+    /// Returns the new phase of the session, or `None` if there is no session in progress.
+    pub fn skip(&self, db: &mut Db) -> DbResult<Option<FocusSession>> {
+        let session = match db.focus_session()? {
+            Some(session) => session,
+            None => return Ok(None),
+        };
+        let next = session.next(&db.focus_settings()?, self.now(), false);
+        db.save_focus_session(Some(&next))?;
+        Ok(Some(next))
+    }
+
+    /// Moves the session in progress to its next phase once the current phase has ended,
+    /// recording a completed work phase.
+    ///
+    /// A phase that ended over `MISSED_PHASE_SECONDS` ago ended while the timer was not running,
+    /// such as while the application was closed. Nobody is known to have worked through it,
+    /// so the session is stopped there instead, without counting the phase.
+    ///
+    /// Returns the phase that started, or `None` if no phase started.
+    pub fn tick(&self, db: &mut Db) -> DbResult<Option<FocusSession>> {
+        let now = self.clock.now();
+        let session = match db.focus_session()? {
+            Some(session) if session.phase_end <= now => session,
+            _ => return Ok(None),
+        };
+        if now - session.phase_end > Duration::seconds(MISSED_PHASE_SECONDS) {
+            db.save_focus_session(None)?;
+            return Ok(None);
+        }
+        let next = session.next(&db.focus_settings()?, session.phase_end, true);
+        db.end_focus_phase(&session, true, Some(&next))?;
+        Ok(Some(next))
+    }
+
+    /// The current time, truncated to the second like the times stored in the database.
+    fn now(&self) -> DateTime<Utc> {
+        MyDateTime::from(self.clock.now()).into()
+    }
+}
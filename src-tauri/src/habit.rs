@@ -0,0 +1,64 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use crate::TaskId;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A task done repeatedly. Finishing a habit checks it in for the day instead of marking it done.
+pub struct Habit {
+    pub task_id: TaskId,
+    /// The habit should be checked in at least once every this many days.
+    pub every_days: u32,
+    /// Days a check-in may be late by without breaking a streak.
+    pub grace_days: u32,
+}
+
+impl Habit {
+    pub fn is_valid(&self) -> bool {
+        self.every_days > 0
+    }
+
+    /// Whether a check-in on `next` continues a streak with the check-in on `previous`.
+    pub fn continues(&self, previous: NaiveDate, next: NaiveDate) -> bool {
+        // more days than a u32 holds is longer than any two dates are apart
+        self.every_days.checked_add(self.grace_days)
+            .map_or(true, |days| (next - previous).num_days() <= days as i64)
+    }
+
+    /// The streaks of check-ins on `dates`, which are in ascending order, as of `today`.
+    pub fn streaks(&self, dates: &[NaiveDate], today: NaiveDate) -> HabitStreaks {
+        let mut current = 0;
+        let mut longest = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for &date in dates.iter().filter(|&&date| date <= today) {
+            current = match previous {
+                Some(previous) if self.continues(previous, date) => current + 1,
+                _ => 1,
+            };
+            longest = longest.max(current);
+            previous = Some(date);
+        }
+        // a streak is only current until its next check-in is overdue
+        match previous {
+            Some(previous) if self.continues(previous, today) => {}
+            _ => current = 0,
+        }
+        HabitStreaks { current, longest }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Streaks of a habit, counted in check-ins.
+pub struct HabitStreaks {
+    pub current: u32,
+    pub longest: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Check-ins on a day, for calendar heatmaps.
+pub struct HeatmapDay {
+    pub date: NaiveDate,
+    pub check_ins: i64,
+}
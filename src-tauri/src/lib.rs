@@ -5,10 +5,57 @@ mod my_date_time;
 mod hsl_color;
 mod filters;
 mod task_filter_options;
+mod quick_add;
+mod local_time;
+mod clock;
+mod reminder;
+mod time_entry;
+mod focus;
+mod stats;
+mod burndown;
+mod habit;
+mod urgency;
+mod planner;
+mod project;
+mod board;
+mod rank;
+mod checklist;
+mod activity;
+mod attachment;
+mod link;
+mod markdown;
+mod template;
+mod task_operations;
+mod bulk;
+mod estimate;
 
 pub use tag::{*};
 pub use task::{*};
 pub use db::{*};
 pub use hsl_color::{*};
 pub use filters::{*};
-pub use task_filter_options::{*};
\ No newline at end of file
+pub use task_filter_options::{*};
+pub use quick_add::{*};
+pub use local_time::{*};
+pub use my_date_time::{*};
+pub use clock::{*};
+pub use reminder::{*};
+pub use time_entry::{*};
+pub use focus::{*};
+pub use stats::{*};
+pub use burndown::{*};
+pub use habit::{*};
+pub use urgency::{*};
+pub use planner::{*};
+pub use project::{*};
+pub use board::{*};
+pub use rank::{*};
+pub use checklist::{*};
+pub use activity::{*};
+pub use attachment::{*};
+pub use link::{*};
+pub use markdown::{*};
+pub use template::{*};
+pub use task_operations::{*};
+pub use bulk::{*};
+pub use estimate::{*};
\ No newline at end of file
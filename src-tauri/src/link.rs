@@ -0,0 +1,103 @@
+use std::ops::Range;
+use serde::{Deserialize, Serialize};
+use crate::TaskId;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A reference to another task written in the body of a task,
+/// either as `[[Task title]]` or as `#123` with the id of the task.
+pub enum TaskReference {
+    Title { title: String },
+    Id { id: TaskId },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A reference in the body of the task `source`, resolved to the task it refers to.
+pub struct TaskLink {
+    pub source: TaskId,
+    /// The task referred to, or `None` if the link is broken: no task has the title,
+    /// or the task was deleted.
+    pub target: Option<TaskId>,
+    pub reference: TaskReference,
+}
+
+/// The distinct task references in `body`, in order of their first appearance.
+///
+/// `#123` only counts as a reference when it is not part of a longer word, like `C#7` or `#12a`.
+pub fn extract_references(body: &str) -> Vec<TaskReference> {
+    let mut references = vec![];
+    for (_, reference) in find_references(body) {
+        if !references.contains(&reference) {
+            references.push(reference);
+        }
+    }
+    references
+}
+
+/// Rewrites the `[[old]]` references in `body` to `[[new]]`, leaving the rest of it as it is.
+pub fn replace_title_references(body: &str, old: &str, new: &str) -> String {
+    let mut replaced = String::with_capacity(body.len());
+    let mut copied = 0;
+    for (range, reference) in find_references(body) {
+        if matches!(&reference, TaskReference::Title { title } if title == old) {
+            replaced.push_str(&body[copied..range.start]);
+            replaced.push_str(&format!("[[{new}]]"));
+            copied = range.end;
+        }
+    }
+    replaced.push_str(&body[copied..]);
+    replaced
+}
+
+/// Rewrites the `#old` references in `body` to `#new`, leaving the rest of it as it is.
+pub fn replace_id_references(body: &str, old: TaskId, new: TaskId) -> String {
+    let mut replaced = String::with_capacity(body.len());
+    let mut copied = 0;
+    for (range, reference) in find_references(body) {
+        if reference == (TaskReference::Id { id: old }) {
+            replaced.push_str(&body[copied..range.start]);
+            replaced.push_str(&format!("#{new}"));
+            copied = range.end;
+        }
+    }
+    replaced.push_str(&body[copied..]);
+    replaced
+}
+
+fn find_references(body: &str) -> Vec<(Range<usize>, TaskReference)> {
+    let mut found = vec![];
+    let mut index = 0;
+    while let Some(next) = body[index..].chars().next() {
+        let rest = &body[index..];
+        if let Some(inner) = rest.strip_prefix("[[") {
+            if let Some(end) = inner.find("]]") {
+                let title = inner[..end].trim();
+                if !title.is_empty() && !title.contains('\n') && !title.contains("[[") {
+                    let length = end + 4;
+                    found.push((index..index + length, TaskReference::Title { title: String::from(title) }));
+                    index += length;
+                    continue;
+                }
+            }
+        } else if let Some(number) = rest.strip_prefix('#') {
+            let digits = number.len() - number.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            // `&` for character references like `&#123;`
+            let after_word = matches!(body[..index].chars().next_back(), Some(c) if is_word(c) || c == '&');
+            let before_word = matches!(number[digits..].chars().next(), Some(c) if is_word(c));
+            if digits > 0 && !after_word && !before_word {
+                if let Ok(id) = number[..digits].parse() {
+                    found.push((index..index + digits + 1, TaskReference::Id { id }));
+                    index += digits + 1;
+                    continue;
+                }
+            }
+        }
+        index += next.len_utf8();
+    }
+    found
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
@@ -0,0 +1,71 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc};
+
+/// Parses an IANA timezone name such as `"America/New_York"`, or `None` if it is unknown.
+pub fn parse_timezone(name: &str) -> Option<chrono_tz::Tz> {
+    name.parse().ok()
+}
+
+/// Converts a local time to UTC, taking the earlier time if it is ambiguous. A time skipped by
+/// a transition (an hour of daylight saving, or even a whole day) is read with the offset in effect
+/// before the transition, so it lands as far past the transition as it was past its start.
+pub fn resolve_local<Tz: TimeZone>(timezone: &Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    if let Some(time) = timezone.from_local_datetime(&local).earliest() {
+        return time.with_timezone(&Utc);
+    }
+    // transitions are far more than a day apart, so a day earlier is before this one
+    let before = local.checked_sub_signed(Duration::days(1)).unwrap_or(local);
+    let offset = timezone.offset_from_utc_datetime(&before).fix().local_minus_utc();
+    Utc.from_utc_datetime(&local.checked_sub_signed(Duration::seconds(offset as i64)).unwrap_or(local))
+}
+
+/// The calendar date of `time` in `timezone`.
+pub fn local_date<Tz: TimeZone>(timezone: &Tz, time: &DateTime<Utc>) -> NaiveDate {
+    time.with_timezone(timezone).date_naive()
+}
+
+/// The first instant of `date` in `timezone`.
+pub fn day_start<Tz: TimeZone>(timezone: &Tz, date: NaiveDate) -> DateTime<Utc> {
+    resolve_local(timezone, date.and_hms_opt(0, 0, 0).unwrap())
+}
+
+/// The first instant of `date` in `timezone` and the first instant of the following day,
+/// so a time is on `date` if it is within `start..end`, or `None` if `date` is the last date there is.
+/// Note the day may not be 24 hours long across a daylight saving transition.
+pub fn day_bounds<Tz: TimeZone>(timezone: &Tz, date: NaiveDate) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let next = date.succ_opt()?;
+    Some((day_start(timezone, date), day_start(timezone, next)))
+}
+
+/// Moves `time` by `days` calendar days in `timezone`, keeping its local wall clock time
+/// (so `9:00` stays `9:00` across a daylight saving transition),
+/// or `None` if that is out of the range of times.
+pub fn add_local_days<Tz: TimeZone>(timezone: &Tz, time: &DateTime<Utc>, days: i64) -> Option<DateTime<Utc>> {
+    let local = time.with_timezone(timezone).naive_local();
+    Some(resolve_local(timezone, local.checked_add_signed(checked_days(days)?)?))
+}
+
+/// `Duration::seconds`, or `None` instead of panicking if `seconds` is out of its range.
+pub fn checked_seconds(seconds: i64) -> Option<Duration> {
+    seconds.checked_mul(1000).map(Duration::milliseconds)
+}
+
+/// `Duration::minutes`, or `None` instead of panicking if `minutes` is out of its range.
+pub fn checked_minutes(minutes: i64) -> Option<Duration> {
+    minutes.checked_mul(60).and_then(checked_seconds)
+}
+
+/// `Duration::days`, or `None` instead of panicking if `days` is out of its range.
+pub fn checked_days(days: i64) -> Option<Duration> {
+    days.checked_mul(24 * 60 * 60).and_then(checked_seconds)
+}
+
+/// The stored time of an all-day date: midnight UTC of that date.
+/// An all-day time is a calendar date regardless of timezone; see `all_day_date`.
+pub fn all_day_time(date: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+}
+
+/// The calendar date of an all-day time stored with `all_day_time`.
+pub fn all_day_date(time: &DateTime<Utc>) -> NaiveDate {
+    time.date_naive()
+}
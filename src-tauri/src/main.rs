@@ -1,12 +1,18 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::collections::HashMap;
 use std::ops::Deref;
+use std::path::{Path, PathBuf};
 // reference:
 // https://gist.github.com/captainhusaynpenguin/5bdb6fcb141628b6865619bcd1c827fd
 use std::sync::{Once, Mutex};
+use std::thread;
+use std::time::Duration as StdDuration;
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::Serialize;
 use tauri::{AppHandle, Manager, State};
+use tauri::api::notification::Notification;
 use app::{*};
 
 fn main() {
@@ -14,6 +20,10 @@ fn main() {
         .setup(|app| {
             let window = app.get_window("main").unwrap();
             window.open_devtools();
+            let app_handle = app.handle();
+            let focus_app_handle = app_handle.clone();
+            thread::spawn(move || run_reminder_scheduler(app_handle));
+            thread::spawn(move || run_focus_timer(focus_app_handle));
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -28,6 +38,118 @@ fn main() {
             finish_task,
             unfinish_task,
             filter_tasks,
+            quick_add_task,
+            user_timezone,
+            set_user_timezone,
+            tasks_due_on,
+            add_new_reminder,
+            reminders_for_task,
+            delete_reminder,
+            snooze_reminder,
+            dismiss_reminder,
+            start_timer,
+            stop_timer,
+            running_timer,
+            add_time_entry,
+            time_entries_for_task,
+            modify_time_entry,
+            delete_time_entry,
+            task_time_spent,
+            tag_time_spent,
+            time_spent_by_day,
+            focus_settings,
+            set_focus_settings,
+            focus_session,
+            start_focus,
+            stop_focus,
+            skip_focus_phase,
+            focus_stats,
+            period_stats,
+            summary_stats,
+            tag_stats,
+            burndown_report,
+            set_habit,
+            remove_habit,
+            all_habits,
+            check_in_habit,
+            undo_habit_check_in,
+            habit_check_ins,
+            habit_streaks,
+            habit_heatmap,
+            urgency_coefficients,
+            set_urgency_coefficients,
+            tag_urgency_weights,
+            set_tag_urgency_weight,
+            filter_urgent_tasks,
+            planner_settings,
+            set_planner_settings,
+            day_plan,
+            add_time_block,
+            modify_time_block,
+            delete_time_block,
+            auto_schedule_day,
+            all_projects,
+            add_new_project,
+            modify_project,
+            set_project_archived,
+            delete_project,
+            project_tasks,
+            move_task_to_project,
+            project_progress,
+            all_boards,
+            add_new_board,
+            modify_board,
+            delete_board,
+            add_column,
+            modify_column,
+            move_column,
+            delete_column,
+            board_view,
+            move_card,
+            remove_card,
+            move_task_before,
+            move_task_after,
+            checklist_items,
+            add_checklist_item,
+            modify_checklist_item,
+            move_checklist_item,
+            delete_checklist_item,
+            convert_body_checklist,
+            require_complete_checklist,
+            set_require_complete_checklist,
+            comments_for_task,
+            add_comment,
+            modify_comment,
+            delete_comment,
+            task_activity,
+            attachments_for_task,
+            add_attachment,
+            attachment_path,
+            delete_attachment,
+            collect_attachment_garbage,
+            backup,
+            task_links,
+            backlinks,
+            broken_links,
+            rendered_body,
+            body_outline,
+            render_draft_body,
+            all_templates,
+            add_new_template,
+            modify_template,
+            delete_template,
+            instantiate_template,
+            add_task_from_template,
+            duplicate_task,
+            split_task,
+            merge_tasks,
+            bulk_update,
+            defer_task,
+            snooze_task,
+            set_task_estimate,
+            estimate_totals,
+            tag_estimate_totals,
+            estimate_comparison,
         ])
         .manage(AppState {db: Mutex::new(None) } )
         .run(tauri::generate_context!())
@@ -70,11 +192,191 @@ impl From<DbError> for AppError {
                     format!("Task {id} is already not done")
                 }
             },
+            DbError::InvalidTimezoneError { name } => AppError {
+                message: format!("Timezone {name} does not exist")
+            },
+            DbError::ReminderDoesNotExistError { id } => AppError {
+                message: format!("Reminder {id} does not exist")
+            },
+            DbError::DurationOutOfRangeError => AppError {
+                message: String::from("Duration is out of range")
+            },
+            DbError::DateOutOfRangeError => AppError {
+                message: String::from("Date is out of range")
+            },
+            DbError::InvalidDateRangeError => AppError {
+                message: String::from("Date range must not end before it starts, or span more than a century")
+            },
+            DbError::InvalidAllDayError => AppError {
+                message: String::from("An all-day due time must be a date")
+            },
+            DbError::AllDayShiftError { id } => AppError {
+                message: format!("The all-day due date of task {id} can only move by whole days")
+            },
+            DbError::TimeEntryDoesNotExistError { id } => AppError {
+                message: format!("Time entry {id} does not exist")
+            },
+            DbError::InvalidTimeEntryError => AppError {
+                message: String::from("Time entry ends before it starts")
+            },
+            DbError::TimerRunningError { id } => AppError {
+                message: format!("Time entry {id} is already running")
+            },
+            DbError::InvalidFocusSettingsError => AppError {
+                message: String::from("Focus session lengths must be positive")
+            },
+            DbError::NotAHabitError { id } => AppError {
+                message: format!("Task {id} is not a habit")
+            },
+            DbError::InvalidHabitError => AppError {
+                message: String::from("Habits must be done at least once every day or more")
+            },
+            DbError::TimeBlockDoesNotExistError { id } => AppError {
+                message: format!("Time block {id} does not exist")
+            },
+            DbError::InvalidTimeBlockError => AppError {
+                message: String::from("Time block must end after it starts")
+            },
+            DbError::InvalidPlannerSettingsError => AppError {
+                message: String::from("Working hours must end after they start, and estimates must be positive")
+            },
+            DbError::ProjectDoesNotExistError { id } => AppError {
+                message: format!("Project {id} does not exist")
+            },
+            DbError::BoardDoesNotExistError { id } => AppError {
+                message: format!("Board {id} does not exist")
+            },
+            DbError::ColumnDoesNotExistError { id } => AppError {
+                message: format!("Column {id} does not exist")
+            },
+            DbError::WipLimitError { id, limit } => AppError {
+                message: format!("Column {id} already holds its limit of {limit} tasks")
+            },
+            DbError::ChecklistItemDoesNotExistError { id } => AppError {
+                message: format!("Checklist item {id} does not exist")
+            },
+            DbError::ChecklistIncompleteError { id, unchecked } => AppError {
+                message: format!("Task {id} still has {unchecked} unchecked checklist items")
+            },
+            DbError::CommentDoesNotExistError { id } => AppError {
+                message: format!("Comment {id} does not exist")
+            },
+            DbError::AttachmentDoesNotExistError { id } => AppError {
+                message: format!("Attachment {id} does not exist")
+            },
+            DbError::AttachmentFileError { message } => AppError {
+                message: format!("Attachment file error: {message}")
+            },
+            DbError::BackupDirectoryError => AppError {
+                message: String::from("Cannot back up into the directory of the database or its attachments")
+            },
+            DbError::TemplateDoesNotExistError { id } => AppError {
+                message: format!("Template {id} does not exist")
+            },
+            DbError::LineDoesNotExistError { id, line } => AppError {
+                message: format!("Task {id} has no line {line}")
+            },
+            DbError::InvalidEstimateError => AppError {
+                message: String::from("Estimates must be positive, and remaining effort needs an estimate and can't be negative")
+            },
         }
     }
 }
 type AppResult<T> = Result<T, AppError>;
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+/// The result of a bulk action on one task, with its error if it failed.
+struct BulkItemReport {
+    id: TaskId,
+    error: Option<AppError>,
+}
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+/// A `BulkOutcome` as sent to the frontend.
+struct BulkReport {
+    results: Vec<BulkItemReport>,
+    rolled_back: bool,
+}
+impl From<BulkOutcome> for BulkReport {
+    fn from(value: BulkOutcome) -> Self {
+        BulkReport {
+            results: value.results.into_iter()
+                .map(|item| BulkItemReport { id: item.id, error: item.result.err().map(AppError::from) })
+                .collect(),
+            rolled_back: value.rolled_back,
+        }
+    }
+}
+
+/// Shows fired reminders as desktop notifications, and emits them to the frontend
+/// as `reminder` events.
+struct TauriNotifier {
+    app_handle: AppHandle,
+}
+impl Notifier for TauriNotifier {
+    fn notify(&self, reminder: &ScheduledReminder) {
+        _ = self.app_handle.emit_all("reminder", reminder);
+        _ = Notification::new(&self.app_handle.config().tauri.bundle.identifier)
+            .title(&reminder.task.title)
+            .body(reminder.message())
+            .show();
+    }
+}
+
+/// The longest the reminder scheduler sleeps, so reminders added in the meantime fire at most this late.
+const MAX_REMINDER_WAIT: StdDuration = StdDuration::from_secs(30);
+
+fn run_reminder_scheduler(app_handle: AppHandle) {
+    let scheduler = ReminderScheduler::new(SystemClock, TauriNotifier { app_handle: app_handle.clone() });
+    let state = app_handle.state::<AppState>();
+    setup_state(&app_handle, state.deref());
+    loop {
+        let next = {
+            let mut binding = state.db.lock().unwrap();
+            let db = binding.as_mut().unwrap();
+            scheduler.tick(db)
+        };
+        let wait = match next {
+            Ok(Some(next)) => (next - Utc::now()).to_std().unwrap_or(StdDuration::ZERO),
+            _ => MAX_REMINDER_WAIT,
+        };
+        thread::sleep(wait.min(MAX_REMINDER_WAIT));
+    }
+}
+
+/// Payload of the `focus-tick` event, emitted every second while a focus session is in progress.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct FocusTick {
+    session: FocusSession,
+    remaining_seconds: i64,
+}
+
+/// Moves the focus session through its phases, emitting `focus-phase` events as phases start
+/// and `focus-tick` events every second. A session whose phase ended while the application was closed
+/// is stopped, after which no more `focus-tick` events are emitted for it.
+fn run_focus_timer(app_handle: AppHandle) {
+    let timer = FocusTimer::new(SystemClock);
+    let state = app_handle.state::<AppState>();
+    setup_state(&app_handle, state.deref());
+    loop {
+        let (started, session) = {
+            let mut binding = state.db.lock().unwrap();
+            let db = binding.as_mut().unwrap();
+            (timer.tick(db).ok().flatten(), db.focus_session().ok().flatten())
+        };
+        if let Some(phase) = started {
+            _ = app_handle.emit_all("focus-phase", phase);
+        }
+        if let Some(session) = session {
+            let remaining_seconds = session.remaining_seconds(Utc::now());
+            _ = app_handle.emit_all("focus-tick", FocusTick { session, remaining_seconds });
+        }
+        thread::sleep(StdDuration::from_secs(1));
+    }
+}
+
 mod util {
     use std::path::PathBuf;
     use tauri::{AppHandle};
@@ -191,4 +493,981 @@ fn filter_tasks(app_handle: AppHandle, state: State<AppState>, filter: TaskFilte
     let mut binding = state.db.lock().unwrap();
     let db = binding.as_mut().unwrap();
     Ok(db.filter_tasks(|task| filter.passes(task))?)
-}
\ No newline at end of file
+}
+
+#[tauri::command]
+fn quick_add_task(app_handle: AppHandle, state: State<AppState>, text: String)
+               -> AppResult<QuickAddedTaskData> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    let now = Utc::now().with_timezone(&db.user_timezone()?);
+    Ok(db.quick_add_task(&text, &now)?)
+}
+
+#[tauri::command]
+fn user_timezone(app_handle: AppHandle, state: State<AppState>) -> AppResult<String> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.user_timezone()?.name().to_string())
+}
+
+#[tauri::command]
+fn set_user_timezone(app_handle: AppHandle, state: State<AppState>, name: String)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.set_user_timezone(&name)?)
+}
+
+#[tauri::command]
+fn tasks_due_on(app_handle: AppHandle, state: State<AppState>, date: NaiveDate)
+               -> AppResult<Vec<Task>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.tasks_due_on(date)?)
+}
+
+#[tauri::command]
+fn add_new_reminder(app_handle: AppHandle, state: State<AppState>, data: EditableReminderData)
+               -> AppResult<GeneratedReminderData> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.add_new_reminder(&data)?)
+}
+
+#[tauri::command]
+fn reminders_for_task(app_handle: AppHandle, state: State<AppState>, task_id: TaskId)
+               -> AppResult<Vec<Reminder>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.reminders_for_task(task_id)?)
+}
+
+#[tauri::command]
+fn delete_reminder(app_handle: AppHandle, state: State<AppState>, id: ReminderId)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.delete_reminder(id)?)
+}
+
+#[tauri::command]
+fn snooze_reminder(app_handle: AppHandle, state: State<AppState>, id: ReminderId, minutes: i64)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    let duration = checked_minutes(minutes).ok_or(DbError::DurationOutOfRangeError)?;
+    Ok(db.snooze_reminder(id, duration, Utc::now())?)
+}
+
+#[tauri::command]
+fn dismiss_reminder(app_handle: AppHandle, state: State<AppState>, id: ReminderId)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.dismiss_reminder(id)?)
+}
+
+#[tauri::command]
+fn start_timer(app_handle: AppHandle, state: State<AppState>, task_id: TaskId)
+               -> AppResult<TimeEntry> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.start_timer(task_id, Utc::now())?)
+}
+
+#[tauri::command]
+fn stop_timer(app_handle: AppHandle, state: State<AppState>) -> AppResult<Option<TimeEntry>> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.stop_timer(Utc::now())?)
+}
+
+#[tauri::command]
+fn running_timer(app_handle: AppHandle, state: State<AppState>) -> AppResult<Option<TimeEntry>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.running_timer()?)
+}
+
+#[tauri::command]
+fn add_time_entry(app_handle: AppHandle, state: State<AppState>, data: EditableTimeEntryData)
+               -> AppResult<GeneratedTimeEntryData> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.add_time_entry(&data)?)
+}
+
+#[tauri::command]
+fn time_entries_for_task(app_handle: AppHandle, state: State<AppState>, task_id: TaskId)
+               -> AppResult<Vec<TimeEntry>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.time_entries_for_task(task_id)?)
+}
+
+#[tauri::command]
+fn modify_time_entry(app_handle: AppHandle, state: State<AppState>, id: TimeEntryId, data: EditableTimeEntryData)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.modify_time_entry(id, &data)?)
+}
+
+#[tauri::command]
+fn delete_time_entry(app_handle: AppHandle, state: State<AppState>, id: TimeEntryId)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.delete_time_entry(id)?)
+}
+
+#[tauri::command]
+fn task_time_spent(app_handle: AppHandle, state: State<AppState>, task_id: TaskId)
+               -> AppResult<i64> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.task_time_spent(task_id, Utc::now())?)
+}
+
+#[tauri::command]
+fn tag_time_spent(app_handle: AppHandle, state: State<AppState>, tag_id: TagId)
+               -> AppResult<i64> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.tag_time_spent(tag_id, Utc::now())?)
+}
+
+#[tauri::command]
+fn time_spent_by_day(app_handle: AppHandle, state: State<AppState>, first: NaiveDate, last: NaiveDate)
+               -> AppResult<Vec<DayTimeSpent>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.time_spent_by_day(first, last, Utc::now())?)
+}
+
+#[tauri::command]
+fn focus_settings(app_handle: AppHandle, state: State<AppState>) -> AppResult<FocusSettings> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.focus_settings()?)
+}
+
+#[tauri::command]
+fn set_focus_settings(app_handle: AppHandle, state: State<AppState>, settings: FocusSettings)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.set_focus_settings(&settings)?)
+}
+
+#[tauri::command]
+fn focus_session(app_handle: AppHandle, state: State<AppState>) -> AppResult<Option<FocusSession>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.focus_session()?)
+}
+
+#[tauri::command]
+fn start_focus(app_handle: AppHandle, state: State<AppState>, task_id: TaskId)
+               -> AppResult<FocusSession> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    let session = FocusTimer::new(SystemClock).start(db, task_id)?;
+    _ = app_handle.emit_all("focus-phase", &session);
+    Ok(session)
+}
+
+#[tauri::command]
+fn stop_focus(app_handle: AppHandle, state: State<AppState>) -> AppResult<Option<FocusSession>> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(FocusTimer::new(SystemClock).stop(db)?)
+}
+
+#[tauri::command]
+fn skip_focus_phase(app_handle: AppHandle, state: State<AppState>) -> AppResult<Option<FocusSession>> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    let session = FocusTimer::new(SystemClock).skip(db)?;
+    if let Some(session) = &session {
+        _ = app_handle.emit_all("focus-phase", session);
+    }
+    Ok(session)
+}
+
+#[tauri::command]
+fn focus_stats(app_handle: AppHandle, state: State<AppState>, date: NaiveDate)
+               -> AppResult<FocusStats> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.focus_stats(date)?)
+}
+
+#[tauri::command]
+fn period_stats(app_handle: AppHandle, state: State<AppState>, period: StatsPeriod, first: NaiveDate, last: NaiveDate)
+               -> AppResult<Vec<PeriodStats>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.period_stats(period, first, last)?)
+}
+
+#[tauri::command]
+fn summary_stats(app_handle: AppHandle, state: State<AppState>, first: NaiveDate, last: NaiveDate)
+               -> AppResult<SummaryStats> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.summary_stats(first, last)?)
+}
+
+#[tauri::command]
+fn tag_stats(app_handle: AppHandle, state: State<AppState>, first: NaiveDate, last: NaiveDate)
+               -> AppResult<Vec<TagStats>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.tag_stats(first, last)?)
+}
+
+#[tauri::command]
+fn burndown_report(app_handle: AppHandle, state: State<AppState>, filter: TaskFilterOptions, period: StatsPeriod,
+                   first: NaiveDate, last: NaiveDate) -> AppResult<BurndownReport> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.burndown_report(&filter, period, first, last)?)
+}
+
+#[tauri::command]
+fn set_habit(app_handle: AppHandle, state: State<AppState>, habit: Habit) -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.set_habit(&habit)?)
+}
+
+#[tauri::command]
+fn remove_habit(app_handle: AppHandle, state: State<AppState>, task_id: TaskId) -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.remove_habit(task_id)?)
+}
+
+#[tauri::command]
+fn all_habits(app_handle: AppHandle, state: State<AppState>) -> AppResult<Vec<Habit>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.all_habits()?)
+}
+
+#[tauri::command]
+fn check_in_habit(app_handle: AppHandle, state: State<AppState>, task_id: TaskId, date: NaiveDate)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.check_in_habit(task_id, date)?)
+}
+
+#[tauri::command]
+fn undo_habit_check_in(app_handle: AppHandle, state: State<AppState>, task_id: TaskId, date: NaiveDate)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.undo_habit_check_in(task_id, date)?)
+}
+
+#[tauri::command]
+fn habit_check_ins(app_handle: AppHandle, state: State<AppState>, task_id: TaskId)
+               -> AppResult<Vec<NaiveDate>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.habit_check_ins(task_id)?)
+}
+
+#[tauri::command]
+fn habit_streaks(app_handle: AppHandle, state: State<AppState>, task_id: TaskId)
+               -> AppResult<HabitStreaks> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    let today = local_date(&db.user_timezone()?, &Utc::now());
+    Ok(db.habit_streaks(task_id, today)?)
+}
+
+#[tauri::command]
+fn habit_heatmap(app_handle: AppHandle, state: State<AppState>, task_id: Option<TaskId>, first: NaiveDate, last: NaiveDate)
+               -> AppResult<Vec<HeatmapDay>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.habit_heatmap(task_id, first, last)?)
+}
+
+#[tauri::command]
+fn urgency_coefficients(app_handle: AppHandle, state: State<AppState>) -> AppResult<UrgencyCoefficients> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.urgency_coefficients()?)
+}
+
+#[tauri::command]
+fn set_urgency_coefficients(app_handle: AppHandle, state: State<AppState>, coefficients: UrgencyCoefficients)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.set_urgency_coefficients(&coefficients)?)
+}
+
+#[tauri::command]
+fn tag_urgency_weights(app_handle: AppHandle, state: State<AppState>) -> AppResult<HashMap<TagId, f64>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.tag_urgency_weights()?)
+}
+
+#[tauri::command]
+fn set_tag_urgency_weight(app_handle: AppHandle, state: State<AppState>, tag_id: TagId, weight: f64)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.set_tag_urgency_weight(tag_id, weight)?)
+}
+
+#[tauri::command]
+fn filter_urgent_tasks(app_handle: AppHandle, state: State<AppState>, filter: TaskFilterOptions, sort: bool)
+               -> AppResult<Vec<UrgentTask>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    if sort {
+        Ok(db.filter_tasks_by_urgency(|task| filter.passes(task), Utc::now())?)
+    } else {
+        Ok(db.filter_urgent_tasks(|task| filter.passes(task), Utc::now())?)
+    }
+}
+
+#[tauri::command]
+fn planner_settings(app_handle: AppHandle, state: State<AppState>) -> AppResult<PlannerSettings> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.planner_settings()?)
+}
+
+#[tauri::command]
+fn set_planner_settings(app_handle: AppHandle, state: State<AppState>, settings: PlannerSettings)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.set_planner_settings(&settings)?)
+}
+
+#[tauri::command]
+fn day_plan(app_handle: AppHandle, state: State<AppState>, date: NaiveDate) -> AppResult<DayPlan> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.day_plan(date)?)
+}
+
+#[tauri::command]
+fn add_time_block(app_handle: AppHandle, state: State<AppState>, data: EditableTimeBlockData)
+               -> AppResult<GeneratedTimeBlockData> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.add_time_block(&data)?)
+}
+
+#[tauri::command]
+fn modify_time_block(app_handle: AppHandle, state: State<AppState>, id: TimeBlockId, data: EditableTimeBlockData)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.modify_time_block(id, &data)?)
+}
+
+#[tauri::command]
+fn delete_time_block(app_handle: AppHandle, state: State<AppState>, id: TimeBlockId) -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.delete_time_block(id)?)
+}
+
+#[tauri::command]
+fn auto_schedule_day(app_handle: AppHandle, state: State<AppState>, date: NaiveDate) -> AppResult<DayPlan> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.auto_schedule_day(date, Utc::now())?)
+}
+
+#[tauri::command]
+fn all_projects(app_handle: AppHandle, state: State<AppState>) -> AppResult<Vec<Project>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.all_projects()?)
+}
+
+#[tauri::command]
+fn add_new_project(app_handle: AppHandle, state: State<AppState>, data: EditableProjectData)
+               -> AppResult<GeneratedProjectData> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.add_new_project(&data)?)
+}
+
+#[tauri::command]
+fn modify_project(app_handle: AppHandle, state: State<AppState>, id: ProjectId, data: EditableProjectData)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.modify_project(id, &data)?)
+}
+
+#[tauri::command]
+fn set_project_archived(app_handle: AppHandle, state: State<AppState>, id: ProjectId, archived: bool)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.set_project_archived(id, archived)?)
+}
+
+#[tauri::command]
+fn delete_project(app_handle: AppHandle, state: State<AppState>, id: ProjectId) -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.delete_project(id)?)
+}
+
+#[tauri::command]
+fn project_tasks(app_handle: AppHandle, state: State<AppState>, id: ProjectId) -> AppResult<Vec<Task>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.project_tasks(id)?)
+}
+
+#[tauri::command]
+fn move_task_to_project(app_handle: AppHandle, state: State<AppState>, task_id: TaskId,
+                        project_id: Option<ProjectId>, position: Option<usize>) -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.move_task_to_project(task_id, project_id, position)?)
+}
+
+#[tauri::command]
+fn project_progress(app_handle: AppHandle, state: State<AppState>, id: ProjectId) -> AppResult<ProjectProgress> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.project_progress(id)?)
+}
+
+#[tauri::command]
+fn all_boards(app_handle: AppHandle, state: State<AppState>) -> AppResult<Vec<Board>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.all_boards()?)
+}
+
+#[tauri::command]
+fn add_new_board(app_handle: AppHandle, state: State<AppState>, data: EditableBoardData)
+               -> AppResult<GeneratedBoardData> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.add_new_board(&data)?)
+}
+
+#[tauri::command]
+fn modify_board(app_handle: AppHandle, state: State<AppState>, id: BoardId, data: EditableBoardData)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.modify_board(id, &data)?)
+}
+
+#[tauri::command]
+fn delete_board(app_handle: AppHandle, state: State<AppState>, id: BoardId) -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.delete_board(id)?)
+}
+
+#[tauri::command]
+fn add_column(app_handle: AppHandle, state: State<AppState>, board_id: BoardId, data: EditableColumnData)
+               -> AppResult<GeneratedColumnData> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.add_column(board_id, &data)?)
+}
+
+#[tauri::command]
+fn modify_column(app_handle: AppHandle, state: State<AppState>, id: ColumnId, data: EditableColumnData)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.modify_column(id, &data)?)
+}
+
+#[tauri::command]
+fn move_column(app_handle: AppHandle, state: State<AppState>, id: ColumnId, position: usize)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.move_column(id, position)?)
+}
+
+#[tauri::command]
+fn delete_column(app_handle: AppHandle, state: State<AppState>, id: ColumnId) -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.delete_column(id)?)
+}
+
+#[tauri::command]
+fn board_view(app_handle: AppHandle, state: State<AppState>, id: BoardId) -> AppResult<BoardView> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.board_view(id)?)
+}
+
+#[tauri::command]
+fn move_card(app_handle: AppHandle, state: State<AppState>, task_id: TaskId, column_id: ColumnId, position: Option<usize>)
+               -> AppResult<FinishedTaskData> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.move_card(task_id, column_id, position)?)
+}
+
+#[tauri::command]
+fn remove_card(app_handle: AppHandle, state: State<AppState>, board_id: BoardId, task_id: TaskId) -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.remove_card(board_id, task_id)?)
+}
+
+#[tauri::command]
+fn move_task_before(app_handle: AppHandle, state: State<AppState>, id: TaskId, before: TaskId) -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.move_task_before(id, before)?)
+}
+
+#[tauri::command]
+fn move_task_after(app_handle: AppHandle, state: State<AppState>, id: TaskId, after: TaskId) -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.move_task_after(id, after)?)
+}
+
+#[tauri::command]
+fn checklist_items(app_handle: AppHandle, state: State<AppState>, task_id: TaskId)
+               -> AppResult<Vec<ChecklistItem>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.checklist_items(task_id)?)
+}
+
+#[tauri::command]
+fn add_checklist_item(app_handle: AppHandle, state: State<AppState>, task_id: TaskId, data: EditableChecklistItemData)
+               -> AppResult<GeneratedChecklistItemData> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.add_checklist_item(task_id, &data)?)
+}
+
+#[tauri::command]
+fn modify_checklist_item(app_handle: AppHandle, state: State<AppState>, id: ChecklistItemId, data: EditableChecklistItemData)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.modify_checklist_item(id, &data)?)
+}
+
+#[tauri::command]
+fn move_checklist_item(app_handle: AppHandle, state: State<AppState>, id: ChecklistItemId, position: usize)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.move_checklist_item(id, position)?)
+}
+
+#[tauri::command]
+fn delete_checklist_item(app_handle: AppHandle, state: State<AppState>, id: ChecklistItemId)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.delete_checklist_item(id)?)
+}
+
+#[tauri::command]
+fn convert_body_checklist(app_handle: AppHandle, state: State<AppState>, task_id: TaskId)
+               -> AppResult<Vec<ChecklistItem>> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.convert_body_checklist(task_id)?)
+}
+
+#[tauri::command]
+fn require_complete_checklist(app_handle: AppHandle, state: State<AppState>) -> AppResult<bool> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.require_complete_checklist()?)
+}
+
+#[tauri::command]
+fn set_require_complete_checklist(app_handle: AppHandle, state: State<AppState>, require: bool)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.set_require_complete_checklist(require)?)
+}
+
+#[tauri::command]
+fn comments_for_task(app_handle: AppHandle, state: State<AppState>, task_id: TaskId)
+               -> AppResult<Vec<Comment>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.comments_for_task(task_id)?)
+}
+
+#[tauri::command]
+fn add_comment(app_handle: AppHandle, state: State<AppState>, task_id: TaskId, data: EditableCommentData)
+               -> AppResult<GeneratedCommentData> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.add_comment(task_id, &data)?)
+}
+
+#[tauri::command]
+fn modify_comment(app_handle: AppHandle, state: State<AppState>, id: CommentId, data: EditableCommentData)
+               -> AppResult<ModifiedCommentData> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.modify_comment(id, &data)?)
+}
+
+#[tauri::command]
+fn delete_comment(app_handle: AppHandle, state: State<AppState>, id: CommentId) -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.delete_comment(id)?)
+}
+
+#[tauri::command]
+fn task_activity(app_handle: AppHandle, state: State<AppState>, task_id: TaskId) -> AppResult<Vec<Activity>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.task_activity(task_id)?)
+}
+
+#[tauri::command]
+fn attachments_for_task(app_handle: AppHandle, state: State<AppState>, task_id: TaskId) -> AppResult<Vec<Attachment>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.attachments_for_task(task_id)?)
+}
+
+#[tauri::command]
+fn add_attachment(app_handle: AppHandle, state: State<AppState>, task_id: TaskId, path: String) -> AppResult<Attachment> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.add_attachment(task_id, Path::new(&path))?)
+}
+
+#[tauri::command]
+fn attachment_path(app_handle: AppHandle, state: State<AppState>, id: AttachmentId) -> AppResult<PathBuf> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.attachment_path(id)?)
+}
+
+#[tauri::command]
+fn delete_attachment(app_handle: AppHandle, state: State<AppState>, id: AttachmentId) -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.delete_attachment(id)?)
+}
+
+#[tauri::command]
+fn collect_attachment_garbage(app_handle: AppHandle, state: State<AppState>) -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.collect_attachment_garbage()?)
+}
+
+#[tauri::command]
+fn backup(app_handle: AppHandle, state: State<AppState>, directory: String) -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.backup(Path::new(&directory))?)
+}
+
+#[tauri::command]
+fn task_links(app_handle: AppHandle, state: State<AppState>, task_id: TaskId) -> AppResult<Vec<TaskLink>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.task_links(task_id)?)
+}
+
+#[tauri::command]
+fn backlinks(app_handle: AppHandle, state: State<AppState>, task_id: TaskId) -> AppResult<Vec<TaskLink>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.backlinks(task_id)?)
+}
+
+#[tauri::command]
+fn broken_links(app_handle: AppHandle, state: State<AppState>) -> AppResult<Vec<TaskLink>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.broken_links()?)
+}
+
+#[tauri::command]
+fn rendered_body(app_handle: AppHandle, state: State<AppState>, task_id: TaskId) -> AppResult<RenderedBody> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.rendered_body(task_id)?)
+}
+
+#[tauri::command]
+fn body_outline(app_handle: AppHandle, state: State<AppState>, task_id: TaskId) -> AppResult<BodyOutline> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.body_outline(task_id)?)
+}
+
+#[tauri::command]
+fn render_draft_body(body: String) -> RenderedBody {
+    render_body(&body)
+}
+
+#[tauri::command]
+fn all_templates(app_handle: AppHandle, state: State<AppState>) -> AppResult<Vec<Template>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.all_templates()?)
+}
+
+#[tauri::command]
+fn add_new_template(app_handle: AppHandle, state: State<AppState>, data: EditableTemplateData)
+               -> AppResult<GeneratedTemplateData> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.add_new_template(&data)?)
+}
+
+#[tauri::command]
+fn modify_template(app_handle: AppHandle, state: State<AppState>, id: TemplateId, data: EditableTemplateData)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.modify_template(id, &data)?)
+}
+
+#[tauri::command]
+fn delete_template(app_handle: AppHandle, state: State<AppState>, id: TemplateId) -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.delete_template(id)?)
+}
+
+#[tauri::command]
+fn instantiate_template(app_handle: AppHandle, state: State<AppState>, id: TemplateId, values: HashMap<String, String>)
+               -> AppResult<TemplateInstance> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    let now = Utc::now().with_timezone(&db.user_timezone()?);
+    Ok(db.instantiate_template(id, &values, &now)?)
+}
+
+#[tauri::command]
+fn add_task_from_template(app_handle: AppHandle, state: State<AppState>, id: TemplateId, values: HashMap<String, String>)
+               -> AppResult<GeneratedTaskData> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    let now = Utc::now().with_timezone(&db.user_timezone()?);
+    Ok(db.add_task_from_template(id, &values, &now)?)
+}
+
+#[tauri::command]
+fn duplicate_task(app_handle: AppHandle, state: State<AppState>, id: TaskId, keep_tag: bool)
+               -> AppResult<GeneratedTaskData> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.duplicate_task(id, keep_tag)?)
+}
+
+#[tauri::command]
+fn split_task(app_handle: AppHandle, state: State<AppState>, id: TaskId, lines: Vec<usize>)
+               -> AppResult<Vec<GeneratedTaskData>> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.split_task(id, &lines)?)
+}
+
+#[tauri::command]
+fn merge_tasks(app_handle: AppHandle, state: State<AppState>, into: TaskId, others: Vec<TaskId>) -> AppResult<Task> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.merge_tasks(into, &others)?)
+}
+
+
+#[tauri::command]
+fn bulk_update(app_handle: AppHandle, state: State<AppState>, target: BulkTarget, action: BulkAction,
+               rollback_on_failure: bool) -> AppResult<BulkReport> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.bulk_update(&target, &action, rollback_on_failure)?.into())
+}
+
+#[tauri::command]
+fn defer_task(app_handle: AppHandle, state: State<AppState>, id: TaskId, until: Option<MyDateTime>)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.defer_task(id, until.map(DateTime::from))?)
+}
+
+#[tauri::command]
+fn snooze_task(app_handle: AppHandle, state: State<AppState>, id: TaskId, minutes: i64)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    let duration = checked_minutes(minutes).ok_or(DbError::DurationOutOfRangeError)?;
+    Ok(db.snooze_task(id, duration, Utc::now())?)
+}
+
+#[tauri::command]
+fn set_task_estimate(app_handle: AppHandle, state: State<AppState>, id: TaskId, estimate: TaskEstimate)
+               -> AppResult<()> {
+    setup_state(&app_handle, state.deref());
+    let mut binding = state.db.lock().unwrap();
+    let db = binding.as_mut().unwrap();
+    Ok(db.set_task_estimate(id, &estimate)?)
+}
+
+#[tauri::command]
+fn estimate_totals(app_handle: AppHandle, state: State<AppState>, filter: TaskFilterOptions)
+               -> AppResult<EstimateTotals> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.estimate_totals(|task| filter.passes_including_hidden(task), Utc::now())?)
+}
+
+#[tauri::command]
+fn tag_estimate_totals(app_handle: AppHandle, state: State<AppState>)
+               -> AppResult<Vec<TagEstimateTotals>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.tag_estimate_totals(Utc::now())?)
+}
+
+#[tauri::command]
+fn estimate_comparison(app_handle: AppHandle, state: State<AppState>, id: TaskId)
+               -> AppResult<Option<EstimateComparison>> {
+    setup_state(&app_handle, state.deref());
+    let binding = state.db.lock().unwrap();
+    let db = binding.as_ref().unwrap();
+    Ok(db.estimate_comparison(id)?)
+}
@@ -0,0 +1,165 @@
+use chrono::{DateTime, TimeZone, Utc, serde::ts_seconds};
+use pulldown_cmark::{Event, Options, Parser, Tag, html};
+use serde::{Deserialize, Serialize};
+use crate::local_time::{all_day_time, resolve_local};
+use crate::quick_add::{parse_date_time, split_words};
+
+/// Number of characters of the plain text of a body kept in its preview.
+pub const PREVIEW_LENGTH: usize = 140;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// The body of a task rendered from markdown, see `render_body`.
+pub struct RenderedBody {
+    /// Sanitized HTML, safe to insert into the page.
+    pub html: String,
+    /// The start of the plain text, on one line, for list views.
+    pub preview: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// What a markdown body mentions, see `outline_body`.
+pub struct BodyOutline {
+    pub headings: Vec<Heading>,
+    pub links: Vec<MarkdownLink>,
+    pub due_mentions: Vec<DueMention>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Heading {
+    /// 1 for `#`, up to 6 for `######`.
+    pub level: u32,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A link such as `[text](url)` or `<url>`.
+pub struct MarkdownLink {
+    pub text: String,
+    pub url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A due date written in the text, like `due tomorrow at 5pm` or `due 2023-09-01`.
+pub struct DueMention {
+    /// The mention as written.
+    pub text: String,
+    #[serde(with = "ts_seconds")]
+    pub time: DateTime<Utc>,
+    /// Whether only a date was written; see `local_time::all_day_time`.
+    pub all_day: bool,
+}
+
+/// Renders a markdown body to sanitized HTML along with a preview of its plain text.
+pub fn render_body(body: &str) -> RenderedBody {
+    RenderedBody {
+        html: render_html(body),
+        preview: preview(&read_markdown(body).text, PREVIEW_LENGTH),
+    }
+}
+
+/// The headings, links and due mentions of a markdown body. Relative dates of due mentions
+/// are resolved against `written`, the time the body was written, in its timezone.
+pub fn outline_body<Tz: TimeZone>(body: &str, written: &DateTime<Tz>) -> BodyOutline {
+    let read = read_markdown(body);
+    BodyOutline {
+        headings: read.headings,
+        links: read.links,
+        due_mentions: read.text.lines().flat_map(|line| due_mentions(line, written)).collect(),
+    }
+}
+
+/// Renders a markdown body to HTML, removing scripts, event handlers and anything else unsafe.
+pub fn render_html(body: &str) -> String {
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, Parser::new_ext(body, markdown_options()));
+    ammonia::clean(&unsafe_html)
+}
+
+struct ReadMarkdown {
+    /// The plain text, with a line for each block.
+    text: String,
+    headings: Vec<Heading>,
+    links: Vec<MarkdownLink>,
+}
+
+fn read_markdown(body: &str) -> ReadMarkdown {
+    let mut read = ReadMarkdown { text: String::new(), headings: vec![], links: vec![] };
+    let text = &mut read.text;
+    // the heading or link whose text is being read, and where its text starts
+    let mut heading = None;
+    let mut link = None;
+    for event in Parser::new_ext(body, markdown_options()) {
+        match event {
+            Event::Start(Tag::Heading(level, _, _)) => heading = Some((level as u32, text.len())),
+            Event::End(Tag::Heading(..)) => {
+                if let Some((level, start)) = heading.take() {
+                    read.headings.push(Heading { level, text: String::from(text[start..].trim()) });
+                }
+                text.push('\n');
+            }
+            Event::Start(Tag::Link(_, url, _)) => link = Some((url.into_string(), text.len())),
+            Event::End(Tag::Link(..)) => {
+                if let Some((url, start)) = link.take() {
+                    read.links.push(MarkdownLink { text: String::from(text[start..].trim()), url });
+                }
+            }
+            Event::End(Tag::Paragraph | Tag::Item | Tag::CodeBlock(_) | Tag::TableRow | Tag::TableHead) => text.push('\n'),
+            Event::End(Tag::TableCell) => text.push(' '),
+            Event::Text(content) | Event::Code(content) => text.push_str(&content),
+            Event::SoftBreak => text.push(' '),
+            Event::HardBreak => text.push('\n'),
+            _ => {}
+        }
+    }
+    read
+}
+
+fn markdown_options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS
+}
+
+/// The start of `text` with its whitespace collapsed, at most `length` characters long
+/// and ending with `…` if some were left out.
+fn preview(text: &str, length: usize) -> String {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.chars().count() <= length {
+        return collapsed;
+    }
+    let end = collapsed.char_indices().nth(length - 1).map(|(end, _)| end).unwrap_or(0);
+    format!("{}…", collapsed[..end].trim_end())
+}
+
+/// The mentions of `due` followed by a date and/or time in a line of text.
+fn due_mentions<Tz: TimeZone>(line: &str, written: &DateTime<Tz>) -> Vec<DueMention> {
+    let today = written.naive_local().date();
+    let words: Vec<(usize, &str)> = split_words(line).into_iter()
+        .map(|(start, word)| (start, word.trim_end_matches(|c: char| ",.;:!?)".contains(c))))
+        .collect();
+    let mut mentions = vec![];
+    let mut i = 0;
+    while i < words.len() {
+        if words[i].1.eq_ignore_ascii_case("due") {
+            if let Some((date, time, next)) = parse_date_time(&words, i + 1, today) {
+                let (time, all_day) = match time {
+                    Some(time) => (resolve_local(&written.timezone(), date.and_time(time)), false),
+                    None => (all_day_time(date), true),
+                };
+                let (last_start, last_word) = words[next - 1];
+                mentions.push(DueMention {
+                    text: String::from(&line[words[i].0..last_start + last_word.len()]),
+                    time,
+                    all_day,
+                });
+                i = next;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    mentions
+}
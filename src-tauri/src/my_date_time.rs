@@ -1,34 +1,66 @@
-use std::fmt::Formatter;
+use std::fmt::{Display, Formatter};
 use chrono::{DateTime, TimeZone, Utc};
 use rusqlite::ToSql;
-use rusqlite::types::{FromSql, FromSqlResult, ToSqlOutput, ValueRef};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use serde::de::{Error, Visitor};
+use serde::de::{Error, Unexpected, Visitor};
 
 /// A wrapper around a chrono datetime, restricted to a second for resolution.
-/// Serializes to an integer being the number of seconds from the last unix epoch.
-#[derive(Debug, PartialEq, Clone, PartialOrd)]
+/// Serializes to an integer being the number of seconds from the last unix epoch,
+/// and is stored in the database the same way.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash)]
 pub struct MyDateTime(pub DateTime<Utc>);
 
-impl From<i64> for MyDateTime {
-    fn from(value: i64) -> Self {
-        MyDateTime (
-            Utc.timestamp_opt(value, 0).unwrap()
-        )
+impl MyDateTime {
+    /// The current time, truncated to the second.
+    pub fn now() -> Self {
+        MyDateTime::from(Utc::now())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// A timestamp of `seconds` outside the range of times that can be represented.
+pub struct TimestampOutOfRangeError {
+    pub seconds: i64,
+}
+
+impl Display for TimestampOutOfRangeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "timestamp {} is out of range", self.seconds)
+    }
+}
+
+impl std::error::Error for TimestampOutOfRangeError {}
+
+impl TryFrom<i64> for MyDateTime {
+    type Error = TimestampOutOfRangeError;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        match Utc.timestamp_opt(value, 0).single() {
+            Some(time) => Ok(MyDateTime(time)),
+            None => Err(TimestampOutOfRangeError { seconds: value }),
+        }
     }
 }
 
 impl From<DateTime<Utc>> for MyDateTime {
     /// Note: may result in precision loss.
     fn from(value: DateTime<Utc>) -> Self {
-        MyDateTime::from(value.timestamp())
+        // the whole seconds of a time that exists are always in range
+        MyDateTime::try_from(value.timestamp()).unwrap()
+    }
+}
+
+impl From<MyDateTime> for DateTime<Utc> {
+    fn from(value: MyDateTime) -> Self {
+        value.0
     }
 }
 
 impl FromSql for MyDateTime {
     fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
         let timestamp_seconds = i64::column_result(value)?;
-        Ok(MyDateTime::from(timestamp_seconds))
+        MyDateTime::try_from(timestamp_seconds).map_err(|error| FromSqlError::Other(Box::new(error)))
     }
 }
 
@@ -55,10 +87,18 @@ impl<'de> Visitor<'de> for MyDateTimeVisitor {
     type Value = MyDateTime;
 
     fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
-        write!(formatter, "a 64 bit signed integer")
+        write!(formatter, "a 64 bit signed integer within the range of times")
     }
 
     fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> where E: Error {
-        Ok(MyDateTime::from(v))
+        MyDateTime::try_from(v).map_err(|_| E::invalid_value(Unexpected::Signed(v), &self))
+    }
+
+    // most formats, including json, give non-negative integers as unsigned
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> where E: Error {
+        match i64::try_from(v) {
+            Ok(v) => self.visit_i64(v),
+            Err(_) => Err(E::invalid_value(Unexpected::Unsigned(v), &self)),
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,179 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc, serde::ts_seconds};
+use serde::{Deserialize, Serialize};
+use crate::{Task, TaskEstimate, TaskId};
+
+pub type TimeBlockId = i64;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A block of time in the calendar, either for working on a task or for another event.
+pub struct TimeBlock {
+    pub id: TimeBlockId,
+    pub task_id: Option<TaskId>,
+    pub title: String,
+    #[serde(with = "ts_seconds")]
+    pub start_time: DateTime<Utc>,
+    #[serde(with = "ts_seconds")]
+    pub end_time: DateTime<Utc>,
+    /// Whether the block is an event that can't be moved, such as a meeting.
+    pub fixed: bool,
+    /// Whether the block was made by the auto-scheduler, which replaces only these blocks when
+    /// planning the day again and works around all others. Modifying a block makes it the user's.
+    #[serde(default)]
+    pub auto_scheduled: bool,
+}
+
+impl TimeBlock {
+    pub fn from_parts(editable: &EditableTimeBlockData, generated: &GeneratedTimeBlockData) -> Self {
+        Self {
+            id: generated.id,
+            task_id: editable.task_id,
+            title: editable.title.clone(),
+            start_time: editable.start_time,
+            end_time: editable.end_time,
+            fixed: editable.fixed,
+            auto_scheduled: false,
+        }
+    }
+    pub fn overlaps(&self, other: &TimeBlock) -> bool {
+        self.start_time < other.end_time && other.start_time < self.end_time
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `TimeBlock` modifiable by the client.
+pub struct EditableTimeBlockData {
+    pub task_id: Option<TaskId>,
+    pub title: String,
+    #[serde(with = "ts_seconds")]
+    pub start_time: DateTime<Utc>,
+    #[serde(with = "ts_seconds")]
+    pub end_time: DateTime<Utc>,
+    pub fixed: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `TimeBlock` determined by the database when a new block is created.
+pub struct GeneratedTimeBlockData {
+    pub id: TimeBlockId,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// How the auto-scheduler plans a day.
+pub struct PlannerSettings {
+    /// Working hours, in the timezone of the user.
+    pub work_start: NaiveTime,
+    pub work_end: NaiveTime,
+    /// A task without an estimate is estimated to take this many minutes per point of difficulty,
+    /// and at least this many. At most `TaskEstimate::MAX_MINUTES`.
+    pub minutes_per_difficulty: i64,
+}
+
+impl Default for PlannerSettings {
+    fn default() -> Self {
+        Self {
+            work_start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            work_end: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            minutes_per_difficulty: 30,
+        }
+    }
+}
+
+impl PlannerSettings {
+    pub fn is_valid(&self) -> bool {
+        self.work_start < self.work_end && self.minutes_per_difficulty > 0
+            && self.minutes_per_difficulty <= TaskEstimate::MAX_MINUTES
+    }
+    /// How long `task` is estimated to take: its remaining effort if that is positive,
+    /// otherwise going by its difficulty. At most `TaskEstimate::MAX_MINUTES`.
+    pub fn estimate(&self, task: &Task) -> Duration {
+        let minutes = match task.remaining_effort_minutes() {
+            Some(minutes) if minutes > 0 => minutes,
+            _ => self.minutes_per_difficulty.saturating_mul(task.difficulty.max(1) as i64),
+        };
+        Duration::minutes(minutes.min(TaskEstimate::MAX_MINUTES))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Two time blocks that overlap.
+pub struct TimeBlockConflict {
+    pub first: TimeBlockId,
+    pub second: TimeBlockId,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// The time blocks of a day in the timezone of the user.
+pub struct DayPlan {
+    pub date: NaiveDate,
+    /// In order of start time.
+    pub blocks: Vec<TimeBlock>,
+    pub conflicts: Vec<TimeBlockConflict>,
+}
+
+/// Every pair of overlapping blocks, given in order of start time.
+pub fn find_conflicts(blocks: &[TimeBlock]) -> Vec<TimeBlockConflict> {
+    let mut conflicts = vec![];
+    for (i, first) in blocks.iter().enumerate() {
+        for second in blocks[i + 1..].iter().take_while(|second| second.start_time < first.end_time) {
+            if first.overlaps(second) {
+                conflicts.push(TimeBlockConflict { first: first.id, second: second.id });
+            }
+        }
+    }
+    conflicts
+}
+
+/// Fits `tasks` into the gaps between `busy` blocks from `start` to `end`.
+///
+/// Tasks are taken in order of due time, then target time, then shortest estimate,
+/// tasks without a time coming last. Each goes in the earliest gap long enough for its estimate,
+/// and tasks that don't fit anywhere are left out.
+///
+/// Returns the new blocks, in order of start time.
+pub fn auto_schedule(tasks: &[Task], busy: &[TimeBlock], start: DateTime<Utc>, end: DateTime<Utc>,
+                     settings: &PlannerSettings) -> Vec<EditableTimeBlockData> {
+    let mut tasks: Vec<&Task> = tasks.iter().collect();
+    tasks.sort_by_key(|task| (
+        task.due_time.is_none(), task.due_time,
+        task.target_time.is_none(), task.target_time,
+        settings.estimate(task),
+    ));
+
+    // free gaps, as start and end
+    let mut gaps = vec![];
+    let mut gap_start = start;
+    let mut busy: Vec<&TimeBlock> = busy.iter().collect();
+    busy.sort_by_key(|block| block.start_time);
+    for block in busy {
+        if block.start_time > gap_start && gap_start < end {
+            gaps.push((gap_start, block.start_time.min(end)));
+        }
+        gap_start = gap_start.max(block.end_time);
+    }
+    if gap_start < end {
+        gaps.push((gap_start, end));
+    }
+
+    let mut scheduled = vec![];
+    for task in tasks {
+        let estimate = settings.estimate(task);
+        if let Some(gap) = gaps.iter_mut().find(|(start, end)| *end - *start >= estimate) {
+            scheduled.push(EditableTimeBlockData {
+                task_id: Some(task.id),
+                title: task.title.clone(),
+                start_time: gap.0,
+                end_time: gap.0 + estimate,
+                fixed: false,
+            });
+            gap.0 += estimate;
+        }
+    }
+    scheduled.sort_by_key(|block| block.start_time);
+    scheduled
+}
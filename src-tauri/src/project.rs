@@ -0,0 +1,103 @@
+use chrono::{DateTime, Utc, serde::ts_seconds, serde::ts_seconds_option};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+use rusqlite::ToSql;
+use serde::{Deserialize, Serialize};
+
+pub type ProjectId = i64;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+/// The state of work on a project.
+/// Implements `ToSql` and `FromSql` so this can be stored in a single SQL column.
+pub enum ProjectStatus {
+    Active,
+    OnHold,
+    Done,
+}
+
+impl ToSql for ProjectStatus {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(match self {
+            ProjectStatus::Active => "active",
+            ProjectStatus::OnHold => "onHold",
+            ProjectStatus::Done => "done",
+        }))
+    }
+}
+
+impl FromSql for ProjectStatus {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "active" => Ok(ProjectStatus::Active),
+            "onHold" => Ok(ProjectStatus::OnHold),
+            "done" => Ok(ProjectStatus::Done),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Represents a project in this application, an ordered list of tasks working towards one goal.
+/// A task is in at most one project.
+pub struct Project {
+    pub id: ProjectId,
+    pub name: String,
+    pub description: String,
+    pub status: ProjectStatus,
+    #[serde(with = "ts_seconds_option")]
+    pub deadline: Option<DateTime<Utc>>,
+    /// The tasks of an archived project are hidden unless asked for; see `TaskFilterOptions`.
+    pub archived: bool,
+    #[serde(with = "ts_seconds")]
+    pub create_time: DateTime<Utc>,
+}
+
+impl Project {
+    pub fn from_parts(editable: &EditableProjectData, generated: &GeneratedProjectData) -> Self {
+        Self {
+            id: generated.id,
+            name: editable.name.clone(),
+            description: editable.description.clone(),
+            status: editable.status,
+            deadline: editable.deadline,
+            archived: false,
+            create_time: generated.create_time,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `Project` modifiable by the client.
+pub struct EditableProjectData {
+    pub name: String,
+    pub description: String,
+    pub status: ProjectStatus,
+    #[serde(with = "ts_seconds_option")]
+    pub deadline: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `Project` determined by the database when a new project is created.
+pub struct GeneratedProjectData {
+    pub id: ProjectId,
+    #[serde(with = "ts_seconds")]
+    pub create_time: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// How far along the tasks of a project are.
+pub struct ProjectProgress {
+    pub project_id: ProjectId,
+    pub total: i64,
+    pub completed: i64,
+    /// Total difficulty of all the tasks.
+    pub difficulty_total: i64,
+    /// Total difficulty of the tasks completed.
+    pub difficulty_completed: i64,
+    /// `completed / total`, or `None` if the project has no tasks.
+    pub completion_rate: Option<f64>,
+}
@@ -0,0 +1,271 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday, serde::ts_seconds};
+use serde::{Deserialize, Serialize};
+use crate::{EditableTaskData, GeneratedTaskData, Tag, TagId};
+use crate::local_time::{all_day_time, checked_days, resolve_local};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// The result of parsing a quick-add line such as `"Write report #work tomorrow 5pm !3 target fri"`.
+pub struct QuickAddData {
+    /// The task described by the line.
+    pub data: EditableTaskData,
+    /// Every recognized piece of the line, in order of appearance.
+    pub pieces: Vec<QuickAddPiece>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A recognized piece of a quick-add line. `start` and `end` are offsets into the line
+/// in UTF-16 code units, matching JavaScript string indexing, so the UI can highlight it.
+pub struct QuickAddPiece {
+    pub kind: QuickAddPieceKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum QuickAddPieceKind {
+    /// A word of the title.
+    Title,
+    /// `#name`; `id` is `None` if no tag has this name.
+    Tag { name: String, id: Option<TagId> },
+    /// `!3`
+    Difficulty { value: i32 },
+    /// A date and/or time, optionally preceded by `due`.
+    /// A date without a time is an all-day due date.
+    DueTime {
+        #[serde(with = "ts_seconds")]
+        time: DateTime<Utc>,
+        all_day: bool,
+    },
+    /// A date and/or time preceded by `target`.
+    TargetTime {
+        #[serde(with = "ts_seconds")]
+        time: DateTime<Utc>,
+    },
+    /// `+paused`
+    Paused,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `Task` created through a quick-add line.
+pub struct QuickAddedTaskData {
+    pub parsed: QuickAddData,
+    pub generated: GeneratedTaskData,
+}
+
+/// Parses a quick-add line into the task it describes.
+///
+/// Recognized pieces are:
+/// * `#name`: the tag with this name (case insensitive), if one of `tags` has it.
+/// * `!n`: difficulty `n`.
+/// * `+paused`: a paused task.
+/// * `[due|target] date [[at] time]` or `[due|target] time`: the due time (default) or target time.
+///
+/// Dates are `today`, `tomorrow`, a weekday (`fri`, `friday`), which is the next such day after today,
+/// `in n days` or `in n weeks`, or `yyyy-mm-dd`. Times are `5pm`, `5:30pm`, `17:00` or `noon`.
+/// A time without a date is today. A due date without a time is an all-day due date,
+/// and a target date without a time is the end of that day.
+/// Dates are resolved relative to `now`, in its timezone.
+///
+/// All other words make up the title.
+pub fn parse_quick_add<Tz: TimeZone>(input: &str, now: &DateTime<Tz>, tags: &[Tag]) -> QuickAddData {
+    let words = split_words(input);
+    let today = now.naive_local().date();
+    let mut data = EditableTaskData {
+        title: String::new(),
+        tag: None,
+        body: String::new(),
+        difficulty: 0,
+        due_time: None,
+        due_all_day: false,
+        target_time: None,
+        paused: false,
+    };
+    let mut title_words = vec![];
+    let mut pieces = vec![];
+
+    let mut i = 0;
+    while i < words.len() {
+        let (start, word) = words[i];
+        let lower = word.to_lowercase();
+        let mut piece = |kind, last: usize| {
+            let (last_start, last_word) = words[last];
+            pieces.push(QuickAddPiece {
+                kind,
+                start: utf16_offset(input, start),
+                end: utf16_offset(input, last_start + last_word.len()),
+            });
+        };
+
+        if let Some(name) = word.strip_prefix('#').filter(|name| !name.is_empty()) {
+            let id = tags.iter()
+                .find(|tag| tag.name.to_lowercase() == name.to_lowercase())
+                .map(|tag| tag.id);
+            if id.is_some() {
+                data.tag = id;
+            }
+            piece(QuickAddPieceKind::Tag { name: name.to_string(), id }, i);
+            i += 1;
+            continue;
+        }
+        if let Some(value) = word.strip_prefix('!').and_then(|value| value.parse::<i32>().ok()) {
+            data.difficulty = value;
+            piece(QuickAddPieceKind::Difficulty { value }, i);
+            i += 1;
+            continue;
+        }
+        if lower == "+paused" {
+            data.paused = true;
+            piece(QuickAddPieceKind::Paused, i);
+            i += 1;
+            continue;
+        }
+
+        let (is_target, date_start) = match lower.as_str() {
+            "target" => (true, i + 1),
+            "due" => (false, i + 1),
+            _ => (false, i),
+        };
+        if let Some((date, time, next)) = parse_date_time(&words, date_start, today) {
+            if is_target {
+                let time = resolve_local(&now.timezone(), date.and_time(time.unwrap_or_else(end_of_day)));
+                data.target_time = Some(time);
+                piece(QuickAddPieceKind::TargetTime { time }, next - 1);
+            } else {
+                let (time, all_day) = match time {
+                    Some(time) => (resolve_local(&now.timezone(), date.and_time(time)), false),
+                    None => (all_day_time(date), true),
+                };
+                data.due_time = Some(time);
+                data.due_all_day = all_day;
+                piece(QuickAddPieceKind::DueTime { time, all_day }, next - 1);
+            }
+            i = next;
+            continue;
+        }
+
+        title_words.push(word);
+        piece(QuickAddPieceKind::Title, i);
+        i += 1;
+    }
+
+    data.title = title_words.join(" ");
+    QuickAddData { data, pieces }
+}
+
+/// Splits a line into its whitespace separated words, along with their byte offsets.
+pub(crate) fn split_words(input: &str) -> Vec<(usize, &str)> {
+    let mut words = vec![];
+    let mut word_start = None;
+    for (index, c) in input.char_indices() {
+        match (c.is_whitespace(), word_start) {
+            (true, Some(start)) => {
+                words.push((start, &input[start..index]));
+                word_start = None;
+            }
+            (false, None) => word_start = Some(index),
+            _ => {}
+        }
+    }
+    if let Some(start) = word_start {
+        words.push((start, &input[start..]));
+    }
+    words
+}
+
+fn utf16_offset(input: &str, byte_offset: usize) -> usize {
+    input[..byte_offset].encode_utf16().count()
+}
+
+/// Parses a date and/or time starting at `words[i]`,
+/// returning it along with the index of the first word after it.
+pub(crate) fn parse_date_time(words: &[(usize, &str)], i: usize, today: NaiveDate) -> Option<(NaiveDate, Option<NaiveTime>, usize)> {
+    if let Some((date, next)) = parse_date(words, i, today) {
+        let time_start = match words.get(next) {
+            Some((_, word)) if word.eq_ignore_ascii_case("at") => next + 1,
+            _ => next,
+        };
+        return match words.get(time_start).and_then(|(_, word)| parse_time(word)) {
+            Some(time) => Some((date, Some(time), time_start + 1)),
+            None => Some((date, None, next)),
+        };
+    }
+    let time_start = match words.get(i) {
+        Some((_, word)) if word.eq_ignore_ascii_case("at") => i + 1,
+        _ => i,
+    };
+    let time = parse_time(words.get(time_start)?.1)?;
+    Some((today, Some(time), time_start + 1))
+}
+
+fn parse_date(words: &[(usize, &str)], i: usize, today: NaiveDate) -> Option<(NaiveDate, usize)> {
+    let word = words.get(i)?.1.to_lowercase();
+    match word.as_str() {
+        "today" | "tod" => return Some((today, i + 1)),
+        "tomorrow" | "tmr" | "tmrw" => return Some((today + Duration::days(1), i + 1)),
+        "in" => {
+            let count: i64 = words.get(i + 1)?.1.parse().ok()?;
+            let unit = words.get(i + 2)?.1.to_lowercase();
+            let days = match unit.as_str() {
+                "day" | "days" | "d" => count,
+                "week" | "weeks" | "w" => count.checked_mul(7)?,
+                _ => return None,
+            };
+            // a count too large for a date is left as part of the title
+            return Some((today.checked_add_signed(checked_days(days)?)?, i + 3));
+        }
+        _ => {}
+    }
+    if let Some(weekday) = parse_weekday(&word) {
+        let days_ahead = (weekday.num_days_from_monday() as i64
+            - today.weekday().num_days_from_monday() as i64 + 6) % 7 + 1;
+        return Some((today + Duration::days(days_ahead), i + 1));
+    }
+    NaiveDate::parse_from_str(&word, "%Y-%m-%d").ok().map(|date| (date, i + 1))
+}
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    Some(match word {
+        "mon" | "monday" => Weekday::Mon,
+        "tue" | "tues" | "tuesday" => Weekday::Tue,
+        "wed" | "wednesday" => Weekday::Wed,
+        "thu" | "thur" | "thurs" | "thursday" => Weekday::Thu,
+        "fri" | "friday" => Weekday::Fri,
+        "sat" | "saturday" => Weekday::Sat,
+        "sun" | "sunday" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+fn parse_time(word: &str) -> Option<NaiveTime> {
+    let word = word.to_lowercase();
+    if word == "noon" {
+        return NaiveTime::from_hms_opt(12, 0, 0);
+    }
+    let (clock, pm) = if let Some(clock) = word.strip_suffix("pm") {
+        (clock, Some(true))
+    } else if let Some(clock) = word.strip_suffix("am") {
+        (clock, Some(false))
+    } else {
+        (word.as_str(), None)
+    };
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) if minute.len() == 2 => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        // a bare number is only a time with am/pm, otherwise it is likely part of the title
+        None if pm.is_some() => (clock.parse::<u32>().ok()?, 0),
+        _ => return None,
+    };
+    let hour = match pm {
+        Some(pm) if (1..=12).contains(&hour) => hour % 12 + if pm { 12 } else { 0 },
+        Some(_) => return None,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn end_of_day() -> NaiveTime {
+    NaiveTime::from_hms_opt(23, 59, 59).unwrap()
+}
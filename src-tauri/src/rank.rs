@@ -0,0 +1,87 @@
+/// Digits of rank keys, in ascending order as bytes so keys sort as plain strings.
+const RANK_DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const RANK_BASE: u8 = RANK_DIGITS.len() as u8;
+
+fn digit_value(digit: u8) -> u8 {
+    RANK_DIGITS.iter().position(|d| *d == digit).expect("Invalid rank digit") as u8
+}
+
+/// A rank key that sorts between `before` and `after`, where either end may be open.
+/// Keys are fractions in base 62 after an implicit point, written without trailing zeros,
+/// so there is always room for another key between any two different keys.
+///
+/// `before` must sort before `after`.
+pub fn rank_between(before: Option<&str>, after: Option<&str>) -> String {
+    let before: Vec<u8> = before.unwrap_or("").bytes().map(digit_value).collect();
+    let after: Option<Vec<u8>> = after.map(|after| after.bytes().map(digit_value).collect());
+    midpoint(&before, after.as_deref()).iter().map(|value| RANK_DIGITS[*value as usize] as char).collect()
+}
+
+/// A rank key that sorts after `before`, to append a key after the last one.
+/// Unlike `rank_between` with an open end, it increments the first digit of `before` that can be,
+/// dropping the digits after it, so keys only grow when every digit is the highest one.
+pub fn rank_after(before: Option<&str>) -> String {
+    let before = match before {
+        Some(before) => before,
+        None => return rank_between(None, None),
+    };
+    let highest = RANK_DIGITS[RANK_BASE as usize - 1];
+    match before.bytes().position(|digit| digit != highest) {
+        Some(i) => {
+            let mut key = before[..i].to_string();
+            key.push(RANK_DIGITS[digit_value(before.as_bytes()[i]) as usize + 1] as char);
+            key
+        }
+        None => format!("{}{}", before, RANK_DIGITS[1] as char),
+    }
+}
+
+fn midpoint(before: &[u8], after: Option<&[u8]>) -> Vec<u8> {
+    if let Some(after) = after {
+        // keep the common prefix, missing digits of `before` being zero
+        let common = after.iter().enumerate()
+            .take_while(|(i, digit)| before.get(*i).copied().unwrap_or(0) == **digit)
+            .count();
+        if common > 0 {
+            let mut key = after[..common].to_vec();
+            key.extend(midpoint(before.get(common..).unwrap_or(&[]), Some(&after[common..])));
+            return key;
+        }
+    }
+    let low = before.first().copied().unwrap_or(0);
+    let high = after.map_or(RANK_BASE, |after| after[0]);
+    if high - low > 1 {
+        vec![(low + high) / 2]
+    } else if matches!(after, Some(after) if after.len() > 1) {
+        // the first digit of `after` alone sorts before it, and after `before`
+        vec![high]
+    } else {
+        let mut key = vec![low];
+        key.extend(midpoint(before.get(1..).unwrap_or(&[]), None));
+        key
+    }
+}
+
+/// `count` evenly spaced rank keys in ascending order, all of about the same length.
+pub fn rank_sequence(count: usize) -> Vec<String> {
+    let slots = count as u128 + 1;
+    let mut length = 1;
+    let mut space = RANK_BASE as u128;
+    // leave room for a few keys between each pair before they grow longer
+    while space < slots * RANK_BASE as u128 {
+        length += 1;
+        space *= RANK_BASE as u128;
+    }
+    (1..slots).map(|slot| {
+        let mut value = slot * space / slots;
+        let mut digits = vec![0; length];
+        for digit in digits.iter_mut().rev() {
+            *digit = RANK_DIGITS[(value % RANK_BASE as u128) as usize];
+            value /= RANK_BASE as u128;
+        }
+        while digits.last() == Some(&RANK_DIGITS[0]) {
+            digits.pop();
+        }
+        String::from_utf8(digits).unwrap()
+    }).collect()
+}
@@ -0,0 +1,171 @@
+use chrono::{DateTime, Duration, TimeZone, Utc, serde::ts_seconds, serde::ts_seconds_option};
+use serde::{Deserialize, Serialize};
+use crate::{Clock, Db, DbResult, Task, TaskId};
+use crate::local_time::{checked_seconds, day_start};
+
+pub type ReminderId = i64;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// When a reminder should fire.
+pub enum ReminderTrigger {
+    /// At an absolute time.
+    At {
+        #[serde(with = "ts_seconds")]
+        time: DateTime<Utc>,
+    },
+    /// Some seconds before the due time of its task.
+    #[serde(rename_all = "camelCase")]
+    BeforeDue { offset_seconds: i64 },
+    /// Some seconds before the target time of its task.
+    #[serde(rename_all = "camelCase")]
+    BeforeTarget { offset_seconds: i64 },
+}
+
+impl ReminderTrigger {
+    /// The most seconds a reminder can fire before (or after, if negative) the time it is relative to.
+    pub const MAX_OFFSET_SECONDS: i64 = 366 * 24 * 60 * 60;
+
+    /// Whether the offset of this trigger, if it has one, is within `MAX_OFFSET_SECONDS`.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            ReminderTrigger::At { .. } => true,
+            ReminderTrigger::BeforeDue { offset_seconds } | ReminderTrigger::BeforeTarget { offset_seconds } =>
+                (-ReminderTrigger::MAX_OFFSET_SECONDS..=ReminderTrigger::MAX_OFFSET_SECONDS).contains(offset_seconds),
+        }
+    }
+
+    /// The time this trigger fires for `task`, or `None` if `task` has no time it is relative to,
+    /// or the offset moves it out of the range of times.
+    /// An all-day due date is relative to the start of that date in `timezone`.
+    pub fn fire_time<Tz: TimeZone>(&self, task: &Task, timezone: &Tz) -> Option<DateTime<Utc>> {
+        let before = |time: Option<DateTime<Utc>>, offset_seconds: i64| {
+            time?.checked_sub_signed(checked_seconds(offset_seconds)?)
+        };
+        match self {
+            ReminderTrigger::At { time } => Some(*time),
+            ReminderTrigger::BeforeDue { offset_seconds } if task.due_all_day =>
+                before(task.due_date(timezone).map(|date| day_start(timezone, date)), *offset_seconds),
+            ReminderTrigger::BeforeDue { offset_seconds } => before(task.due_time, *offset_seconds),
+            ReminderTrigger::BeforeTarget { offset_seconds } => before(task.target_time, *offset_seconds),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Represents a reminder of a task in this application.
+pub struct Reminder {
+    pub id: ReminderId,
+    pub task_id: TaskId,
+    pub trigger: ReminderTrigger,
+    /// If set, the reminder fires at this time instead of its trigger.
+    #[serde(with = "ts_seconds_option")]
+    pub snoozed_until: Option<DateTime<Utc>>,
+    pub dismissed: bool,
+    /// The last time this reminder fired, so it fires at most once per fire time.
+    #[serde(with = "ts_seconds_option")]
+    pub last_fired_time: Option<DateTime<Utc>>,
+}
+
+impl Reminder {
+    pub fn from_parts(editable: &EditableReminderData, generated: &GeneratedReminderData) -> Self {
+        Self {
+            id: generated.id,
+            task_id: editable.task_id,
+            trigger: editable.trigger.clone(),
+            snoozed_until: None,
+            dismissed: false,
+            last_fired_time: None,
+        }
+    }
+
+    /// The time this reminder of `task` should next fire, or `None` if it never will.
+    /// A snooze only counts until the reminder fires again.
+    pub fn fire_time<Tz: TimeZone>(&self, task: &Task, timezone: &Tz) -> Option<DateTime<Utc>> {
+        if self.dismissed || task.is_done() {
+            return None;
+        }
+        let snoozed_until = self.snoozed_until.filter(|until| Some(*until) > self.last_fired_time);
+        let fire_time = snoozed_until.or_else(|| self.trigger.fire_time(task, timezone))?;
+        match self.last_fired_time {
+            Some(last_fired_time) if last_fired_time >= fire_time => None,
+            _ => Some(fire_time),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `Reminder` modifiable by the client.
+pub struct EditableReminderData {
+    pub task_id: TaskId,
+    pub trigger: ReminderTrigger,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `Reminder` determined by the database when a new reminder is created.
+pub struct GeneratedReminderData {
+    pub id: ReminderId,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A reminder along with its task and the time it should fire.
+pub struct ScheduledReminder {
+    pub reminder: Reminder,
+    pub task: Task,
+    #[serde(with = "ts_seconds")]
+    pub fire_time: DateTime<Utc>,
+}
+
+impl ScheduledReminder {
+    /// A short description of why this reminder fired, for notifications.
+    pub fn message(&self) -> String {
+        match self.reminder.trigger {
+            _ if self.reminder.snoozed_until.is_some() => String::from("Snoozed reminder"),
+            ReminderTrigger::At { .. } => String::from("Reminder"),
+            ReminderTrigger::BeforeDue { .. } => String::from("Due soon"),
+            ReminderTrigger::BeforeTarget { .. } => String::from("Target time coming up"),
+        }
+    }
+}
+
+/// Receives reminders as they fire, such as to show a desktop notification.
+pub trait Notifier {
+    fn notify(&self, reminder: &ScheduledReminder);
+}
+
+/// Fires the reminders stored in a `Db` once their time has come.
+pub struct ReminderScheduler<C: Clock, N: Notifier> {
+    pub clock: C,
+    pub notifier: N,
+}
+
+impl<C: Clock, N: Notifier> ReminderScheduler<C, N> {
+    pub fn new(clock: C, notifier: N) -> Self {
+        Self { clock, notifier }
+    }
+
+    /// Notifies of every reminder whose time has come, in order of fire time, and marks them fired.
+    /// This includes reminders missed while the application was closed.
+    ///
+    /// Returns the fire time of the next reminder still to come, if any.
+    pub fn tick(&self, db: &mut Db) -> DbResult<Option<DateTime<Utc>>> {
+        let now = self.clock.now();
+        for scheduled in db.scheduled_reminders()? {
+            if scheduled.fire_time > now {
+                return Ok(Some(scheduled.fire_time));
+            }
+            self.notifier.notify(&scheduled);
+            db.mark_reminder_fired(scheduled.reminder.id, now)?;
+        }
+        Ok(None)
+    }
+
+    /// Snoozes a reminder so it fires again `duration` from now.
+    pub fn snooze(&self, db: &mut Db, id: ReminderId, duration: Duration) -> DbResult<()> {
+        db.snooze_reminder(id, duration, self.clock.now())
+    }
+}
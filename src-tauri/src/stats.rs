@@ -0,0 +1,122 @@
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use crate::TagId;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+/// A length of time statistics are grouped by. Weeks start on Monday.
+pub enum StatsPeriod {
+    Day,
+    Week,
+    Month,
+}
+
+/// The most days statistics can be computed over at once, about a century.
+pub const MAX_RANGE_DAYS: i64 = 100 * 366;
+
+impl StatsPeriod {
+    /// The first day of the period containing `date`, or `None` if that is out of the range of dates.
+    pub fn start_of(self, date: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            StatsPeriod::Day => Some(date),
+            StatsPeriod::Week => date.checked_sub_days(Days::new(date.weekday().num_days_from_monday() as u64)),
+            StatsPeriod::Month => date.with_day(1),
+        }
+    }
+
+    /// The first day of the period after the one starting on `start`,
+    /// or `None` if that is out of the range of dates.
+    pub fn next_start(self, start: NaiveDate) -> Option<NaiveDate> {
+        self.advance(start, 1)
+    }
+
+    /// `date` moved ahead by `count` periods, or `None` if that is out of the range of dates.
+    pub fn advance(self, date: NaiveDate, count: u32) -> Option<NaiveDate> {
+        match self {
+            StatsPeriod::Day => date.checked_add_days(Days::new(count as u64)),
+            StatsPeriod::Week => date.checked_add_days(Days::new(7 * count as u64)),
+            StatsPeriod::Month => date.checked_add_months(Months::new(count)),
+        }
+    }
+
+    /// The periods overlapping `first` to `last` inclusive, as their start along with
+    /// the days of that period within the range, from the first to the last day exclusive.
+    /// Returns `None` if the first period or the day after `last` is out of the range of dates.
+    pub fn ranges(self, first: NaiveDate, last: NaiveDate) -> Option<Vec<(NaiveDate, NaiveDate, NaiveDate)>> {
+        let mut ranges = vec![];
+        let mut start = self.start_of(first)?;
+        let end = last.succ_opt()?;
+        while start < end {
+            // the range ends before the next period does if that is out of the range of dates
+            let next = self.next_start(start).map_or(end, |next| next.min(end));
+            ranges.push((start, start.max(first), next));
+            start = next;
+        }
+        Some(ranges)
+    }
+}
+
+/// `part / whole`, or `None` if `whole` is zero.
+pub(crate) fn ratio(part: i64, whole: i64) -> Option<f64> {
+    if whole == 0 {
+        None
+    } else {
+        Some(part as f64 / whole as f64)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Tasks created and completed during a period.
+pub struct PeriodStats {
+    /// The first day of the period.
+    pub start: NaiveDate,
+    pub created: i64,
+    pub completed: i64,
+    /// Total difficulty of the tasks completed.
+    pub difficulty_completed: i64,
+    /// Total estimate of the tasks completed; tasks without an estimate count none.
+    pub estimate_minutes_completed: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// How many of the tasks finished with a deadline made it.
+pub struct OnTimeStats {
+    pub finished: i64,
+    pub on_time: i64,
+    /// `on_time / finished`, or `None` if no task with a deadline was finished.
+    pub rate: Option<f64>,
+}
+
+impl OnTimeStats {
+    pub fn new(finished: i64, on_time: i64) -> Self {
+        Self { finished, on_time, rate: ratio(on_time, finished) }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Statistics of the tasks created and completed over a range of days.
+pub struct SummaryStats {
+    pub created: i64,
+    pub completed: i64,
+    /// `completed / created`, or `None` if no task was created.
+    pub completion_rate: Option<f64>,
+    /// The average seconds from creating to finishing the completed tasks.
+    pub average_lead_seconds: Option<f64>,
+    /// Completed tasks finished by their due time. An all-day due date is made if finished that day.
+    pub due: OnTimeStats,
+    /// Completed tasks finished by their target time.
+    pub target: OnTimeStats,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Tasks completed with a tag over a range of days; `tag_id` is `None` for tasks without a tag.
+pub struct TagStats {
+    pub tag_id: Option<TagId>,
+    pub completed: i64,
+    pub difficulty_completed: i64,
+    pub estimate_minutes_completed: i64,
+}
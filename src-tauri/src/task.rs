@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
-use crate::TagId;
-use chrono::{DateTime, Utc, serde::ts_seconds, serde::ts_seconds_option};
+use crate::{ChecklistProgress, ProjectId, TagId};
+use crate::local_time::{all_day_date, all_day_time, local_date};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc, serde::ts_seconds, serde::ts_seconds_option};
 
 pub type TaskId = i64;
 
@@ -19,11 +20,35 @@ pub struct Task {
     pub last_edit_time: DateTime<Utc>,
     #[serde(with = "ts_seconds_option")]
     pub due_time: Option<DateTime<Utc>>,
+    /// Whether `due_time` is an all-day date rather than a time; see `local_time::all_day_time`.
+    #[serde(default)]
+    pub due_all_day: bool,
     #[serde(with = "ts_seconds_option")]
     pub target_time: Option<DateTime<Utc>>,
     #[serde(with = "ts_seconds_option")]
     pub done_time: Option<DateTime<Utc>>,
     pub paused: bool,
+    /// The project this task is in, set by `Db::move_task_to_project`.
+    #[serde(default)]
+    pub project: Option<ProjectId>,
+    /// Whether the project of this task is archived.
+    #[serde(default)]
+    pub archived: bool,
+    /// Progress through the checklist of this task.
+    #[serde(default)]
+    pub checklist: ChecklistProgress,
+    /// The task is hidden from default views until this time, set by `Db::defer_task`.
+    #[serde(default, with = "ts_seconds_option")]
+    pub defer_time: Option<DateTime<Utc>>,
+    /// Whether `defer_time` was still to come when the task was read from the database.
+    #[serde(default)]
+    pub deferred: bool,
+    /// How long the task is expected to take, set by `Db::set_task_estimate`.
+    #[serde(default)]
+    pub estimate_minutes: Option<i64>,
+    /// The part of `estimate_minutes` still left to do.
+    #[serde(default)]
+    pub remaining_minutes: Option<i64>,
 }
 
 impl Task {
@@ -37,14 +62,44 @@ impl Task {
             create_time: generated.create_time.clone(),
             last_edit_time: generated.last_edit_time.clone(),
             due_time: editable.due_time.as_ref().map(|time| time.clone()),
+            due_all_day: editable.due_all_day,
             target_time: editable.target_time.as_ref().map(|time| time.clone()),
             done_time: generated.done_time.as_ref().map(|time| time.clone()),
             paused: false,
+            project: None,
+            archived: false,
+            checklist: ChecklistProgress::default(),
+            defer_time: None,
+            deferred: false,
+            estimate_minutes: None,
+            remaining_minutes: None,
         }
     }
     pub fn is_done(&self) -> bool {
         self.done_time.is_some()
     }
+    /// The minutes of work left on this task: none once it is done, otherwise its remaining effort,
+    /// or its whole estimate if no remaining effort is set. `None` if it has no estimate.
+    pub fn remaining_effort_minutes(&self) -> Option<i64> {
+        self.estimate_minutes.map(|estimate| if self.is_done() {
+            0
+        } else {
+            self.remaining_minutes.unwrap_or(estimate)
+        })
+    }
+    /// Whether this task is hidden at `time` because its defer time has not come yet.
+    pub fn is_deferred_at(&self, time: &DateTime<Utc>) -> bool {
+        matches!(self.defer_time, Some(defer_time) if defer_time > *time)
+    }
+    /// The calendar date this task is due on in `timezone`, or `None` if it has no due time.
+    /// An all-day due date is the same date in every timezone.
+    pub fn due_date<Tz: TimeZone>(&self, timezone: &Tz) -> Option<NaiveDate> {
+        self.due_time.as_ref().map(|time| if self.due_all_day {
+            all_day_date(time)
+        } else {
+            local_date(timezone, time)
+        })
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
@@ -57,11 +112,21 @@ pub struct EditableTaskData {
     pub difficulty: i32,
     #[serde(with = "ts_seconds_option")]
     pub due_time: Option<DateTime<Utc>>,
+    /// Whether `due_time` is an all-day date rather than a time; see `local_time::all_day_time`.
+    #[serde(default)]
+    pub due_all_day: bool,
     #[serde(with = "ts_seconds_option")]
     pub target_time: Option<DateTime<Utc>>,
     pub paused: bool,
 }
 
+impl EditableTaskData {
+    /// Whether an all-day due time, if any, is a date stored with `local_time::all_day_time`.
+    pub fn is_valid(&self) -> bool {
+        !self.due_all_day || matches!(self.due_time, Some(time) if time == all_day_time(all_day_date(&time)))
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
 /// Fields of a `Task` determined by the database when a new task is created.
@@ -1,17 +1,17 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use crate::{TagId, Task, TaskId};
+use crate::{ProjectId, TagId, Task, TaskId};
 use crate::filters::{*};
 
 /// Represents the possible filters from a user, for every field of a `Task` supported
 /// by this application.
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct TaskFilterOptions {
     pub id_filter: Option<ExactlyFilter<TaskId>>,
-    pub title_filter: Option<ContainsStringFilter>,
+    pub title_filter: Option<StringFilter>,
     pub tag_filter: Option<OptionalFilter<SetFilter<TagId>>>,
-    pub body_filter: Option<ContainsStringFilter>,
+    pub body_filter: Option<StringFilter>,
     pub difficulty_filter: Option<SetFilter<i32>>,
     pub create_time_filter: Option<OrderedRangeFilter<DateTime<Utc>>>,
     pub last_edit_time_filter: Option<OrderedRangeFilter<DateTime<Utc>>>,
@@ -19,10 +19,29 @@ pub struct TaskFilterOptions {
     pub target_time_filter: Option<OptionalFilter<OrderedRangeFilter<DateTime<Utc>>>>,
     pub done_time_filter: Option<OptionalFilter<OrderedRangeFilter<DateTime<Utc>>>>,
     pub paused_filter: Option<ExactlyFilter<bool>>,
+    pub project_filter: Option<OptionalFilter<SetFilter<ProjectId>>>,
+    /// Tasks of archived projects are hidden unless this is set.
+    #[serde(default)]
+    pub include_archived: bool,
+    /// Whether tasks are deferred, replacing `include_deferred` when set.
+    pub deferred_filter: Option<ExactlyFilter<bool>>,
+    /// Deferred tasks are hidden unless this or `deferred_filter` is set.
+    #[serde(default)]
+    pub include_deferred: bool,
 }
 
 impl ApplyFilter<Task> for TaskFilterOptions {
     fn passes(&self, task: &Task) -> bool {
+        self.passes_including_hidden(task) &&
+        (self.include_archived || !task.archived) &&
+        (self.deferred_filter.is_some() || self.include_deferred || !task.deferred)
+    }
+}
+
+impl TaskFilterOptions {
+    /// Like `passes`, but without hiding deferred tasks and tasks of archived projects by default,
+    /// for reports over every task the filters match rather than lists of tasks to show.
+    pub fn passes_including_hidden(&self, task: &Task) -> bool {
         none_or_filter(&self.id_filter, &task.id) &&
         none_or_filter(&self.title_filter, &task.title) &&
         none_or_filter(&self.tag_filter, &task.tag) &&
@@ -33,7 +52,9 @@ impl ApplyFilter<Task> for TaskFilterOptions {
         none_or_filter(&self.due_time_filter, &task.due_time) &&
         none_or_filter(&self.target_time_filter, &task.target_time) &&
         none_or_filter(&self.done_time_filter, &task.done_time) &&
-        none_or_filter(&self.paused_filter, &task.paused)
+        none_or_filter(&self.paused_filter, &task.paused) &&
+        none_or_filter(&self.project_filter, &task.project) &&
+        none_or_filter(&self.deferred_filter, &task.deferred)
     }
 }
 
@@ -0,0 +1,49 @@
+/// Splits the lines of a body with the 0-based indices in `lines` out of it.
+///
+/// Returns the rest of the body, and the titles of the tasks made from the selected lines:
+/// each line without its list marker or checkbox, leaving out blank lines.
+/// Returns `None` if one of `lines` is past the end of the body.
+pub fn split_body(body: &str, lines: &[usize]) -> Option<(String, Vec<String>)> {
+    let body_lines: Vec<&str> = body.lines().collect();
+    if lines.iter().any(|line| *line >= body_lines.len()) {
+        return None;
+    }
+    let mut rest = vec![];
+    let mut titles = vec![];
+    for (index, line) in body_lines.iter().enumerate() {
+        if !lines.contains(&index) {
+            rest.push(*line);
+        } else if !line.trim().is_empty() {
+            titles.push(line_title(line));
+        }
+    }
+    Some((rest.join("\n"), titles))
+}
+
+/// Combines the body of the task others are merged into with the titles and bodies of the others,
+/// each under a heading with its title.
+pub fn merge_bodies(body: &str, others: &[(&str, &str)]) -> String {
+    let mut parts = vec![];
+    if !body.trim().is_empty() {
+        parts.push(String::from(body.trim_end()));
+    }
+    for (title, body) in others {
+        parts.push(format!("## {title}"));
+        if !body.trim().is_empty() {
+            parts.push(String::from(body.trim_end()));
+        }
+    }
+    parts.join("\n\n")
+}
+
+fn line_title(line: &str) -> String {
+    let line = line.trim();
+    let line = ["- ", "* ", "+ "].iter()
+        .find_map(|marker| line.strip_prefix(marker))
+        .unwrap_or(line)
+        .trim_start();
+    let line = ["[ ]", "[x]", "[X]"].iter()
+        .find_map(|checkbox| line.strip_prefix(checkbox))
+        .unwrap_or(line);
+    String::from(line.trim())
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use crate::{EditableChecklistItemData, EditableTaskData, TagId};
+use crate::local_time::{all_day_time, checked_seconds};
+
+pub type TemplateId = i64;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A kind of task created repeatedly. The title, body and checklist can hold placeholders
+/// like `{{date}}`, filled in when a task is made from it; see `fill_placeholders`.
+pub struct Template {
+    pub id: TemplateId,
+    pub name: String,
+    pub title: String,
+    pub body: String,
+    pub tag: Option<TagId>,
+    pub difficulty: i32,
+    /// The due time of a new task is this many seconds after it is made.
+    pub due_offset_seconds: Option<i64>,
+    /// Whether the due time is the all-day date it falls on; see `local_time::all_day_time`.
+    pub due_all_day: bool,
+    /// The target time of a new task is this many seconds after it is made.
+    pub target_offset_seconds: Option<i64>,
+    /// The texts of the checklist items of a new task, in order.
+    pub checklist: Vec<String>,
+}
+
+impl Template {
+    /// The most seconds the due or target time of a new task can be after (or before, if negative)
+    /// the time it is made.
+    pub const MAX_OFFSET_SECONDS: i64 = 10 * 366 * 24 * 60 * 60;
+
+    pub fn from_parts(editable: &EditableTemplateData, generated: &GeneratedTemplateData) -> Self {
+        Self {
+            id: generated.id,
+            name: editable.name.clone(),
+            title: editable.title.clone(),
+            body: editable.body.clone(),
+            tag: editable.tag,
+            difficulty: editable.difficulty,
+            due_offset_seconds: editable.due_offset_seconds,
+            due_all_day: editable.due_all_day,
+            target_offset_seconds: editable.target_offset_seconds,
+            checklist: editable.checklist.clone(),
+        }
+    }
+
+    /// The task this template makes at `now`, with placeholders filled from `values`
+    /// (see `fill_placeholders`) and offsets counted from `now`,
+    /// or `None` if an offset moves a time out of the range of times.
+    pub fn instantiate<Tz: TimeZone>(&self, now: &DateTime<Tz>, values: &HashMap<String, String>) -> Option<TemplateInstance> {
+        let due_time = match self.due_offset_seconds {
+            Some(offset) => {
+                let due = now.clone().checked_add_signed(checked_seconds(offset)?)?;
+                Some(if self.due_all_day {
+                    all_day_time(due.naive_local().date())
+                } else {
+                    due.with_timezone(&Utc)
+                })
+            }
+            None => None,
+        };
+        let target_time = match self.target_offset_seconds {
+            Some(offset) => Some(now.clone().checked_add_signed(checked_seconds(offset)?)?.with_timezone(&Utc)),
+            None => None,
+        };
+        Some(TemplateInstance {
+            data: EditableTaskData {
+                title: fill_placeholders(&self.title, now, values),
+                tag: self.tag,
+                body: fill_placeholders(&self.body, now, values),
+                difficulty: self.difficulty,
+                due_time,
+                due_all_day: self.due_all_day && due_time.is_some(),
+                target_time,
+                paused: false,
+            },
+            checklist: self.checklist.iter()
+                .map(|text| EditableChecklistItemData { text: fill_placeholders(text, now, values), checked: false })
+                .collect(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `Template` modifiable by the client.
+pub struct EditableTemplateData {
+    pub name: String,
+    pub title: String,
+    pub body: String,
+    pub tag: Option<TagId>,
+    pub difficulty: i32,
+    pub due_offset_seconds: Option<i64>,
+    #[serde(default)]
+    pub due_all_day: bool,
+    pub target_offset_seconds: Option<i64>,
+    #[serde(default)]
+    pub checklist: Vec<String>,
+}
+
+impl EditableTemplateData {
+    /// Whether the offsets of the due and target times are within `Template::MAX_OFFSET_SECONDS`.
+    pub fn is_valid(&self) -> bool {
+        let range = -Template::MAX_OFFSET_SECONDS..=Template::MAX_OFFSET_SECONDS;
+        [self.due_offset_seconds, self.target_offset_seconds].iter()
+            .flatten()
+            .all(|offset| range.contains(offset))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `Template` determined by the database when a new template is created.
+pub struct GeneratedTemplateData {
+    pub id: TemplateId,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A task made from a template, not yet added to the database.
+pub struct TemplateInstance {
+    pub data: EditableTaskData,
+    pub checklist: Vec<EditableChecklistItemData>,
+}
+
+/// Replaces the placeholders `{{name}}` in `text`. `values` are used first, then the built-in
+/// `{{date}}` (`2023-08-20`), `{{time}}` (`17:30`) and `{{weekday}}` (`Sunday`) of `now`.
+/// Unknown placeholders are left as they are.
+pub fn fill_placeholders<Tz: TimeZone>(text: &str, now: &DateTime<Tz>, values: &HashMap<String, String>) -> String {
+    let local = now.naive_local();
+    let mut filled = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let length = match rest[start + 2..].find("}}") {
+            Some(length) => length,
+            None => break,
+        };
+        let name = rest[start + 2..start + 2 + length].trim();
+        let value = match values.get(name) {
+            Some(value) => Some(value.clone()),
+            None => match name {
+                "date" => Some(local.format("%Y-%m-%d").to_string()),
+                "time" => Some(local.format("%H:%M").to_string()),
+                "weekday" => Some(local.format("%A").to_string()),
+                _ => None,
+            },
+        };
+        let end = start + length + 4;
+        filled.push_str(&rest[..start]);
+        match value {
+            Some(value) => filled.push_str(&value),
+            None => filled.push_str(&rest[start..end]),
+        }
+        rest = &rest[end..];
+    }
+    filled.push_str(rest);
+    filled
+}
@@ -0,0 +1,72 @@
+use chrono::{DateTime, NaiveDate, Utc, serde::ts_seconds, serde::ts_seconds_option};
+use serde::{Deserialize, Serialize};
+use crate::TaskId;
+
+pub type TimeEntryId = i64;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A span of time spent working on a task, either timed or entered by hand.
+pub struct TimeEntry {
+    pub id: TimeEntryId,
+    pub task_id: TaskId,
+    #[serde(with = "ts_seconds")]
+    pub start_time: DateTime<Utc>,
+    /// `None` while this entry is the running timer.
+    #[serde(with = "ts_seconds_option")]
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+impl TimeEntry {
+    pub fn from_parts(editable: &EditableTimeEntryData, generated: &GeneratedTimeEntryData) -> Self {
+        Self {
+            id: generated.id,
+            task_id: editable.task_id,
+            start_time: editable.start_time,
+            end_time: editable.end_time,
+        }
+    }
+    pub fn is_running(&self) -> bool {
+        self.end_time.is_none()
+    }
+    /// The end of this entry, where a running timer ends at `now`.
+    pub fn end_time_or(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        self.end_time.unwrap_or(now).max(self.start_time)
+    }
+    /// The seconds of this entry that fall between `from` and `to`.
+    pub fn seconds_between(&self, from: DateTime<Utc>, to: DateTime<Utc>, now: DateTime<Utc>) -> i64 {
+        let start = self.start_time.max(from);
+        let end = self.end_time_or(now).min(to);
+        (end - start).num_seconds().max(0)
+    }
+    /// The length of this entry in seconds, where a running timer ends at `now`.
+    pub fn seconds(&self, now: DateTime<Utc>) -> i64 {
+        (self.end_time_or(now) - self.start_time).num_seconds()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `TimeEntry` modifiable by the client.
+pub struct EditableTimeEntryData {
+    pub task_id: TaskId,
+    #[serde(with = "ts_seconds")]
+    pub start_time: DateTime<Utc>,
+    #[serde(with = "ts_seconds_option")]
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Fields of a `TimeEntry` determined by the database when a new entry is created.
+pub struct GeneratedTimeEntryData {
+    pub id: TimeEntryId,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// The total time spent on tasks during a day in the timezone of the user.
+pub struct DayTimeSpent {
+    pub date: NaiveDate,
+    pub seconds: i64,
+}
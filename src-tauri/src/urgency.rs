@@ -0,0 +1,74 @@
+use std::cmp::Ordering;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::Task;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// Weights of each part of the urgency score of a task; see `UrgencyCoefficients::score`.
+pub struct UrgencyCoefficients {
+    pub due: f64,
+    pub target: f64,
+    pub difficulty: f64,
+    pub age: f64,
+    pub paused: f64,
+    pub blocked: f64,
+    pub tag: f64,
+}
+
+impl Default for UrgencyCoefficients {
+    fn default() -> Self {
+        Self {
+            due: 12.0,
+            target: 6.0,
+            difficulty: 0.5,
+            age: 2.0,
+            paused: -5.0,
+            blocked: -5.0,
+            tag: 1.0,
+        }
+    }
+}
+
+impl UrgencyCoefficients {
+    /// The urgency score of `task` at `now`, higher being more urgent. It is the sum of:
+    /// * `due * proximity(due_time)` and `target * proximity(target_time)`, see `deadline_proximity`.
+    /// * `difficulty * task.difficulty`.
+    /// * `age * min(days since created / 365, 1)`.
+    /// * `paused` if the task is paused, and `blocked` if it is `blocked`: the database counts a task
+    ///   as blocked while it links to another task that is not done and not a habit (see `Db::blocked_tasks`).
+    /// * `tag * tag_weight`, the weight of the tag of the task.
+    pub fn score(&self, task: &Task, now: DateTime<Utc>, tag_weight: f64, blocked: bool) -> f64 {
+        let age_days = (now - task.create_time).num_seconds() as f64 / SECONDS_PER_DAY;
+        self.due * task.due_time.map_or(0.0, |time| deadline_proximity(time, now))
+            + self.target * task.target_time.map_or(0.0, |time| deadline_proximity(time, now))
+            + self.difficulty * task.difficulty as f64
+            + self.age * (age_days / 365.0).clamp(0.0, 1.0)
+            + if task.paused { self.paused } else { 0.0 }
+            + if blocked { self.blocked } else { 0.0 }
+            + self.tag * tag_weight
+    }
+}
+
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+/// How close `deadline` is at `now`, from 0.2 when 14 or more days away
+/// rising linearly to 1 when 7 or more days overdue.
+pub fn deadline_proximity(deadline: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+    let days_left = (deadline - now).num_seconds() as f64 / SECONDS_PER_DAY;
+    (0.2 + 0.8 * (14.0 - days_left) / 21.0).clamp(0.2, 1.0)
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+/// A task along with its urgency score, serialized as the task with an extra `urgency` field.
+pub struct UrgentTask {
+    #[serde(flatten)]
+    pub task: Task,
+    pub urgency: f64,
+}
+
+/// Sorts tasks from most to least urgent, keeping the order of equally urgent tasks.
+pub fn sort_by_urgency(tasks: &mut [UrgentTask]) {
+    tasks.sort_by(|a, b| b.urgency.partial_cmp(&a.urgency).unwrap_or(Ordering::Equal));
+}
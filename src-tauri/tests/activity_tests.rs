@@ -0,0 +1,68 @@
+use chrono::Duration;
+use rusqlite::Connection;
+use app::*;
+use DbError::{CommentDoesNotExistError, TaskDoesNotExistError};
+mod util;
+use util::*;
+
+fn comment(text: &str) -> EditableCommentData {
+    EditableCommentData { author: String::from("sam"), text: String::from(text) }
+}
+
+#[test]
+fn db_comments() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let generated = db.add_comment(ids[0], &comment("Are these *ripe*?")).expect("Add comment should not fail");
+        let mut expected = Comment::from_parts(ids[0], &comment("Are these *ripe*?"), &generated);
+        assert_eq!(db.comment_by_id(generated.id), Ok(Some(expected.clone())));
+
+        let modified = db.modify_comment(generated.id, &comment("Are these **ripe**?")).expect("Modify comment should not fail");
+        expected.text = String::from("Are these **ripe**?");
+        expected.edit_time = Some(modified.edit_time);
+        let second = db.add_comment(ids[0], &comment("Yes")).unwrap().id;
+        assert_eq!(db.comments_for_task(ids[0]).unwrap()[0], expected);
+        assert_eq!(db.comments_for_task(ids[0]).unwrap().len(), 2);
+        assert_eq!(db.comments_for_task(ids[1]), Ok(vec![]));
+
+        db.delete_comment(second).expect("Delete comment should not fail");
+        assert_eq!(db.delete_comment(second), Err(CommentDoesNotExistError { id: second }));
+        assert_eq!(db.modify_comment(second, &comment("No")), Err(CommentDoesNotExistError { id: second }));
+        assert_eq!(db.add_comment(0, &comment("Hello")), Err(TaskDoesNotExistError { id: 0 }));
+
+        db.delete_task(ids[0]).unwrap();
+        assert_eq!(db.comment_by_id(generated.id), Ok(None));
+    });
+}
+
+#[test]
+fn db_task_activity() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let comment_id = db.add_comment(ids[1], &comment("Which kind?")).unwrap().id;
+        let later = db.comment_by_id(comment_id).unwrap().unwrap().create_time + Duration::hours(1);
+        let conn = Connection::open(TEST_PATH).unwrap();
+        conn.execute("UPDATE comments SET create_time = ?2 WHERE id = ?1", (comment_id, later.timestamp())).unwrap();
+
+        let tagged = EditableTaskData { tag: Some(1), ..sample_task_data()[1].clone() };
+        db.modify_task(ids[1], &tagged).unwrap();
+        // same tag, so only an edit
+        db.modify_task(ids[1], &tagged).unwrap();
+        db.finish_task(ids[1]).unwrap();
+        db.unfinish_task(ids[1]).unwrap();
+
+        let activity = db.task_activity(ids[1]).expect("Task activity should not fail");
+        let kinds: Vec<ActivityKind> = activity.iter().map(|activity| activity.kind.clone()).collect();
+        assert_eq!(kinds, vec![
+            ActivityKind::Event { event: TaskEvent::Created },
+            ActivityKind::Event { event: TaskEvent::Edited },
+            ActivityKind::Event { event: TaskEvent::TagChanged { from: None, to: Some(1) } },
+            ActivityKind::Event { event: TaskEvent::Edited },
+            ActivityKind::Event { event: TaskEvent::Finished },
+            ActivityKind::Event { event: TaskEvent::Unfinished },
+            ActivityKind::Comment { comment: db.comment_by_id(comment_id).unwrap().unwrap() },
+        ]);
+        assert!(activity.windows(2).all(|pair| pair[0].time <= pair[1].time));
+        assert_eq!(db.task_activity(0), Err(TaskDoesNotExistError { id: 0 }));
+    });
+}
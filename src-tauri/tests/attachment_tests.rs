@@ -0,0 +1,151 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use app::*;
+use DbError::{AttachmentDoesNotExistError, AttachmentFileError, BackupDirectoryError, TaskDoesNotExistError};
+mod util;
+use util::*;
+
+const ATTACHMENT_DIRECTORY: &str = "test-outputs/attachments";
+const BACKUP_DIRECTORY: &str = "test-outputs/backup";
+
+/// Writes a file to attach under `test-outputs`, starting without stored attachments from earlier tests.
+fn source_file(name: &str, contents: &str) -> PathBuf {
+    _ = fs::remove_dir_all(ATTACHMENT_DIRECTORY);
+    let path = Path::new("test-outputs").join(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn stored_files() -> usize {
+    fs::read_dir(ATTACHMENT_DIRECTORY).map(|entries| entries.count()).unwrap_or(0)
+}
+
+#[test]
+fn db_attachments() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let source = source_file("receipt.PDF", "apples, 2 lbs");
+        let attachment = db.add_attachment(ids[0], &source).expect("Add attachment should not fail");
+        assert_eq!(attachment.task_id, ids[0]);
+        assert_eq!(attachment.name, "receipt.PDF");
+        assert_eq!(attachment.size, 13);
+        assert_eq!(attachment.mime, "application/pdf");
+        assert_eq!(attachment.hash, content_hash(b"apples, 2 lbs"));
+        assert_eq!(db.attachment_by_id(attachment.id), Ok(Some(attachment.clone())));
+        assert_eq!(db.attachments_for_task(ids[0]), Ok(vec![attachment.clone()]));
+        assert_eq!(db.attachments_for_task(ids[1]), Ok(vec![]));
+
+        let path = db.attachment_path(attachment.id).unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), "apples, 2 lbs");
+
+        assert_eq!(db.add_attachment(0, &source), Err(TaskDoesNotExistError { id: 0 }));
+        assert!(matches!(db.add_attachment(ids[0], Path::new("test-outputs/missing.txt")), Err(AttachmentFileError { .. })));
+        assert_eq!(db.attachment_path(0), Err(AttachmentDoesNotExistError { id: 0 }));
+        assert_eq!(db.delete_attachment(0), Err(AttachmentDoesNotExistError { id: 0 }));
+    });
+}
+
+#[test]
+fn attachments_are_deduplicated() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let source = source_file("notes.txt", "remember the milk");
+        let first = db.add_attachment(ids[0], &source).unwrap();
+        let second = db.add_attachment(ids[1], &source).unwrap();
+        assert_ne!(first.id, second.id);
+        assert_eq!(first.hash, second.hash);
+        assert_eq!(stored_files(), 1);
+
+        // the contents stay while another attachment has them
+        db.delete_attachment(first.id).expect("Delete attachment should not fail");
+        assert_eq!(stored_files(), 1);
+        assert_eq!(db.attachment_by_id(first.id), Ok(None));
+        db.delete_attachment(second.id).unwrap();
+        assert_eq!(stored_files(), 0);
+    });
+}
+
+#[test]
+fn deleting_task_removes_attachments() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let source = source_file("photo.png", "not really a photo");
+        let kept = db.add_attachment(ids[1], &source).unwrap();
+        db.add_attachment(ids[0], &source).unwrap();
+        fs::write(&source, "another photo").unwrap();
+        db.add_attachment(ids[0], &source).unwrap();
+        assert_eq!(stored_files(), 2);
+
+        // only files are collected
+        fs::create_dir(Path::new(ATTACHMENT_DIRECTORY).join("folder")).unwrap();
+        db.delete_task(ids[0]).unwrap();
+        assert_eq!(db.attachments_for_task(ids[0]), Ok(vec![]));
+        assert_eq!(stored_files(), 2);
+        assert!(Path::new(ATTACHMENT_DIRECTORY).join("folder").is_dir());
+        assert_eq!(fs::read_to_string(db.attachment_path(kept.id).unwrap()).unwrap(), "not really a photo");
+    });
+}
+
+#[test]
+fn collect_attachment_garbage_reports_failure() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let source = source_file("song.mp3", "la la la");
+        let attachment = db.add_attachment(ids[0], &source).unwrap();
+        fs::write(Path::new(ATTACHMENT_DIRECTORY).join("leftover"), "from a failed collection").unwrap();
+        db.collect_attachment_garbage().expect("Collect attachment garbage should not fail");
+        assert_eq!(stored_files(), 1);
+
+        // a deletion is kept when its files can't be collected, and collecting again reports why
+        fs::remove_dir_all(ATTACHMENT_DIRECTORY).unwrap();
+        fs::write(ATTACHMENT_DIRECTORY, "not a directory").unwrap();
+        db.delete_attachment(attachment.id).expect("Delete attachment should not fail");
+        assert_eq!(db.attachment_by_id(attachment.id), Ok(None));
+        assert!(matches!(db.collect_attachment_garbage(), Err(AttachmentFileError { .. })));
+        fs::remove_file(ATTACHMENT_DIRECTORY).unwrap();
+        assert_eq!(db.collect_attachment_garbage(), Ok(()));
+    });
+}
+
+#[test]
+fn backup_includes_attachments() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let source = source_file("list.md", "- eggs");
+        let attachment = db.add_attachment(ids[0], &source).unwrap();
+        _ = fs::remove_dir_all(BACKUP_DIRECTORY);
+        db.backup(Path::new(BACKUP_DIRECTORY)).expect("Backup should not fail");
+
+        let copy = Db::connect(Path::new(BACKUP_DIRECTORY).join("test-db.sqlite")).unwrap();
+        assert_eq!(copy.all_tasks(), db.all_tasks());
+        assert_eq!(copy.attachments_for_task(ids[0]), Ok(vec![attachment.clone()]));
+        assert_eq!(fs::read_to_string(copy.attachment_path(attachment.id).unwrap()).unwrap(), "- eggs");
+        drop(copy);
+
+        // an earlier backup is replaced
+        db.delete_task(ids[1]).unwrap();
+        db.backup(Path::new(BACKUP_DIRECTORY)).expect("Backup over a backup should not fail");
+        let copy = Db::connect(Path::new(BACKUP_DIRECTORY).join("test-db.sqlite")).unwrap();
+        assert_eq!(copy.all_tasks(), db.all_tasks());
+        drop(copy);
+        _ = fs::remove_dir_all(BACKUP_DIRECTORY);
+    });
+}
+
+#[test]
+fn backup_refuses_own_directories() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let source = source_file("list.md", "- eggs");
+        let attachment = db.add_attachment(ids[0], &source).unwrap();
+        assert_eq!(db.backup(Path::new("test-outputs")), Err(BackupDirectoryError));
+        assert_eq!(db.backup(Path::new("test-outputs/../test-outputs")), Err(BackupDirectoryError));
+        assert_eq!(db.backup(Path::new(ATTACHMENT_DIRECTORY)), Err(BackupDirectoryError));
+        assert_eq!(db.backup(&Path::new(ATTACHMENT_DIRECTORY).join("inner")), Err(BackupDirectoryError));
+        _ = fs::remove_dir_all(Path::new(ATTACHMENT_DIRECTORY).join("inner"));
+
+        // the originals are untouched and still writable
+        assert_eq!(fs::read_to_string(db.attachment_path(attachment.id).unwrap()).unwrap(), "- eggs");
+        db.finish_task(ids[1]).expect("Database should still be writable");
+    });
+}
@@ -0,0 +1,143 @@
+use rusqlite::Connection;
+use app::*;
+use DbError::{BoardDoesNotExistError, ColumnDoesNotExistError, TaskDoesNotExistError, WipLimitError};
+mod util;
+use util::*;
+
+fn column(name: &str, wip_limit: Option<u32>, terminal: bool) -> EditableColumnData {
+    EditableColumnData { name: String::from(name), wip_limit, terminal }
+}
+
+/// Adds a board with Backlog, In Progress (WIP limit 1) and Done columns, returning their ids.
+fn add_sample_board(db: &mut Db) -> (BoardId, Vec<ColumnId>) {
+    let board = db.add_new_board(&EditableBoardData { name: String::from("Fruit") }).unwrap().id;
+    let columns = [
+        column("Backlog", None, false),
+        column("In Progress", Some(1), false),
+        column("Done", None, true),
+    ].iter().map(|data| db.add_column(board, data).unwrap().id).collect();
+    (board, columns)
+}
+
+fn cards(db: &Db, board: BoardId) -> Vec<Vec<TaskId>> {
+    db.board_view(board).unwrap().columns.iter()
+        .map(|column| column.tasks.iter().map(|task| task.id).collect())
+        .collect()
+}
+
+#[test]
+fn db_boards_and_columns() {
+    run_db_test(|mut db| {
+        let (board, columns) = add_sample_board(&mut db);
+        assert_eq!(db.all_boards(), Ok(vec![Board { id: board, name: String::from("Fruit") }]));
+        db.modify_board(board, &EditableBoardData { name: String::from("Berries") }).expect("Modify board should not fail");
+        assert_eq!(db.board_by_id(board).unwrap().unwrap().name, "Berries");
+        assert_eq!(db.add_column(0, &column("Review", None, false)), Err(BoardDoesNotExistError { id: 0 }));
+
+        db.move_column(columns[2], 0).expect("Move column should not fail");
+        let names = |db: &Db| db.board_view(board).unwrap().columns.iter()
+            .map(|column| column.column.name.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(names(&db), vec!["Done", "Backlog", "In Progress"]);
+        db.modify_column(columns[1], &column("Doing", Some(2), false)).expect("Modify column should not fail");
+        db.delete_column(columns[0]).expect("Delete column should not fail");
+        assert_eq!(names(&db), vec!["Done", "Doing"]);
+        assert_eq!(db.column_by_id(columns[1]).unwrap().unwrap().wip_limit, Some(2));
+        assert_eq!(db.delete_column(columns[0]), Err(ColumnDoesNotExistError { id: columns[0] }));
+
+        db.delete_board(board).expect("Delete board should not fail");
+        assert_eq!(db.column_by_id(columns[1]), Ok(None));
+        assert_eq!(db.board_view(board), Err(BoardDoesNotExistError { id: board }));
+    });
+}
+
+#[test]
+fn db_move_cards() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let (board, columns) = add_sample_board(&mut db);
+        db.move_card(ids[0], columns[0], None).expect("Move card should not fail");
+        db.move_card(ids[1], columns[0], Some(0)).unwrap();
+        assert_eq!(cards(&db, board), vec![vec![ids[1], ids[0]], vec![], vec![]]);
+        assert_eq!(db.task_column(board, ids[0]).unwrap().unwrap().id, columns[0]);
+
+        db.move_card(ids[0], columns[1], None).unwrap();
+        assert_eq!(db.move_card(ids[1], columns[1], None), Err(WipLimitError { id: columns[1], limit: 1 }));
+        // reordering within a full column is fine
+        db.move_card(ids[0], columns[1], Some(0)).unwrap();
+        assert_eq!(cards(&db, board), vec![vec![ids[1]], vec![ids[0]], vec![]]);
+
+        db.remove_card(board, ids[1]).expect("Remove card should not fail");
+        assert_eq!(db.task_column(board, ids[1]), Ok(None));
+        db.delete_task(ids[0]).unwrap();
+        assert_eq!(cards(&db, board), vec![vec![], vec![], vec![]]);
+        assert_eq!(db.move_card(ids[0], columns[0], None), Err(TaskDoesNotExistError { id: ids[0] }));
+        assert_eq!(db.move_card(ids[1], 0, None), Err(ColumnDoesNotExistError { id: 0 }));
+    });
+}
+
+#[test]
+fn db_terminal_column_finishes_task() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let (board, columns) = add_sample_board(&mut db);
+        db.move_card(ids[0], columns[0], None).unwrap();
+        let finished = db.move_card(ids[0], columns[2], None).unwrap();
+        assert!(finished.done_time.is_some());
+        assert_eq!(db.task_by_id(ids[0]).unwrap().unwrap().done_time, finished.done_time);
+        // staying in a terminal column keeps the done time
+        assert_eq!(db.move_card(ids[0], columns[2], Some(0)), Ok(finished));
+
+        assert_eq!(db.move_card(ids[0], columns[1], None), Ok(FinishedTaskData { done_time: None }));
+        assert!(!db.task_by_id(ids[0]).unwrap().unwrap().is_done());
+        assert_eq!(cards(&db, board), vec![vec![], vec![ids[0]], vec![]]);
+    });
+}
+
+#[test]
+fn db_cards_on_several_boards() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let (first, first_columns) = add_sample_board(&mut db);
+        let (second, second_columns) = add_sample_board(&mut db);
+        db.move_card(ids[0], first_columns[1], None).unwrap();
+        db.move_card(ids[0], second_columns[0], None).unwrap();
+        assert_eq!(cards(&db, first), vec![vec![], vec![ids[0]], vec![]]);
+        assert_eq!(cards(&db, second), vec![vec![ids[0]], vec![], vec![]]);
+        assert_eq!(db.task_columns(ids[0]).unwrap().iter().map(|column| column.id).collect::<Vec<_>>(),
+                   vec![first_columns[1], second_columns[0]]);
+
+        db.move_card(ids[0], second_columns[1], None).unwrap();
+        assert_eq!(cards(&db, first), vec![vec![], vec![ids[0]], vec![]]);
+        db.remove_card(second, ids[0]).unwrap();
+        assert_eq!(db.task_column(first, ids[0]).unwrap().unwrap().id, first_columns[1]);
+        assert_eq!(db.task_column(second, ids[0]), Ok(None));
+
+        db.delete_board(first).unwrap();
+        assert_eq!(db.task_columns(ids[0]), Ok(vec![]));
+    });
+}
+
+#[test]
+fn db_failed_move_keeps_done_status() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let (board, columns) = add_sample_board(&mut db);
+        db.move_card(ids[0], columns[0], None).unwrap();
+        let conn = Connection::open(TEST_PATH).unwrap();
+        conn.execute_batch(r#"
+            CREATE TRIGGER fail_cards BEFORE UPDATE ON board_cards BEGIN SELECT RAISE(ABORT, 'no moving'); END;
+        "#).unwrap();
+        assert!(db.move_card(ids[0], columns[2], None).is_err());
+        assert!(!db.task_by_id(ids[0]).unwrap().unwrap().is_done());
+        assert_eq!(db.task_activity(ids[0]).unwrap().len(), 1);
+        assert_eq!(cards(&db, board), vec![vec![ids[0]], vec![], vec![]]);
+
+        // the connection is not left inside the savepoint
+        conn.execute_batch("DROP TRIGGER fail_cards;").unwrap();
+        db.move_card(ids[0], columns[2], None).unwrap();
+        drop(db);
+        let db = Db::connect(TEST_PATH).unwrap();
+        assert!(db.task_by_id(ids[0]).unwrap().unwrap().is_done());
+    });
+}
@@ -0,0 +1,120 @@
+use std::collections::HashSet;
+use chrono::Duration;
+use app::*;
+use DbError::{AllDayShiftError, DurationOutOfRangeError, TagDoesNotExistError, TaskDoesNotExistError, TaskStatusError};
+mod util;
+use util::*;
+
+/// Adds the sample tags and every sample task twice, returning the ids of the tasks.
+fn add_bulk_tasks(db: &mut Db) -> Vec<TaskId> {
+    let mut ids = add_sample_tasks(db);
+    ids.extend(sample_task_data().iter().map(|data| db.add_new_task(data).unwrap().id));
+    ids
+}
+
+fn item(id: TaskId, result: DbResult<()>) -> BulkItemResult {
+    BulkItemResult { id, result }
+}
+
+#[test]
+fn db_bulk_update_ids() {
+    run_db_test(|mut db| {
+        let ids = add_bulk_tasks(&mut db);
+        db.finish_task(ids[1]).unwrap();
+
+        let target = BulkTarget::Ids { ids: vec![ids[0], ids[1], 0, ids[0]] };
+        let outcome = db.bulk_update(&target, &BulkAction::Finish, false).expect("Bulk update should not fail");
+        assert_eq!(outcome.results, vec![
+            item(ids[0], Ok(())),
+            item(ids[1], Err(TaskStatusError { id: ids[1], actual_status: true })),
+            item(0, Err(TaskDoesNotExistError { id: 0 })),
+        ]);
+        assert!(!outcome.rolled_back);
+        assert!(db.task_by_id(ids[0]).unwrap().unwrap().done_time.is_some());
+
+        let target = BulkTarget::Ids { ids: vec![ids[2], ids[3]] };
+        let outcome = db.bulk_update(&target, &BulkAction::Retag { tag: Some(2) }, false).unwrap();
+        assert!(!outcome.has_failures());
+        assert_eq!(db.task_by_id(ids[3]).unwrap().unwrap().tag, Some(2));
+        let outcome = db.bulk_update(&target, &BulkAction::Retag { tag: Some(100) }, false).unwrap();
+        assert_eq!(outcome.results[0].result, Err(TagDoesNotExistError { id: 100 }));
+    });
+}
+
+#[test]
+fn db_bulk_update_rollback() {
+    run_db_test(|mut db| {
+        let ids = add_bulk_tasks(&mut db);
+        let target = BulkTarget::Ids { ids: vec![ids[0], 0, ids[2]] };
+
+        let outcome = db.bulk_update(&target, &BulkAction::Delete, true).unwrap();
+        assert!(outcome.rolled_back);
+        assert_eq!(outcome.results[0].result, Ok(()));
+        assert_eq!(db.all_tasks().unwrap().len(), 4);
+
+        let outcome = db.bulk_update(&target, &BulkAction::Delete, false).unwrap();
+        assert!(!outcome.rolled_back);
+        assert_eq!(db.all_tasks().unwrap().iter().map(|task| task.id).collect::<Vec<_>>(), [ids[1], ids[3]]);
+
+        // without failures, nothing is rolled back
+        let target = BulkTarget::Ids { ids: vec![ids[1], ids[3]] };
+        let outcome = db.bulk_update(&target, &BulkAction::Pause, true).unwrap();
+        assert!(!outcome.rolled_back);
+        assert!(db.all_tasks().unwrap().iter().all(|task| task.paused));
+    });
+}
+
+#[test]
+fn db_bulk_update_filter() {
+    run_db_test(|mut db| {
+        let ids = add_bulk_tasks(&mut db);
+        let filter = TaskFilterOptions {
+            difficulty_filter: Some(SetFilter { set: HashSet::from([3]) }),
+            ..Default::default()
+        };
+        let target = BulkTarget::Filter { filter: Box::new(filter) };
+
+        let outcome = db.bulk_update(&target, &BulkAction::ShiftDue { seconds: -Duration::days(1).num_seconds() }, false).unwrap();
+        assert_eq!(outcome.results, vec![item(ids[0], Ok(())), item(ids[2], Ok(()))]);
+        let due_time = sample_task_data()[0].due_time.map(|time| time - Duration::days(1));
+        assert_eq!(db.task_by_id(ids[2]).unwrap().unwrap().due_time, due_time);
+
+        db.bulk_update(&target, &BulkAction::SetDifficulty { difficulty: 1 }, false).unwrap();
+        let difficulties: Vec<i32> = db.all_tasks().unwrap().iter().map(|task| task.difficulty).collect();
+        assert_eq!(difficulties, [1, 0, 1, 0]);
+        // the filter now matches nothing
+        assert_eq!(db.bulk_update(&target, &BulkAction::Delete, true).unwrap().results, vec![]);
+
+        // tasks without a due time are left as they are
+        let target = BulkTarget::Ids { ids: vec![ids[1]] };
+        let before = db.task_by_id(ids[1]).unwrap().unwrap();
+        db.bulk_update(&target, &BulkAction::ShiftDue { seconds: 60 }, false).unwrap();
+        assert_eq!(db.task_by_id(ids[1]).unwrap().unwrap(), before);
+    });
+}
+
+#[test]
+fn db_bulk_shift_due_failures() {
+    run_db_test(|mut db| {
+        let ids = add_bulk_tasks(&mut db);
+        let all_day = EditableTaskData { due_all_day: true, ..sample_task_data()[0].clone() };
+        db.modify_task(ids[2], &all_day).unwrap();
+        let target = BulkTarget::Ids { ids: vec![ids[0], ids[2]] };
+
+        for seconds in [i64::MAX, i64::MIN, Duration::max_value().num_seconds()] {
+            let outcome = db.bulk_update(&target, &BulkAction::ShiftDue { seconds }, true).expect("Bulk update should not fail");
+            assert_eq!(outcome.results[0].result, Err(DurationOutOfRangeError));
+            assert!(outcome.rolled_back);
+        }
+        let outcome = db.bulk_update(&target, &BulkAction::ShiftDue { seconds: 3600 }, false).unwrap();
+        assert_eq!(outcome.results, vec![item(ids[0], Ok(())), item(ids[2], Err(AllDayShiftError { id: ids[2] }))]);
+        assert_eq!(db.task_by_id(ids[2]).unwrap().unwrap().due_time, all_day.due_time);
+
+        // whole days keep an all-day date, and the connection is not left inside the savepoint
+        let outcome = db.bulk_update(&target, &BulkAction::ShiftDue { seconds: Duration::days(2).num_seconds() }, true).unwrap();
+        assert!(!outcome.has_failures());
+        drop(db);
+        let db = Db::connect(TEST_PATH).unwrap();
+        assert_eq!(db.task_by_id(ids[2]).unwrap().unwrap().due_time, all_day.due_time.map(|time| time + Duration::days(2)));
+    });
+}
@@ -0,0 +1,92 @@
+use app::*;
+use DbError::{ChecklistIncompleteError, ChecklistItemDoesNotExistError, TaskDoesNotExistError};
+mod util;
+use util::*;
+
+fn item(text: &str, checked: bool) -> EditableChecklistItemData {
+    EditableChecklistItemData { text: String::from(text), checked }
+}
+
+fn texts(items: &[ChecklistItem]) -> Vec<&str> {
+    items.iter().map(|item| item.text.as_str()).collect()
+}
+
+#[test]
+fn extract_checklist_lines() {
+    let body = "Shopping\n- [ ] Milk\n  * [x] Bread\n- [X] Eggs\n- [ ]\n- Butter\n[ ] Jam";
+    let (rest, items) = extract_checklist(body);
+    assert_eq!(rest, "Shopping\n- [ ]\n- Butter\n[ ] Jam");
+    assert_eq!(items, vec![item("Milk", false), item("Bread", true), item("Eggs", true)]);
+    assert_eq!(extract_checklist("No list"), (String::from("No list"), vec![]));
+}
+
+#[test]
+fn db_checklist_items() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let milk = db.add_checklist_item(ids[0], &item("Milk", false)).expect("Add checklist item should not fail").id;
+        let bread = db.add_checklist_item(ids[0], &item("Bread", true)).unwrap().id;
+        let eggs = db.add_checklist_item(ids[0], &item("Eggs", false)).unwrap().id;
+        assert_eq!(db.checklist_item_by_id(milk), Ok(Some(ChecklistItem::from_parts(ids[0], &item("Milk", false),
+                                                                                  &GeneratedChecklistItemData { id: milk }))));
+        assert_eq!(db.task_by_id(ids[0]).unwrap().unwrap().checklist, ChecklistProgress { checked: 1, total: 3 });
+
+        db.move_checklist_item(eggs, 0).expect("Move checklist item should not fail");
+        assert_eq!(texts(&db.checklist_items(ids[0]).unwrap()), vec!["Eggs", "Milk", "Bread"]);
+        db.modify_checklist_item(milk, &item("Oat milk", true)).expect("Modify checklist item should not fail");
+        db.delete_checklist_item(eggs).expect("Delete checklist item should not fail");
+        assert_eq!(texts(&db.checklist_items(ids[0]).unwrap()), vec!["Oat milk", "Bread"]);
+        assert_eq!(db.checklist_progress(ids[0]), Ok(ChecklistProgress { checked: 2, total: 2 }));
+
+        assert_eq!(db.add_checklist_item(0, &item("Milk", false)), Err(TaskDoesNotExistError { id: 0 }));
+        assert_eq!(db.modify_checklist_item(eggs, &item("Eggs", true)), Err(ChecklistItemDoesNotExistError { id: eggs }));
+        assert_eq!(db.delete_checklist_item(eggs), Err(ChecklistItemDoesNotExistError { id: eggs }));
+        db.delete_task(ids[0]).unwrap();
+        assert_eq!(db.checklist_item_by_id(bread), Ok(None));
+    });
+}
+
+#[test]
+fn db_finish_task_with_checklist() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let milk = db.add_checklist_item(ids[0], &item("Milk", false)).unwrap().id;
+        db.add_checklist_item(ids[0], &item("Bread", false)).unwrap();
+        assert_eq!(db.require_complete_checklist(), Ok(false));
+        db.finish_task(ids[0]).expect("Finish task should not fail without required checklists");
+        db.unfinish_task(ids[0]).unwrap();
+
+        db.set_require_complete_checklist(true).unwrap();
+        assert_eq!(db.finish_task(ids[0]), Err(ChecklistIncompleteError { id: ids[0], unchecked: 2 }));
+        db.modify_checklist_item(milk, &item("Milk", true)).unwrap();
+        assert_eq!(db.finish_task(ids[0]), Err(ChecklistIncompleteError { id: ids[0], unchecked: 1 }));
+        // tasks without a checklist are unaffected
+        db.finish_task(ids[1]).expect("Finish task without checklist should not fail");
+    });
+}
+
+#[test]
+fn db_convert_body_checklist() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        db.add_checklist_item(ids[1], &item("Wash", false)).unwrap();
+        let data = EditableTaskData { body: String::from("Steps:\n- [ ] Peel\n- [x] Slice [[Knife]]"), ..sample_task_data()[1].clone() };
+        db.modify_task(ids[1], &data).unwrap();
+        assert_eq!(db.task_links(ids[1]).unwrap().len(), 1);
+        let events = db.task_activity(ids[1]).unwrap().len();
+        let added = db.convert_body_checklist(ids[1]).expect("Convert checklist should not fail");
+        assert_eq!(texts(&added), vec!["Peel", "Slice [[Knife]]"]);
+        assert_eq!(texts(&db.checklist_items(ids[1]).unwrap()), vec!["Wash", "Peel", "Slice [[Knife]]"]);
+        let task = db.task_by_id(ids[1]).unwrap().unwrap();
+        assert_eq!(task.body, "Steps:");
+        assert_eq!(task.checklist, ChecklistProgress { checked: 1, total: 3 });
+        // the links of the converted lines are gone and the conversion is an edit
+        assert_eq!(db.task_links(ids[1]), Ok(vec![]));
+        let activity = db.task_activity(ids[1]).unwrap();
+        assert_eq!(activity.len(), events + 1);
+        assert_eq!(activity.last().unwrap().kind, ActivityKind::Event { event: TaskEvent::Edited });
+
+        assert_eq!(db.convert_body_checklist(ids[1]), Ok(vec![]));
+        assert_eq!(db.convert_body_checklist(0), Err(TaskDoesNotExistError { id: 0 }));
+    });
+}
@@ -0,0 +1,82 @@
+use chrono::{Duration, TimeZone, Utc};
+use app::*;
+use DbError::{DurationOutOfRangeError, TaskDoesNotExistError};
+mod util;
+use util::*;
+
+fn ids(tasks: Vec<Task>) -> Vec<TaskId> {
+    tasks.into_iter().map(|task| task.id).collect()
+}
+
+#[test]
+fn task_is_deferred_at() {
+    let mut task = Task::from_parts(&sample_task_data()[1], &GeneratedTaskData {
+        id: 1,
+        create_time: Utc::now(),
+        last_edit_time: Utc::now(),
+        done_time: None,
+    });
+    let time = Utc.with_ymd_and_hms(2023, 9, 1, 12, 0, 0).unwrap();
+    assert!(!task.is_deferred_at(&time));
+    task.defer_time = Some(time);
+    assert!(task.is_deferred_at(&(time - Duration::seconds(1))));
+    assert!(!task.is_deferred_at(&time));
+}
+
+#[test]
+fn db_defer_task() {
+    run_db_test(|mut db| {
+        add_sample_tags(&mut db);
+        let deferred = db.add_new_task(&sample_task_data()[0]).unwrap().id;
+        let available = db.add_new_task(&sample_task_data()[1]).unwrap().id;
+        let until = Utc.timestamp_opt(Utc::now().timestamp(), 0).unwrap() + Duration::days(1);
+
+        db.defer_task(deferred, Some(until)).expect("Defer task should not fail");
+        let task = db.task_by_id(deferred).unwrap().unwrap();
+        assert_eq!(task.defer_time, Some(until));
+        assert!(task.deferred);
+
+        // deferred tasks are hidden unless asked for
+        let filter = TaskFilterOptions::default();
+        assert_eq!(ids(db.filter_tasks(|task| filter.passes(task)).unwrap()), [available]);
+        let filter = TaskFilterOptions { include_deferred: true, ..Default::default() };
+        assert_eq!(ids(db.filter_tasks(|task| filter.passes(task)).unwrap()), [deferred, available]);
+        // filtering on being deferred replaces the default hiding
+        let filter = TaskFilterOptions { deferred_filter: Some(ExactlyFilter { value: true }), ..Default::default() };
+        assert_eq!(ids(db.filter_tasks(|task| filter.passes(task)).unwrap()), [deferred]);
+        let filter = TaskFilterOptions {
+            deferred_filter: Some(ExactlyFilter { value: false }),
+            include_deferred: true,
+            ..Default::default()
+        };
+        assert_eq!(ids(db.filter_tasks(|task| filter.passes(task)).unwrap()), [available]);
+        // reports count hidden tasks unless filtered out explicitly
+        assert_eq!(ids(db.filter_tasks(|task| filter.passes_including_hidden(task)).unwrap()), [available]);
+        let filter = TaskFilterOptions::default();
+        assert_eq!(ids(db.filter_tasks(|task| filter.passes_including_hidden(task)).unwrap()), [deferred, available]);
+
+        // a task shows again by itself once its defer time has passed
+        db.defer_task(deferred, Some(Utc::now() - Duration::minutes(1))).unwrap();
+        assert!(!db.task_by_id(deferred).unwrap().unwrap().deferred);
+        db.defer_task(deferred, None).unwrap();
+        assert_eq!(db.task_by_id(deferred).unwrap().unwrap().defer_time, None);
+
+        assert_eq!(db.defer_task(0, Some(until)), Err(TaskDoesNotExistError { id: 0 }));
+    });
+}
+
+#[test]
+fn db_snooze_task() {
+    run_db_test(|mut db| {
+        let id = db.add_new_task(&sample_task_data()[1]).unwrap().id;
+        let now = Utc.timestamp_opt(Utc::now().timestamp(), 0).unwrap();
+        db.snooze_task(id, Duration::minutes(30), now).expect("Snooze task should not fail");
+        let task = db.task_by_id(id).unwrap().unwrap();
+        assert_eq!(task.defer_time, Some(now + Duration::minutes(30)));
+        assert!(task.deferred);
+
+        assert_eq!(db.snooze_task(id, Duration::max_value(), now), Err(DurationOutOfRangeError));
+        assert_eq!(db.task_by_id(id).unwrap().unwrap().defer_time, Some(now + Duration::minutes(30)));
+        assert_eq!(db.snooze_task(0, Duration::minutes(30), now), Err(TaskDoesNotExistError { id: 0 }));
+    });
+}
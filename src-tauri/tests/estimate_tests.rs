@@ -0,0 +1,115 @@
+use chrono::{Duration, TimeZone, Utc};
+use app::*;
+use DbError::{InvalidEstimateError, TaskDoesNotExistError};
+mod util;
+use util::*;
+
+fn estimate(estimate_minutes: Option<i64>, remaining_minutes: Option<i64>) -> TaskEstimate {
+    TaskEstimate { estimate_minutes, remaining_minutes }
+}
+
+fn sample_task() -> Task {
+    Task::from_parts(&sample_task_data()[1], &GeneratedTaskData {
+        id: 1,
+        create_time: Utc::now(),
+        last_edit_time: Utc::now(),
+        done_time: None,
+    })
+}
+
+#[test]
+fn validate_estimates() {
+    assert!(estimate(None, None).is_valid());
+    assert!(estimate(Some(30), Some(0)).is_valid());
+    assert!(estimate(Some(30), Some(45)).is_valid());
+    assert!(!estimate(Some(0), None).is_valid());
+    assert!(!estimate(Some(30), Some(-1)).is_valid());
+    assert!(!estimate(None, Some(10)).is_valid());
+    assert!(estimate(Some(TaskEstimate::MAX_MINUTES), Some(TaskEstimate::MAX_MINUTES)).is_valid());
+    assert!(!estimate(Some(TaskEstimate::MAX_MINUTES + 1), None).is_valid());
+    assert!(!estimate(Some(30), Some(i64::MAX)).is_valid());
+}
+
+#[test]
+fn remaining_effort_and_planning() {
+    let settings = PlannerSettings::default();
+    let mut task = sample_task();
+    assert_eq!(task.remaining_effort_minutes(), None);
+    assert_eq!(settings.estimate(&task), Duration::minutes(30));
+
+    task.estimate_minutes = Some(90);
+    assert_eq!(task.remaining_effort_minutes(), Some(90));
+    assert_eq!(settings.estimate(&task), Duration::minutes(90));
+    task.remaining_minutes = Some(20);
+    assert_eq!(settings.estimate(&task), Duration::minutes(20));
+    // nothing is left to plan, so it goes by difficulty again
+    task.remaining_minutes = Some(0);
+    assert_eq!(settings.estimate(&task), Duration::minutes(30));
+
+    // estimates stored out of range are capped rather than overflowing
+    task.estimate_minutes = Some(i64::MAX);
+    task.remaining_minutes = None;
+    assert_eq!(settings.estimate(&task), Duration::minutes(TaskEstimate::MAX_MINUTES));
+    let settings = PlannerSettings { minutes_per_difficulty: i64::MAX, ..PlannerSettings::default() };
+    assert!(!settings.is_valid());
+    task.estimate_minutes = None;
+    assert_eq!(settings.estimate(&task), Duration::minutes(TaskEstimate::MAX_MINUTES));
+
+    task.estimate_minutes = Some(90);
+    task.remaining_minutes = Some(20);
+    task.done_time = Some(Utc::now());
+    assert_eq!(task.remaining_effort_minutes(), Some(0));
+}
+
+#[test]
+fn compare_estimates() {
+    let comparison = EstimateComparison::new(1, 60, 90 * 60);
+    assert_eq!(comparison.difference_seconds, 30 * 60);
+    assert_eq!(comparison.ratio, 1.5);
+    let comparison = EstimateComparison::new(1, i64::MAX, i64::MIN);
+    assert_eq!(comparison.difference_seconds, i64::MIN);
+}
+
+#[test]
+fn db_task_estimates() {
+    run_db_test(|mut db| {
+        add_sample_tags(&mut db);
+        let tagged = db.add_new_task(&sample_task_data()[0]).unwrap().id;
+        let untagged = db.add_new_task(&sample_task_data()[1]).unwrap().id;
+        let other = db.add_new_task(&sample_task_data()[1]).unwrap().id;
+
+        db.set_task_estimate(tagged, &estimate(Some(60), Some(40))).expect("Set task estimate should not fail");
+        db.set_task_estimate(untagged, &estimate(Some(30), None)).unwrap();
+        let task = db.task_by_id(tagged).unwrap().unwrap();
+        assert_eq!((task.estimate_minutes, task.remaining_minutes), (Some(60), Some(40)));
+        assert_eq!(db.set_task_estimate(tagged, &estimate(None, Some(5))), Err(InvalidEstimateError));
+        assert_eq!(db.set_task_estimate(0, &estimate(None, None)), Err(TaskDoesNotExistError { id: 0 }));
+
+        let start = Utc.with_ymd_and_hms(2023, 9, 1, 9, 0, 0).unwrap();
+        db.add_time_entry(&EditableTimeEntryData {
+            task_id: tagged,
+            start_time: start,
+            end_time: Some(start + Duration::minutes(75)),
+        }).unwrap();
+
+        let totals = db.estimate_totals(|_| true, Utc::now()).unwrap();
+        assert_eq!(totals, EstimateTotals {
+            tasks: 3,
+            estimated: 2,
+            estimate_minutes: 90,
+            remaining_minutes: 70,
+            tracked_seconds: 75 * 60,
+        });
+        assert_eq!(db.estimate_totals(|task| task.id == other, Utc::now()).unwrap().estimated, 0);
+        let tags = db.tag_estimate_totals(Utc::now()).unwrap();
+        assert_eq!(tags.iter().map(|tag| (tag.tag_id, tag.totals.remaining_minutes)).collect::<Vec<_>>(),
+                   [(None, 30), (Some(1), 40)]);
+
+        // the comparison is only made once the task is finished
+        assert_eq!(db.estimate_comparison(tagged), Ok(None));
+        db.finish_task(tagged).unwrap();
+        assert_eq!(db.estimate_comparison(tagged), Ok(Some(EstimateComparison::new(tagged, 60, 75 * 60))));
+        assert_eq!(db.estimate_totals(|_| true, Utc::now()).unwrap().remaining_minutes, 30);
+        assert_eq!(db.estimate_comparison(0), Err(TaskDoesNotExistError { id: 0 }));
+    });
+}
@@ -0,0 +1,121 @@
+use app::*;
+
+fn s(string: &str) -> String {
+    String::from(string)
+}
+
+#[test]
+fn contains_filter_is_case_sensitive() {
+    let filter = ContainsStringFilter { pattern: s("berry") };
+    assert!(filter.passes(&s("A type of berry")));
+    assert!(!filter.passes(&s("A type of BERRY")));
+}
+
+#[test]
+fn insensitive_contains_filter() {
+    let filter = InsensitiveContainsStringFilter { pattern: s("cafe") };
+    assert!(filter.passes(&s("Go to the Café")));
+    assert!(filter.passes(&s("CAFÉ")));
+    assert!(!filter.passes(&s("caff")));
+}
+
+#[test]
+fn prefix_and_suffix_filters() {
+    let prefix = PrefixStringFilter { pattern: s("Blue"), ignore_case: false };
+    assert!(prefix.passes(&s("Blueberries")));
+    assert!(!prefix.passes(&s("blueberries")));
+    assert!(!prefix.passes(&s("Not Blueberries")));
+
+    let prefix = PrefixStringFilter { pattern: s("blue"), ignore_case: true };
+    assert!(prefix.passes(&s("Blueberries")));
+
+    let suffix = SuffixStringFilter { pattern: s("ries"), ignore_case: false };
+    assert!(suffix.passes(&s("Blueberries")));
+    assert!(!suffix.passes(&s("Blueberries!")));
+
+    let suffix = SuffixStringFilter { pattern: s("RIES"), ignore_case: true };
+    assert!(suffix.passes(&s("Blueberries")));
+}
+
+#[test]
+fn regex_filter() {
+    let filter = RegexStringFilter::new(r"^\d{3}-[a-z]+$").expect("Valid regex should compile");
+    assert!(filter.passes(&s("123-abc")));
+    assert!(!filter.passes(&s("12-abc")));
+}
+
+#[test]
+fn regex_filter_invalid_pattern() {
+    let error = RegexStringFilter::new("(unclosed").expect_err("Invalid regex should not compile");
+    assert!(matches!(&error, FilterError::InvalidRegexError { pattern, .. } if pattern == "(unclosed"));
+    assert!(error.to_string().starts_with("Invalid regular expression `(unclosed`"));
+}
+
+#[test]
+fn regex_filter_invalid_pattern_deserialization() {
+    let result = serde_json::from_str::<StringFilter>(r#"{"Regex": {"pattern": "[a-"}}"#);
+    let message = result.err().expect("Invalid regex should not deserialize").to_string();
+    assert!(message.contains("Invalid regular expression `[a-`"), "{}", message);
+}
+
+#[test]
+fn fuzzy_score_values() {
+    assert_eq!(fuzzy_score("", "anything"), Some(1.0));
+    assert_eq!(fuzzy_score("berry", "Blueberry"), Some(1.0));
+    assert_eq!(fuzzy_score("bry", "berry"), Some(0.6));
+    // shortest span is used, not the first one found
+    assert_eq!(fuzzy_score("ab", "a-x-ab"), Some(1.0));
+    assert_eq!(fuzzy_score("éA", "Eat"), Some(1.0));
+    assert_eq!(fuzzy_score("yrreb", "berry"), None);
+}
+
+#[test]
+fn fuzzy_filter() {
+    let filter = FuzzyStringFilter::new("wrt rpt", 0.5).unwrap();
+    assert!(filter.passes(&s("Write report")));
+    assert!(!filter.passes(&s("Write the big quarterly report")));
+    assert!(!filter.passes(&s("Read report")));
+}
+
+#[test]
+fn fuzzy_filter_invalid_score() {
+    assert_eq!(FuzzyStringFilter::new("a", 1.5), Err(FilterError::InvalidScoreError { min_score: 1.5 }));
+    assert!(serde_json::from_str::<FuzzyStringFilter>(r#"{"pattern": "a", "minScore": -1}"#).is_err());
+}
+
+#[test]
+fn string_filter_round_trip() {
+    let json = r#"{"Regex":{"pattern":"^a"}}"#;
+    let filter: StringFilter = serde_json::from_str(json).unwrap();
+    assert!(filter.passes(&s("abc")));
+    assert_eq!(serde_json::to_string(&filter).unwrap(), json);
+
+    let json = r#"{"Fuzzy":{"pattern":"abc","minScore":0.5}}"#;
+    let filter: StringFilter = serde_json::from_str(json).unwrap();
+    assert_eq!(serde_json::to_string(&filter).unwrap(), json);
+}
+
+#[test]
+fn task_filter_options_use_string_filters() {
+    let json = r#"{
+        "titleFilter": {"InsensitiveContains": {"pattern": "BLUE"}},
+        "bodyFilter": {"Prefix": {"pattern": "A type"}}
+    }"#;
+    let filter: TaskFilterOptions = serde_json::from_str(json).unwrap();
+    let task = Task::from_parts(&EditableTaskData {
+        title: s("Blueberries"),
+        tag: None,
+        body: s("A type of berry"),
+        difficulty: 0,
+        due_time: None,
+        due_all_day: false,
+        target_time: None,
+        paused: false,
+    }, &GeneratedTaskData {
+        id: 1,
+        create_time: chrono::Utc::now(),
+        last_edit_time: chrono::Utc::now(),
+        done_time: None,
+    });
+    assert!(filter.passes(&task));
+}
@@ -0,0 +1,136 @@
+use std::cell::Cell;
+use chrono::{Duration, NaiveDate};
+use app::*;
+use DbError::{InvalidFocusSettingsError, TaskDoesNotExistError};
+mod util;
+use util::*;
+
+fn sample_timer() -> FocusTimer<FakeClock> {
+    FocusTimer::new(FakeClock(Cell::new(sample_time())))
+}
+
+fn sample_settings() -> FocusSettings {
+    FocusSettings {
+        work_seconds: 25 * 60,
+        short_break_seconds: 5 * 60,
+        long_break_seconds: 15 * 60,
+        long_break_every: 2,
+    }
+}
+
+fn phase(started: DbResult<Option<FocusSession>>) -> Option<FocusPhase> {
+    started.unwrap().map(|session| session.phase)
+}
+
+#[test]
+fn db_focus_settings() {
+    run_db_test(|mut db| {
+        assert_eq!(db.focus_settings(), Ok(FocusSettings::default()));
+        db.set_focus_settings(&sample_settings()).expect("Set focus settings should not fail");
+        assert_eq!(db.focus_settings(), Ok(sample_settings()));
+        let invalid = FocusSettings { long_break_every: 0, ..sample_settings() };
+        assert_eq!(db.set_focus_settings(&invalid), Err(InvalidFocusSettingsError));
+        let max = FocusSettings { work_seconds: FocusSettings::MAX_PHASE_SECONDS, ..sample_settings() };
+        db.set_focus_settings(&max).unwrap();
+        for work_seconds in [FocusSettings::MAX_PHASE_SECONDS + 1, i64::MAX] {
+            let invalid = FocusSettings { work_seconds, ..sample_settings() };
+            assert_eq!(db.set_focus_settings(&invalid), Err(InvalidFocusSettingsError));
+        }
+        db.set_focus_settings(&sample_settings()).unwrap();
+        assert_eq!(db.focus_settings(), Ok(sample_settings()));
+    });
+}
+
+#[test]
+fn focus_session_cycles_through_phases() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        db.set_focus_settings(&sample_settings()).unwrap();
+        let timer = sample_timer();
+        let session = timer.start(&mut db, ids[0]).expect("Start focus should not fail");
+        assert_eq!(session.phase_end, sample_time() + Duration::minutes(25));
+        assert_eq!(db.focus_session(), Ok(Some(session)));
+        assert_eq!(timer.tick(&mut db), Ok(None));
+
+        timer.clock.advance(Duration::minutes(25));
+        assert_eq!(phase(timer.tick(&mut db)), Some(FocusPhase::ShortBreak));
+        timer.clock.advance(Duration::minutes(5));
+        assert_eq!(phase(timer.tick(&mut db)), Some(FocusPhase::Work));
+        // a tick a little late still completes the phase
+        timer.clock.advance(Duration::minutes(25) + Duration::seconds(MISSED_PHASE_SECONDS));
+        assert_eq!(phase(timer.tick(&mut db)), Some(FocusPhase::LongBreak));
+        timer.clock.advance(Duration::minutes(15) - Duration::seconds(MISSED_PHASE_SECONDS));
+        let started = timer.tick(&mut db).unwrap().unwrap();
+        assert_eq!(started.phase, FocusPhase::Work);
+        assert_eq!(started.completed_in_cycle, 0);
+        assert_eq!(started.phase_start, sample_time() + Duration::minutes(70));
+        assert_eq!(db.focus_stats(NaiveDate::from_ymd_opt(2023, 8, 31).unwrap()).unwrap().sessions, 2);
+
+        assert_eq!(timer.stop(&mut db).unwrap(), Some(started));
+        assert_eq!(db.focus_session(), Ok(None));
+        assert_eq!(timer.start(&mut db, 0), Err(TaskDoesNotExistError { id: 0 }));
+    });
+}
+
+#[test]
+fn focus_session_skip_does_not_count() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        db.set_focus_settings(&sample_settings()).unwrap();
+        let timer = sample_timer();
+        assert_eq!(timer.skip(&mut db), Ok(None));
+        timer.start(&mut db, ids[0]).unwrap();
+        timer.clock.advance(Duration::minutes(10));
+        let skipped = timer.skip(&mut db).unwrap().unwrap();
+        assert_eq!(skipped.phase, FocusPhase::ShortBreak);
+        assert_eq!(skipped.completed_in_cycle, 0);
+        assert_eq!(skipped.phase_end, sample_time() + Duration::minutes(15));
+        assert_eq!(db.focus_stats(NaiveDate::from_ymd_opt(2023, 8, 31).unwrap()).unwrap().sessions, 0);
+    });
+}
+
+#[test]
+fn focus_session_stops_after_restart() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let timer = sample_timer();
+        let session = timer.start(&mut db, ids[1]).unwrap();
+        drop(db);
+
+        let mut db = Db::connect(TEST_PATH).unwrap();
+        assert_eq!(db.focus_session(), Ok(Some(session.clone())));
+        // still in the first work phase
+        timer.clock.advance(Duration::minutes(20));
+        assert_eq!(timer.tick(&mut db), Ok(None));
+        assert_eq!(db.focus_session(), Ok(Some(session)));
+
+        // closed through the end of the work phase and the break after it
+        timer.clock.advance(Duration::minutes(20));
+        assert_eq!(timer.tick(&mut db), Ok(None));
+        assert_eq!(db.focus_session(), Ok(None));
+        assert_eq!(db.focus_stats(NaiveDate::from_ymd_opt(2023, 8, 31).unwrap()).unwrap().sessions, 0);
+    });
+}
+
+#[test]
+fn db_focus_stats() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let date = NaiveDate::from_ymd_opt(2023, 8, 31).unwrap();
+        db.record_focus_session(ids[0], sample_time(), sample_time() + Duration::minutes(25)).unwrap();
+        db.record_focus_session(ids[0], sample_time() + Duration::hours(1), sample_time() + Duration::minutes(85)).unwrap();
+        db.record_focus_session(ids[1], sample_time(), sample_time() + Duration::minutes(20)).unwrap();
+        // the next day
+        db.record_focus_session(ids[1], sample_time() + Duration::hours(12), sample_time() + Duration::minutes(745)).unwrap();
+
+        let stats = db.focus_stats(date).unwrap();
+        assert_eq!(stats.sessions, 3);
+        assert_eq!(stats.seconds, 70 * 60);
+        assert_eq!(stats.for_task(ids[0]), Some(&FocusCount { id: ids[0], sessions: 2, seconds: 50 * 60 }));
+        assert_eq!(stats.for_task(ids[1]), Some(&FocusCount { id: ids[1], sessions: 1, seconds: 20 * 60 }));
+        assert_eq!(stats.tags, vec![FocusCount { id: 1, sessions: 2, seconds: 50 * 60 }]);
+
+        db.delete_task(ids[0]).unwrap();
+        assert_eq!(db.focus_stats(date).unwrap().sessions, 1);
+    });
+}
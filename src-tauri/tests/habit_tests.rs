@@ -0,0 +1,122 @@
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+use app::*;
+use DbError::{InvalidHabitError, NotAHabitError, TaskDoesNotExistError, TaskStatusError};
+mod util;
+use util::*;
+
+fn daily(grace_days: u32) -> Habit {
+    Habit { task_id: 1, every_days: 1, grace_days }
+}
+
+/// Adds an untagged task and makes it a daily habit, returning its id.
+fn add_habit(db: &mut Db) -> TaskId {
+    let id = db.add_new_task(&sample_task_data()[1]).unwrap().id;
+    db.set_habit(&Habit { task_id: id, ..daily(0) }).unwrap();
+    id
+}
+
+#[test]
+fn habit_streaks() {
+    let dates = [date(2023, 9, 1), date(2023, 9, 2), date(2023, 9, 3), date(2023, 9, 5), date(2023, 9, 6)];
+    assert_eq!(daily(0).streaks(&dates, date(2023, 9, 6)), HabitStreaks { current: 2, longest: 3 });
+    assert_eq!(daily(0).streaks(&dates, date(2023, 9, 7)), HabitStreaks { current: 2, longest: 3 });
+    assert_eq!(daily(0).streaks(&dates, date(2023, 9, 8)), HabitStreaks { current: 0, longest: 3 });
+    // a missed day is forgiven
+    assert_eq!(daily(1).streaks(&dates, date(2023, 9, 8)), HabitStreaks { current: 5, longest: 5 });
+    // check-ins after today are ignored
+    assert_eq!(daily(0).streaks(&dates, date(2023, 9, 2)), HabitStreaks { current: 2, longest: 2 });
+    assert_eq!(daily(0).streaks(&[], date(2023, 9, 2)), HabitStreaks { current: 0, longest: 0 });
+
+    let weekly = Habit { task_id: 1, every_days: 7, grace_days: 0 };
+    let dates = [date(2023, 9, 1), date(2023, 9, 8), date(2023, 9, 16)];
+    assert_eq!(weekly.streaks(&dates, date(2023, 9, 20)), HabitStreaks { current: 1, longest: 2 });
+
+    let forever = Habit { task_id: 1, every_days: u32::MAX, grace_days: u32::MAX };
+    assert_eq!(forever.streaks(&dates, date(2023, 9, 20)), HabitStreaks { current: 3, longest: 3 });
+}
+
+#[test]
+fn db_habit_set_and_remove() {
+    run_db_test(|mut db| {
+        let id = add_habit(&mut db);
+        assert_eq!(db.habit(id), Ok(Some(Habit { task_id: id, ..daily(0) })));
+        db.set_habit(&Habit { task_id: id, ..daily(2) }).expect("Set habit should not fail");
+        assert_eq!(db.all_habits(), Ok(vec![Habit { task_id: id, ..daily(2) }]));
+        assert_eq!(db.set_habit(&Habit { task_id: id, every_days: 0, grace_days: 0 }), Err(InvalidHabitError));
+        assert_eq!(db.set_habit(&Habit { task_id: 0, ..daily(0) }), Err(TaskDoesNotExistError { id: 0 }));
+        let done = db.add_new_task(&sample_task_data()[1]).unwrap().id;
+        db.finish_task(done).unwrap();
+        assert_eq!(db.set_habit(&Habit { task_id: done, ..daily(0) }), Err(TaskStatusError { id: done, actual_status: true }));
+        assert_eq!(db.habit(done), Ok(None));
+
+        db.check_in_habit(id, date(2023, 9, 1)).unwrap();
+        db.remove_habit(id).expect("Remove habit should not fail");
+        assert_eq!(db.habit(id), Ok(None));
+        assert_eq!(db.remove_habit(id), Err(NotAHabitError { id }));
+        assert_eq!(db.check_in_habit(id, date(2023, 9, 1)), Err(NotAHabitError { id }));
+        assert_eq!(db.habit_check_ins(id), Ok(vec![]));
+    });
+}
+
+#[test]
+fn db_habit_finish_checks_in() {
+    run_db_test(|mut db| {
+        let id = add_habit(&mut db);
+        db.set_user_timezone("America/New_York").unwrap();
+        // still 30 August in New York
+        let now = Utc.with_ymd_and_hms(2023, 8, 31, 3, 0, 0).unwrap();
+        assert_eq!(db.finish_task_at(id, now), Ok(FinishedTaskData { done_time: None }));
+        assert!(!db.task_by_id(id).unwrap().unwrap().is_done());
+        assert_eq!(db.habit_check_ins(id), Ok(vec![date(2023, 8, 30)]));
+        assert_eq!(db.finish_task_at(id, now), Err(TaskStatusError { id, actual_status: true }));
+        db.finish_task_at(id, sample_time()).expect("Check in on the next day should not fail");
+        assert_eq!(db.habit_check_ins(id), Ok(vec![date(2023, 8, 30), date(2023, 8, 31)]));
+
+        db.unfinish_task_at(id, now).expect("Unfinish habit should not fail");
+        assert_eq!(db.habit_check_ins(id), Ok(vec![date(2023, 8, 31)]));
+        assert_eq!(db.unfinish_task_at(id, now), Err(TaskStatusError { id, actual_status: false }));
+    });
+}
+
+#[test]
+fn db_habit_finish_stops_timer() {
+    run_db_test(|mut db| {
+        let id = add_habit(&mut db);
+        db.start_timer(id, sample_time()).unwrap();
+        db.finish_task_at(id, sample_time() + Duration::hours(1)).unwrap();
+        assert_eq!(db.running_timer(), Ok(None));
+        assert_eq!(db.time_entries_for_task(id).unwrap()[0].end_time, Some(sample_time() + Duration::hours(1)));
+
+        // a failed check-in leaves the timer running
+        db.start_timer(id, sample_time() + Duration::hours(2)).unwrap();
+        assert_eq!(db.finish_task_at(id, sample_time() + Duration::hours(3)), Err(TaskStatusError { id, actual_status: true }));
+        assert!(db.running_timer().unwrap().is_some());
+    });
+}
+
+#[test]
+fn db_habit_streaks_and_heatmap() {
+    run_db_test(|mut db| {
+        let id = add_habit(&mut db);
+        let other = add_habit(&mut db);
+        for day in [1, 2, 3, 5] {
+            db.check_in_habit(id, date(2023, 9, day)).unwrap();
+        }
+        db.check_in_habit(other, date(2023, 9, 3)).unwrap();
+        assert_eq!(db.check_in_habit(id, date(2023, 9, 3)), Err(TaskStatusError { id, actual_status: true }));
+        assert_eq!(db.habit_streaks(id, date(2023, 9, 5)), Ok(HabitStreaks { current: 1, longest: 3 }));
+        assert_eq!(db.habit_streaks(0, date(2023, 9, 5)), Err(NotAHabitError { id: 0 }));
+
+        let counts = |heatmap: Vec<HeatmapDay>| heatmap.iter().map(|day| day.check_ins).collect::<Vec<_>>();
+        assert_eq!(counts(db.habit_heatmap(None, date(2023, 9, 2), date(2023, 9, 5)).unwrap()), vec![1, 2, 0, 1]);
+        assert_eq!(counts(db.habit_heatmap(Some(other), date(2023, 9, 2), date(2023, 9, 5)).unwrap()), vec![0, 1, 0, 0]);
+        assert_eq!(db.habit_heatmap(None, date(2023, 9, 2), date(2023, 9, 2)).unwrap()[0].date, date(2023, 9, 2));
+
+        db.delete_task(id).unwrap();
+        assert_eq!(counts(db.habit_heatmap(None, date(2023, 9, 1), date(2023, 9, 1) + Duration::days(1)).unwrap()), vec![0, 0]);
+
+        assert_eq!(db.habit_heatmap(None, date(2023, 9, 2), date(2023, 9, 1)), Err(DbError::InvalidDateRangeError));
+        assert_eq!(db.habit_heatmap(None, date(1900, 1, 1), date(2023, 9, 1)), Err(DbError::InvalidDateRangeError));
+        assert_eq!(db.habit_heatmap(None, NaiveDate::MAX, NaiveDate::MAX), Err(DbError::DateOutOfRangeError));
+    });
+}
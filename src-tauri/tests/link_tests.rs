@@ -0,0 +1,118 @@
+use app::*;
+use DbError::TaskDoesNotExistError;
+use TaskReference::{Id, Title};
+mod util;
+use util::*;
+
+fn title(title: &str) -> TaskReference {
+    Title { title: String::from(title) }
+}
+
+/// Adds a task with no tag, returning its id.
+fn add_task(db: &mut Db, title: &str, body: &str) -> TaskId {
+    let data = EditableTaskData { title: String::from(title), tag: None, body: String::from(body), ..sample_task_data()[1].clone() };
+    db.add_new_task(&data).unwrap().id
+}
+
+fn rename_task(db: &mut Db, id: TaskId, title: &str) {
+    let task = db.task_by_id(id).unwrap().unwrap();
+    let data = EditableTaskData {
+        title: String::from(title),
+        tag: task.tag,
+        body: task.body,
+        difficulty: task.difficulty,
+        due_time: task.due_time,
+        due_all_day: task.due_all_day,
+        target_time: task.target_time,
+        paused: task.paused,
+    };
+    db.modify_task(id, &data).unwrap();
+}
+
+#[test]
+fn extract_task_references() {
+    assert_eq!(extract_references("See [[Buy milk]] and #12, then [[ Buy milk ]] again."),
+               vec![title("Buy milk"), Id { id: 12 }]);
+    assert_eq!(extract_references("# Heading\nC#7, #12a, issue#3, &#123; and [[]] are not references"), vec![]);
+    assert_eq!(extract_references("(#4) [[a\nb]] [[Ünïcode]]"), vec![Id { id: 4 }, title("Ünïcode")]);
+    assert_eq!(replace_title_references("[[Old]], [[ Old ]] and [[Older]]", "Old", "New"),
+               "[[New]], [[New]] and [[Older]]");
+    assert_eq!(replace_id_references("#4, (#4) #42 &#4; C#4", 4, 7), "#7, (#7) #42 &#4; C#4");
+}
+
+#[test]
+fn db_links_and_backlinks() {
+    run_db_test(|mut db| {
+        let milk = add_task(&mut db, "Buy milk", "");
+        let eggs = add_task(&mut db, "Buy eggs", "");
+        let list = add_task(&mut db, "Shopping", &format!("[[Buy milk]], #{eggs}, [[Buy bread]]"));
+        assert_eq!(db.task_links(list), Ok(vec![
+            TaskLink { source: list, target: Some(milk), reference: title("Buy milk") },
+            TaskLink { source: list, target: Some(eggs), reference: Id { id: eggs } },
+            TaskLink { source: list, target: None, reference: title("Buy bread") },
+        ]));
+        assert_eq!(db.backlinks(milk), Ok(vec![TaskLink { source: list, target: Some(milk), reference: title("Buy milk") }]));
+        assert_eq!(db.broken_links().unwrap().len(), 1);
+
+        // a new task with the title resolves the broken link
+        let bread = add_task(&mut db, "Buy bread", "");
+        assert_eq!(db.backlinks(bread).unwrap().len(), 1);
+        assert_eq!(db.broken_links(), Ok(vec![]));
+
+        // links follow edits of the body
+        let data = EditableTaskData { title: String::from("Shopping"), body: String::from("Only [[Buy eggs]]"), ..sample_task_data()[1].clone() };
+        db.modify_task(list, &data).unwrap();
+        assert_eq!(db.backlinks(milk), Ok(vec![]));
+        assert_eq!(db.task_links(list), Ok(vec![TaskLink { source: list, target: Some(eggs), reference: title("Buy eggs") }]));
+
+        assert_eq!(db.task_links(0), Err(TaskDoesNotExistError { id: 0 }));
+        assert_eq!(db.backlinks(0), Err(TaskDoesNotExistError { id: 0 }));
+    });
+}
+
+#[test]
+fn renaming_task_updates_links() {
+    run_db_test(|mut db| {
+        let milk = add_task(&mut db, "Buy milk", "");
+        let list = add_task(&mut db, "Shopping", "[[Buy milk]] and [[Buy oat milk]]");
+        rename_task(&mut db, milk, "Buy oat milk");
+        assert_eq!(db.task_by_id(list).unwrap().unwrap().body, "[[Buy oat milk]] and [[Buy oat milk]]");
+        assert_eq!(db.backlinks(milk).unwrap().len(), 2);
+        assert!(db.backlinks(milk).unwrap().iter().all(|link| link.reference == title("Buy oat milk")));
+        assert_eq!(db.broken_links(), Ok(vec![]));
+    });
+}
+
+#[test]
+fn deleting_task_breaks_links() {
+    run_db_test(|mut db| {
+        let milk = add_task(&mut db, "Buy milk", "");
+        let list = add_task(&mut db, "Shopping", &format!("[[Buy milk]] #{milk}"));
+        db.delete_task(milk).unwrap();
+        assert_eq!(db.broken_links(), Ok(vec![
+            TaskLink { source: list, target: None, reference: title("Buy milk") },
+            TaskLink { source: list, target: None, reference: Id { id: milk } },
+        ]));
+
+        // only the title can refer to another task
+        let again = add_task(&mut db, "Buy milk", "");
+        assert_eq!(db.backlinks(again), Ok(vec![TaskLink { source: list, target: Some(again), reference: title("Buy milk") }]));
+        assert_eq!(db.broken_links().unwrap().len(), 1);
+
+        db.delete_task(list).unwrap();
+        assert_eq!(db.broken_links(), Ok(vec![]));
+    });
+}
+
+#[test]
+fn deleting_task_moves_links_to_same_title() {
+    run_db_test(|mut db| {
+        let first = add_task(&mut db, "Buy milk", "");
+        let second = add_task(&mut db, "Buy milk", "");
+        let list = add_task(&mut db, "Shopping", "[[Buy milk]]");
+        assert_eq!(db.backlinks(first), Ok(vec![TaskLink { source: list, target: Some(first), reference: title("Buy milk") }]));
+        db.delete_task(first).unwrap();
+        assert_eq!(db.backlinks(second), Ok(vec![TaskLink { source: list, target: Some(second), reference: title("Buy milk") }]));
+        assert_eq!(db.broken_links(), Ok(vec![]));
+    });
+}
@@ -0,0 +1,123 @@
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::America::New_York;
+use chrono_tz::{Antarctica, Pacific};
+use chrono_tz::Tz;
+use app::*;
+mod util;
+use util::*;
+
+#[test]
+fn day_bounds_regular_day() {
+    let (start, end) = day_bounds(&New_York, date(2023, 9, 1)).unwrap();
+    assert_eq!(start, Utc.with_ymd_and_hms(2023, 9, 1, 4, 0, 0).unwrap());
+    assert_eq!(end - start, Duration::hours(24));
+}
+
+#[test]
+fn day_bounds_across_dst() {
+    // spring forward: 2:00 becomes 3:00
+    let (start, end) = day_bounds(&New_York, date(2023, 3, 12)).unwrap();
+    assert_eq!(start, Utc.with_ymd_and_hms(2023, 3, 12, 5, 0, 0).unwrap());
+    assert_eq!(end - start, Duration::hours(23));
+    // fall back: 2:00 becomes 1:00
+    let (start, end) = day_bounds(&New_York, date(2023, 11, 5)).unwrap();
+    assert_eq!(start, Utc.with_ymd_and_hms(2023, 11, 5, 4, 0, 0).unwrap());
+    assert_eq!(end - start, Duration::hours(25));
+    assert_eq!(day_bounds(&New_York, NaiveDate::MAX), None);
+}
+
+#[test]
+fn resolve_local_across_dst() {
+    // skipped time moves forward an hour
+    let skipped = date(2023, 3, 12).and_hms_opt(2, 30, 0).unwrap();
+    assert_eq!(resolve_local(&New_York, skipped), Utc.with_ymd_and_hms(2023, 3, 12, 7, 30, 0).unwrap());
+    // ambiguous time takes the earlier one, in daylight time
+    let repeated = date(2023, 11, 5).and_hms_opt(1, 30, 0).unwrap();
+    assert_eq!(resolve_local(&New_York, repeated), Utc.with_ymd_and_hms(2023, 11, 5, 5, 30, 0).unwrap());
+}
+
+#[test]
+fn resolve_local_across_long_gaps() {
+    // two hours skipped, from 1:00 UTC+0 to 3:00 UTC+2
+    let skipped = date(2024, 3, 31).and_hms_opt(1, 30, 0).unwrap();
+    assert_eq!(resolve_local(&Antarctica::Troll, skipped), Utc.with_ymd_and_hms(2024, 3, 31, 1, 30, 0).unwrap());
+    // 30 December 2011 was skipped, going from UTC-10 to UTC+14
+    let (start, end) = day_bounds(&Pacific::Apia, date(2011, 12, 30)).unwrap();
+    assert_eq!(start, Utc.with_ymd_and_hms(2011, 12, 30, 10, 0, 0).unwrap());
+    assert_eq!(end, start);
+    assert_eq!(day_start(&Pacific::Apia, date(2011, 12, 29)), Utc.with_ymd_and_hms(2011, 12, 29, 10, 0, 0).unwrap());
+}
+
+#[test]
+fn add_local_days_keeps_wall_clock_time() {
+    // 9:00 EST
+    let before = Utc.with_ymd_and_hms(2023, 3, 11, 14, 0, 0).unwrap();
+    // 9:00 EDT
+    assert_eq!(add_local_days(&New_York, &before, 1), Some(Utc.with_ymd_and_hms(2023, 3, 12, 13, 0, 0).unwrap()));
+    assert_eq!(add_local_days(&New_York, &Utc.with_ymd_and_hms(2023, 3, 12, 13, 0, 0).unwrap(), -1), Some(before));
+    assert_eq!(add_local_days(&New_York, &before, i64::MAX), None);
+    assert_eq!(add_local_days(&New_York, &before, 1_000_000_000), None);
+    assert_eq!(local_date(&New_York, &before), date(2023, 3, 11));
+}
+
+#[test]
+fn quick_add_relative_dates_across_dst() {
+    let now = Utc.with_ymd_and_hms(2023, 3, 11, 15, 0, 0).unwrap().with_timezone(&New_York);
+    let parsed = parse_quick_add("Water plants tomorrow 9am", &now, &[]);
+    assert_eq!(parsed.data.due_time, Some(Utc.with_ymd_and_hms(2023, 3, 12, 13, 0, 0).unwrap()));
+}
+
+#[test]
+fn task_due_date_all_day_and_timed() {
+    let mut data = sample_task_data()[1].clone();
+    // 22:00 on 31 August in New York
+    data.due_time = Some(Utc.with_ymd_and_hms(2023, 9, 1, 2, 0, 0).unwrap());
+    let generated = GeneratedTaskData {
+        id: 1,
+        create_time: Utc::now(),
+        last_edit_time: Utc::now(),
+        done_time: None,
+    };
+    let timed = Task::from_parts(&data, &generated);
+    assert_eq!(timed.due_date(&New_York), Some(date(2023, 8, 31)));
+    assert_eq!(timed.due_date(&Utc), Some(date(2023, 9, 1)));
+
+    data.due_time = Some(all_day_time(date(2023, 9, 1)));
+    data.due_all_day = true;
+    let all_day = Task::from_parts(&data, &generated);
+    assert_eq!(all_day.due_date(&New_York), Some(date(2023, 9, 1)));
+    assert_eq!(all_day.due_date(&Utc), Some(date(2023, 9, 1)));
+}
+
+#[test]
+fn db_user_timezone() {
+    run_db_test(|mut db| {
+        assert_eq!(db.user_timezone(), Ok(Tz::UTC));
+        db.set_user_timezone("America/New_York").expect("Set timezone should not fail");
+        assert_eq!(db.user_timezone(), Ok(New_York));
+        assert_eq!(db.set_user_timezone("Mars/Olympus_Mons"),
+                   Err(DbError::InvalidTimezoneError { name: String::from("Mars/Olympus_Mons") }));
+        assert_eq!(db.user_timezone(), Ok(New_York));
+    });
+}
+
+#[test]
+fn db_tasks_due_on() {
+    run_db_test(|mut db| {
+        let mut timed = sample_task_data()[1].clone();
+        timed.due_time = Some(Utc.with_ymd_and_hms(2023, 9, 1, 2, 0, 0).unwrap());
+        let timed_result = db.add_new_task(&timed).unwrap();
+        let mut all_day = sample_task_data()[1].clone();
+        all_day.due_time = Some(all_day_time(date(2023, 9, 1)));
+        all_day.due_all_day = true;
+        let all_day_result = db.add_new_task(&all_day).unwrap();
+
+        let ids = |tasks: Vec<Task>| tasks.iter().map(|task| task.id).collect::<Vec<_>>();
+        assert_eq!(ids(db.tasks_due_on(date(2023, 9, 1)).unwrap()), vec![timed_result.id, all_day_result.id]);
+
+        db.set_user_timezone("America/New_York").unwrap();
+        assert_eq!(ids(db.tasks_due_on(date(2023, 9, 1)).unwrap()), vec![all_day_result.id]);
+        assert_eq!(ids(db.tasks_due_on(date(2023, 8, 31)).unwrap()), vec![timed_result.id]);
+        assert!(db.task_by_id(all_day_result.id).unwrap().unwrap().due_all_day);
+    });
+}
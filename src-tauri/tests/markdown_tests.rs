@@ -0,0 +1,77 @@
+use chrono::{TimeZone, Utc};
+use rusqlite::Connection;
+use app::*;
+use DbError::TaskDoesNotExistError;
+mod util;
+use util::*;
+
+const BODY: &str = "# Plan\n\nBuy *fresh* [berries](https://example.com/berries), due tomorrow at 5pm.\n\n\
+## Notes\n\n<script>alert(1)</script><a href=\"javascript:alert(1)\" onclick=\"x()\">click</a>\n\n\
+- [x] wash\n- [ ] eat, due 2023-09-01";
+
+#[test]
+fn render_markdown_body() {
+    let rendered = render_body(BODY);
+    assert!(rendered.html.contains("<h1>Plan</h1>"));
+    assert!(rendered.html.contains("<em>fresh</em>"));
+    assert!(!rendered.html.contains("script"));
+    assert!(!rendered.html.contains("javascript"));
+    assert!(!rendered.html.contains("onclick"));
+    assert!(rendered.preview.starts_with("Plan Buy fresh berries, due tomorrow at 5pm. Notes"));
+    assert!(!rendered.preview.contains('\n'));
+}
+
+#[test]
+fn outline_markdown_body() {
+    let written = Utc.with_ymd_and_hms(2023, 8, 20, 12, 0, 0).unwrap();
+    let outline = outline_body(BODY, &written);
+    assert_eq!(outline.headings, vec![
+        Heading { level: 1, text: String::from("Plan") },
+        Heading { level: 2, text: String::from("Notes") },
+    ]);
+    assert_eq!(outline.links[0], MarkdownLink { text: String::from("berries"), url: String::from("https://example.com/berries") });
+    assert_eq!(outline.due_mentions, vec![
+        DueMention {
+            text: String::from("due tomorrow at 5pm"),
+            time: Utc.with_ymd_and_hms(2023, 8, 21, 17, 0, 0).unwrap(),
+            all_day: false,
+        },
+        DueMention {
+            text: String::from("due 2023-09-01"),
+            time: Utc.with_ymd_and_hms(2023, 9, 1, 0, 0, 0).unwrap(),
+            all_day: true,
+        },
+    ]);
+}
+
+#[test]
+fn long_preview_is_shortened() {
+    let rendered = render_body(&"berry ".repeat(100));
+    assert!(rendered.preview.ends_with('…'));
+    assert!(rendered.preview.chars().count() <= PREVIEW_LENGTH);
+    assert!(rendered.preview.starts_with("berry berry"));
+    assert_eq!(render_body("").preview, "");
+}
+
+#[test]
+fn db_rendered_body_is_cached_until_modified() {
+    run_db_test(|mut db| {
+        add_sample_tags(&mut db);
+        let mut data = sample_task_data()[0].clone();
+        data.body = String::from("# Plan");
+        let id = db.add_new_task(&data).unwrap().id;
+        assert_eq!(db.rendered_body(id).unwrap().html, "<h1>Plan</h1>\n");
+        assert_eq!(db.body_outline(id).unwrap().headings[0].text, "Plan");
+
+        // the cached result is kept while the body is unchanged
+        let connection = Connection::open(TEST_PATH).unwrap();
+        connection.execute("UPDATE tasks SET body = '# Other' WHERE id = ?1", (id,)).unwrap();
+        assert_eq!(db.rendered_body(id).unwrap().preview, "Plan");
+
+        data.body = String::from("# Changed");
+        db.modify_task(id, &data).unwrap();
+        assert_eq!(db.rendered_body(id).unwrap().preview, "Changed");
+        assert_eq!(db.rendered_body(0), Err(TaskDoesNotExistError { id: 0 }));
+        assert_eq!(db.body_outline(0), Err(TaskDoesNotExistError { id: 0 }));
+    });
+}
@@ -0,0 +1,42 @@
+use chrono::{Duration, TimeZone, Timelike, Utc};
+use app::*;
+mod util;
+use util::*;
+
+#[test]
+fn my_date_time_truncates_to_seconds() {
+    let time = Utc.with_ymd_and_hms(2023, 9, 1, 12, 30, 15).unwrap() + Duration::milliseconds(999);
+    let truncated = MyDateTime::from(time);
+    assert_eq!(truncated.0, Utc.with_ymd_and_hms(2023, 9, 1, 12, 30, 15).unwrap());
+    assert_eq!(MyDateTime::now().0.nanosecond(), 0);
+}
+
+#[test]
+fn my_date_time_serde_round_trip() {
+    let time = MyDateTime::try_from(1693571415).unwrap();
+    let json = serde_json::to_string(&time).unwrap();
+    assert_eq!(json, "1693571415");
+    assert_eq!(serde_json::from_str::<MyDateTime>(&json).unwrap(), time);
+    assert!(serde_json::from_str::<MyDateTime>("\"2023-09-01\"").is_err());
+    assert!(serde_json::from_str::<MyDateTime>(&i64::MAX.to_string()).is_err());
+    assert_eq!(MyDateTime::try_from(i64::MIN), Err(TimestampOutOfRangeError { seconds: i64::MIN }));
+}
+
+#[test]
+fn db_times_are_whole_seconds() {
+    run_db_test(|mut db| {
+        let mut data = sample_task_data()[0].clone();
+        data.tag = None;
+        data.due_time = Some(Utc.with_ymd_and_hms(2023, 9, 1, 0, 0, 0).unwrap() + Duration::milliseconds(500));
+        let generated = db.add_new_task(&data).unwrap();
+        assert_eq!(generated.create_time.nanosecond(), 0);
+        assert_eq!(generated.last_edit_time.nanosecond(), 0);
+
+        let task = db.task_by_id(generated.id).unwrap().unwrap();
+        assert_eq!(task.due_time, Some(Utc.with_ymd_and_hms(2023, 9, 1, 0, 0, 0).unwrap()));
+        assert_eq!(task.create_time, generated.create_time);
+
+        let finished = db.finish_task(generated.id).unwrap();
+        assert_eq!(db.task_by_id(generated.id).unwrap().unwrap().done_time, finished.done_time);
+    });
+}
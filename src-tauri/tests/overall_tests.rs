@@ -1,9 +1,133 @@
+use chrono::{TimeZone, Utc};
+use rusqlite::Connection;
+use app::{ChecklistProgress, Db, HslColor, Tag, Task};
 mod util;
 use util::*;
 
+/// The tables as they were stored before any migrations, with text timestamps.
+const LEGACY_SCHEMA: &str = r#"
+    create table tags (
+        "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+        "name" TEXT NOT NULL,
+        "color" INTEGER NOT NULL,
+        "active" INTEGER NOT NULL,
+        "create_time" STRING NOT NULL
+    );
+    create table tasks (
+        "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+        "title" TEXT NOT NULL,
+        "body" TEXT NOT NULL,
+        "difficulty" INTEGER NOT NULL,
+        "create_time" STRING NOT NULL,
+        "last_edit_time" STRING NOT NULL,
+        "due_time" STRING,
+        "target_time" STRING,
+        "done_time" STRING,
+        "paused" INTEGER
+    );
+    create table tags_tasks (
+        "task_id" INTEGER NOT NULL,
+        "tag_id" INTEGER NOT NULL,
+        PRIMARY KEY (task_id, tag_id)
+    );
+"#;
+
 #[test]
 fn create_database() {
     run_db_test(|_| {
         // Nothing, `run_db_test` creates a database and unwraps
     });
-}
\ No newline at end of file
+}
+
+#[test]
+fn reconnect_database() {
+    run_db_test(|mut db| {
+        db.set_user_timezone("Europe/Paris").unwrap();
+        drop(db);
+        // migrations should not be applied again
+        let db = Db::connect(TEST_PATH).expect("Reconnecting should not fail");
+        assert_eq!(db.user_timezone().unwrap().name(), "Europe/Paris");
+    });
+}
+
+
+#[test]
+fn migrate_text_timestamps() {
+    run_db_test(|db| {
+        drop(db);
+        // recreate the database as it was stored before any migrations
+        _ = std::fs::remove_file(TEST_PATH);
+        let conn = Connection::open(TEST_PATH).unwrap();
+        conn.execute_batch(LEGACY_SCHEMA).unwrap();
+        let create_time = Utc.with_ymd_and_hms(2023, 5, 1, 15, 30, 0).unwrap();
+        let due_time = Utc.with_ymd_and_hms(2023, 9, 1, 0, 0, 0).unwrap();
+        conn.execute(
+            "INSERT INTO tags (name, color, active, create_time) values ('old', 0, 1, ?1);",
+            (&create_time,)).unwrap();
+        conn.execute(r#"
+            INSERT INTO tasks (title, body, difficulty, create_time, last_edit_time, due_time, paused)
+            values ('first', '', 1, ?1, ?1, ?2, 0), ('second', '', 2, ?1, ?1, NULL, 1);
+        "#, (&create_time, &due_time)).unwrap();
+        conn.execute("INSERT INTO tags_tasks (task_id, tag_id) values (1, 1);", ()).unwrap();
+        conn.execute("DELETE FROM tasks WHERE id = 2;", ()).unwrap();
+        drop(conn);
+
+        let mut db = Db::connect(TEST_PATH).expect("Migrating should not fail");
+        assert_eq!(db.all_tags().unwrap(), vec![Tag {
+            id: 1,
+            name: String::from("old"),
+            color: HslColor { hue: 0, saturation: 0, lightness: 0 },
+            active: true,
+            create_time,
+        }]);
+        assert_eq!(db.all_tasks().unwrap(), vec![Task {
+            id: 1,
+            title: String::from("first"),
+            tag: Some(1),
+            body: String::new(),
+            difficulty: 1,
+            create_time,
+            last_edit_time: create_time,
+            due_time: Some(due_time),
+            due_all_day: false,
+            target_time: None,
+            done_time: None,
+            paused: false,
+            project: None,
+            archived: false,
+            checklist: ChecklistProgress::default(),
+            defer_time: None,
+            deferred: false,
+            estimate_minutes: None,
+            remaining_minutes: None,
+        }]);
+        // ids of deleted tasks are still not reused
+        assert_eq!(db.add_new_task(&sample_task_data()[1]).unwrap().id, 3);
+        drop(db);
+
+        let conn = Connection::open(TEST_PATH).unwrap();
+        let types: (String, String) = conn.query_row(
+            "SELECT typeof(create_time), typeof(due_time) FROM tasks WHERE id = 1", [],
+            |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        assert_eq!(types, (String::from("integer"), String::from("integer")));
+    });
+}
+
+#[test]
+fn migrate_empty_tables_keep_sequence() {
+    run_db_test(|db| {
+        drop(db);
+        _ = std::fs::remove_file(TEST_PATH);
+        let conn = Connection::open(TEST_PATH).unwrap();
+        conn.execute_batch(LEGACY_SCHEMA).unwrap();
+        conn.execute_batch(r#"
+            INSERT INTO tags (name, color, active, create_time) values ('a', 0, 1, '2023-05-01'), ('b', 0, 1, '2023-05-01');
+            DELETE FROM tags;
+        "#).unwrap();
+        drop(conn);
+
+        let mut db = Db::connect(TEST_PATH).expect("Migrating should not fail");
+        // ids of tags deleted before the migration are still not reused
+        assert_eq!(db.add_new_tag(&sample_tag_data()[0]).unwrap().id, 3);
+    });
+}
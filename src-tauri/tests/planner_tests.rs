@@ -0,0 +1,207 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+use app::*;
+use DbError::{InvalidPlannerSettingsError, InvalidTimeBlockError, TaskDoesNotExistError, TimeBlockDoesNotExistError};
+mod util;
+use util::*;
+
+fn sample_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2023, 8, 31).unwrap()
+}
+
+fn time(hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2023, 8, 31, hour, minute, 0).unwrap()
+}
+
+fn block(id: TimeBlockId, start: DateTime<Utc>, end: DateTime<Utc>) -> TimeBlock {
+    TimeBlock {
+        id,
+        task_id: None,
+        title: String::from("Meeting"),
+        start_time: start,
+        end_time: end,
+        fixed: true,
+        auto_scheduled: false,
+    }
+}
+
+fn meeting() -> EditableTimeBlockData {
+    EditableTimeBlockData {
+        task_id: None,
+        title: String::from("Meeting"),
+        start_time: time(10, 0),
+        end_time: time(11, 0),
+        fixed: true,
+    }
+}
+
+#[test]
+fn planner_find_conflicts() {
+    let blocks = vec![
+        block(1, time(9, 0), time(10, 0)),
+        block(2, time(9, 30), time(11, 0)),
+        block(3, time(10, 30), time(11, 30)),
+        // touching is not overlapping
+        block(4, time(11, 30), time(12, 0)),
+    ];
+    assert_eq!(find_conflicts(&blocks), vec![
+        TimeBlockConflict { first: 1, second: 2 },
+        TimeBlockConflict { first: 2, second: 3 },
+    ]);
+}
+
+#[test]
+fn planner_auto_schedule() {
+    let tasks: Vec<Task> = sample_task_data().iter().enumerate().map(|(i, data)| {
+        Task::from_parts(data, &GeneratedTaskData {
+            id: i as TaskId + 1,
+            create_time: time(8, 0),
+            last_edit_time: time(8, 0),
+            done_time: None,
+        })
+    }).collect();
+    let busy = vec![block(1, time(10, 0), time(11, 0))];
+    let scheduled = auto_schedule(&tasks, &busy, time(9, 0), time(13, 0), &PlannerSettings::default());
+    // the task due first takes 90 minutes, so doesn't fit before the meeting
+    assert_eq!(scheduled.iter().map(|block| (block.task_id, block.start_time, block.end_time)).collect::<Vec<_>>(), vec![
+        (Some(2), time(9, 0), time(9, 30)),
+        (Some(1), time(11, 0), time(12, 30)),
+    ]);
+    assert!(scheduled.iter().all(|block| !block.fixed));
+
+    let scheduled = auto_schedule(&tasks, &busy, time(9, 0), time(12, 0), &PlannerSettings::default());
+    assert_eq!(scheduled.iter().map(|block| block.task_id).collect::<Vec<_>>(), vec![Some(2)]);
+}
+
+#[test]
+fn db_planner_settings() {
+    run_db_test(|mut db| {
+        assert_eq!(db.planner_settings(), Ok(PlannerSettings::default()));
+        let settings = PlannerSettings {
+            work_start: NaiveTime::from_hms_opt(8, 30, 0).unwrap(),
+            work_end: NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+            minutes_per_difficulty: 20,
+        };
+        db.set_planner_settings(&settings).expect("Set planner settings should not fail");
+        assert_eq!(db.planner_settings(), Ok(settings.clone()));
+        let invalid = PlannerSettings { work_end: settings.work_start, ..settings.clone() };
+        assert_eq!(db.set_planner_settings(&invalid), Err(InvalidPlannerSettingsError));
+        assert_eq!(db.planner_settings(), Ok(settings));
+    });
+}
+
+#[test]
+fn db_time_blocks() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let id = db.add_time_block(&meeting()).expect("Add time block should not fail").id;
+        assert_eq!(db.time_block_by_id(id), Ok(Some(TimeBlock::from_parts(&meeting(), &GeneratedTimeBlockData { id }))));
+
+        let work = EditableTimeBlockData {
+            task_id: Some(ids[0]),
+            title: String::from("Work"),
+            start_time: time(10, 30),
+            end_time: time(12, 0),
+            fixed: true,
+        };
+        let work_id = db.add_time_block(&work).unwrap().id;
+        let plan = db.day_plan(sample_date()).unwrap();
+        assert_eq!(plan.blocks.iter().map(|block| block.id).collect::<Vec<_>>(), vec![id, work_id]);
+        assert_eq!(plan.conflicts, vec![TimeBlockConflict { first: id, second: work_id }]);
+
+        let moved = EditableTimeBlockData { start_time: time(11, 0), ..work.clone() };
+        db.modify_time_block(work_id, &moved).expect("Modify time block should not fail");
+        assert_eq!(db.day_plan(sample_date()).unwrap().conflicts, vec![]);
+
+        let backwards = EditableTimeBlockData { end_time: time(9, 0), ..meeting() };
+        assert_eq!(db.add_time_block(&backwards), Err(InvalidTimeBlockError));
+        let missing_task = EditableTimeBlockData { task_id: Some(0), ..meeting() };
+        assert_eq!(db.add_time_block(&missing_task), Err(TaskDoesNotExistError { id: 0 }));
+        assert_eq!(db.modify_time_block(0, &meeting()), Err(TimeBlockDoesNotExistError { id: 0 }));
+
+        db.delete_task(ids[0]).unwrap();
+        assert_eq!(db.time_block_by_id(work_id), Ok(None));
+        db.delete_time_block(id).expect("Delete time block should not fail");
+        assert_eq!(db.delete_time_block(id), Err(TimeBlockDoesNotExistError { id }));
+        assert_eq!(db.day_plan(sample_date()).unwrap().blocks, vec![]);
+    });
+}
+
+#[test]
+fn db_auto_schedule_day() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let meeting_id = db.add_time_block(&meeting()).unwrap().id;
+        let plan = db.auto_schedule_day(sample_date(), time(8, 0)).expect("Auto schedule should not fail");
+        let planned = |plan: &DayPlan| plan.blocks.iter()
+            .map(|block| (block.task_id, block.start_time, block.end_time))
+            .collect::<Vec<_>>();
+        assert_eq!(planned(&plan), vec![
+            (Some(ids[1]), time(9, 0), time(9, 30)),
+            (None, time(10, 0), time(11, 0)),
+            (Some(ids[0]), time(11, 0), time(12, 30)),
+        ]);
+        assert_eq!(plan.conflicts, vec![]);
+
+        // replanning later in the day moves the scheduled blocks, but not the meeting
+        let plan = db.auto_schedule_day(sample_date(), time(9, 15)).unwrap();
+        assert_eq!(planned(&plan), vec![
+            (Some(ids[1]), time(9, 15), time(9, 45)),
+            (None, time(10, 0), time(11, 0)),
+            (Some(ids[0]), time(11, 0), time(12, 30)),
+        ]);
+        assert_eq!(plan.blocks[1].id, meeting_id);
+        assert_eq!(plan.blocks.iter().map(|block| block.auto_scheduled).collect::<Vec<_>>(), vec![true, false, true]);
+
+        // done tasks are not scheduled
+        db.finish_task(ids[1]).unwrap();
+        let plan = db.auto_schedule_day(sample_date(), time(8, 0)).unwrap();
+        assert_eq!(plan.blocks.len(), 2);
+
+        // blocks of the user are kept even if not fixed, including moved auto-scheduled blocks
+        let lunch = EditableTimeBlockData {
+            title: String::from("Lunch"),
+            start_time: time(12, 30),
+            end_time: time(13, 30),
+            fixed: false,
+            ..meeting()
+        };
+        let lunch_id = db.add_time_block(&lunch).unwrap().id;
+        let moved = &plan.blocks[1];
+        db.modify_time_block(moved.id, &EditableTimeBlockData {
+            task_id: moved.task_id,
+            title: moved.title.clone(),
+            start_time: time(14, 0),
+            end_time: time(15, 30),
+            fixed: false,
+        }).unwrap();
+        let plan = db.auto_schedule_day(sample_date(), time(8, 0)).unwrap();
+        assert_eq!(planned(&plan), vec![
+            (None, time(10, 0), time(11, 0)),
+            (None, time(12, 30), time(13, 30)),
+            (Some(ids[0]), time(14, 0), time(15, 30)),
+        ]);
+        assert_eq!(plan.blocks[1].id, lunch_id);
+        assert!(plan.blocks.iter().all(|block| !block.auto_scheduled));
+    });
+}
+
+#[test]
+fn db_auto_schedule_day_skips_archived_projects() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let project = db.add_new_project(&EditableProjectData {
+            name: String::from("Orchard"),
+            description: String::new(),
+            status: ProjectStatus::Active,
+            deadline: None,
+        }).unwrap().id;
+        db.move_task_to_project(ids[1], Some(project), None).unwrap();
+        db.set_project_archived(project, true).unwrap();
+        let plan = db.auto_schedule_day(sample_date(), time(8, 0)).unwrap();
+        assert_eq!(plan.blocks.iter().map(|block| block.task_id).collect::<Vec<_>>(), vec![Some(ids[0])]);
+
+        db.set_project_archived(project, false).unwrap();
+        let plan = db.auto_schedule_day(sample_date(), time(8, 0)).unwrap();
+        assert_eq!(plan.blocks.len(), 2);
+    });
+}
@@ -0,0 +1,128 @@
+use chrono::{TimeZone, Utc};
+use app::*;
+use DbError::{ProjectDoesNotExistError, TaskDoesNotExistError};
+mod util;
+use util::*;
+
+fn sample_project_data() -> EditableProjectData {
+    EditableProjectData {
+        name: String::from("Garden"),
+        description: String::from("Grow some fruit"),
+        status: ProjectStatus::Active,
+        deadline: Some(Utc.with_ymd_and_hms(2023, 10, 1, 0, 0, 0).unwrap()),
+    }
+}
+
+/// Adds the sample tags and three tasks, returning the ids of the tasks.
+fn add_project_tasks(db: &mut Db) -> Vec<TaskId> {
+    let mut ids = add_sample_tasks(db);
+    ids.push(db.add_new_task(&sample_task_data()[1]).unwrap().id);
+    ids
+}
+
+fn task_ids(tasks: &[Task]) -> Vec<TaskId> {
+    tasks.iter().map(|task| task.id).collect()
+}
+
+#[test]
+fn db_add_modify_delete_project() {
+    run_db_test(|mut db| {
+        let generated = db.add_new_project(&sample_project_data()).expect("Add project should not fail");
+        let project = Project::from_parts(&sample_project_data(), &generated);
+        assert_eq!(db.project_by_id(generated.id), Ok(Some(project.clone())));
+
+        let on_hold = EditableProjectData { status: ProjectStatus::OnHold, deadline: None, ..sample_project_data() };
+        db.modify_project(generated.id, &on_hold).expect("Modify project should not fail");
+        assert_eq!(db.all_projects(), Ok(vec![Project::from_parts(&on_hold, &generated)]));
+        assert_eq!(db.modify_project(0, &on_hold), Err(ProjectDoesNotExistError { id: 0 }));
+
+        db.delete_project(generated.id).expect("Delete project should not fail");
+        assert_eq!(db.project_by_id(generated.id), Ok(None));
+        assert_eq!(db.delete_project(generated.id), Err(ProjectDoesNotExistError { id: generated.id }));
+    });
+}
+
+#[test]
+fn db_move_tasks_between_projects() {
+    run_db_test(|mut db| {
+        let ids = add_project_tasks(&mut db);
+        let garden = db.add_new_project(&sample_project_data()).unwrap().id;
+        let kitchen = db.add_new_project(&sample_project_data()).unwrap().id;
+        for id in &ids {
+            db.move_task_to_project(*id, Some(garden), None).expect("Move task should not fail");
+        }
+        assert_eq!(task_ids(&db.project_tasks(garden).unwrap()), ids);
+        assert_eq!(db.task_by_id(ids[0]).unwrap().unwrap().project, Some(garden));
+
+        // reorder within the project
+        db.move_task_to_project(ids[2], Some(garden), Some(0)).unwrap();
+        assert_eq!(task_ids(&db.project_tasks(garden).unwrap()), vec![ids[2], ids[0], ids[1]]);
+
+        db.move_task_to_project(ids[0], Some(kitchen), Some(5)).unwrap();
+        assert_eq!(task_ids(&db.project_tasks(garden).unwrap()), vec![ids[2], ids[1]]);
+        assert_eq!(task_ids(&db.project_tasks(kitchen).unwrap()), vec![ids[0]]);
+
+        db.move_task_to_project(ids[1], None, None).unwrap();
+        assert_eq!(db.task_by_id(ids[1]).unwrap().unwrap().project, None);
+        assert_eq!(db.move_task_to_project(0, Some(garden), None), Err(TaskDoesNotExistError { id: 0 }));
+        assert_eq!(db.move_task_to_project(ids[1], Some(0), None), Err(ProjectDoesNotExistError { id: 0 }));
+
+        db.delete_task(ids[2]).unwrap();
+        assert_eq!(db.project_tasks(garden), Ok(vec![]));
+        db.delete_project(kitchen).unwrap();
+        assert_eq!(db.task_by_id(ids[0]).unwrap().unwrap().project, None);
+    });
+}
+
+#[test]
+fn db_project_progress() {
+    run_db_test(|mut db| {
+        let ids = add_project_tasks(&mut db);
+        let garden = db.add_new_project(&sample_project_data()).unwrap().id;
+        assert_eq!(db.project_progress(garden).unwrap().completion_rate, None);
+        for id in &ids {
+            db.move_task_to_project(*id, Some(garden), None).unwrap();
+        }
+        db.finish_task(ids[0]).unwrap();
+        db.finish_task(ids[1]).unwrap();
+        assert_eq!(db.project_progress(garden), Ok(ProjectProgress {
+            project_id: garden,
+            total: 3,
+            completed: 2,
+            difficulty_total: 3,
+            difficulty_completed: 3,
+            completion_rate: Some(2.0 / 3.0),
+        }));
+        assert_eq!(db.project_progress(0), Err(ProjectDoesNotExistError { id: 0 }));
+    });
+}
+
+#[test]
+fn db_archived_project_hides_tasks() {
+    run_db_test(|mut db| {
+        let ids = add_project_tasks(&mut db);
+        let garden = db.add_new_project(&sample_project_data()).unwrap().id;
+        db.move_task_to_project(ids[0], Some(garden), None).unwrap();
+        db.set_project_archived(garden, true).expect("Archive project should not fail");
+        assert!(db.project_by_id(garden).unwrap().unwrap().archived);
+
+        let filter = TaskFilterOptions::default();
+        assert_eq!(task_ids(&db.filter_tasks(|task| filter.passes(task)).unwrap()), vec![ids[1], ids[2]]);
+        let filter = TaskFilterOptions {
+            project_filter: Some(OptionalFilter::OnlySome(SetFilter { set: [garden].into() })),
+            include_archived: true,
+            ..Default::default()
+        };
+        assert_eq!(task_ids(&db.filter_tasks(|task| filter.passes(task)).unwrap()), vec![ids[0]]);
+        let filter = TaskFilterOptions {
+            project_filter: Some(OptionalFilter::OnlyNone),
+            ..Default::default()
+        };
+        assert_eq!(task_ids(&db.filter_tasks(|task| filter.passes(task)).unwrap()), vec![ids[1], ids[2]]);
+
+        db.set_project_archived(garden, false).unwrap();
+        let filter = TaskFilterOptions::default();
+        assert_eq!(db.filter_tasks(|task| filter.passes(task)).unwrap().len(), 3);
+        assert_eq!(db.set_project_archived(0, true), Err(ProjectDoesNotExistError { id: 0 }));
+    });
+}
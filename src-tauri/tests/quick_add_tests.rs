@@ -0,0 +1,101 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone, Utc};
+use app::*;
+mod util;
+use util::*;
+
+/// Wednesday, 6 September 2023, 10:00 at UTC-7.
+fn sample_now() -> DateTime<FixedOffset> {
+    FixedOffset::west_opt(7 * 3600).unwrap()
+        .with_ymd_and_hms(2023, 9, 6, 10, 0, 0).unwrap()
+}
+
+fn local(day: u32, hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+    sample_now().timezone()
+        .with_ymd_and_hms(2023, 9, day, hour, minute, second).unwrap()
+        .with_timezone(&Utc)
+}
+
+#[test]
+fn quick_add_parse_full_line() {
+    let tag = Tag::from_parts(&sample_tag_data()[0], &GeneratedTagData { id: 4, create_time: Utc::now() });
+    let input = "Write report #New_Tag tomorrow 5pm !3 target fri";
+    let parsed = parse_quick_add(input, &sample_now(), &[tag]);
+
+    assert_eq!(parsed.data, EditableTaskData {
+        title: String::from("Write report"),
+        tag: Some(4),
+        body: String::new(),
+        difficulty: 3,
+        due_time: Some(local(7, 17, 0, 0)),
+        due_all_day: false,
+        target_time: Some(local(8, 23, 59, 59)),
+        paused: false,
+    });
+    let spans: Vec<&str> = parsed.pieces.iter()
+        .map(|piece| &input[piece.start..piece.end])
+        .collect();
+    assert_eq!(spans, vec!["Write", "report", "#New_Tag", "tomorrow 5pm", "!3", "target fri"]);
+    assert_eq!(parsed.pieces[2].kind, QuickAddPieceKind::Tag { name: String::from("New_Tag"), id: Some(4) });
+    assert_eq!(parsed.pieces[3].kind, QuickAddPieceKind::DueTime { time: local(7, 17, 0, 0), all_day: false });
+}
+
+#[test]
+fn quick_add_parse_all_day_due_date() {
+    let parsed = parse_quick_add("x fri", &sample_now(), &[]);
+    let friday = NaiveDate::from_ymd_opt(2023, 9, 8).unwrap();
+    assert!(parsed.data.due_all_day);
+    assert_eq!(parsed.data.due_time, Some(all_day_time(friday)));
+    assert_eq!(parsed.pieces[1].kind, QuickAddPieceKind::DueTime { time: all_day_time(friday), all_day: true });
+}
+
+#[test]
+fn quick_add_parse_unknown_tag_and_paused() {
+    let parsed = parse_quick_add("Call mom #family +paused", &sample_now(), &[]);
+    assert_eq!(parsed.data.title, "Call mom");
+    assert_eq!(parsed.data.tag, None);
+    assert!(parsed.data.paused);
+    assert_eq!(parsed.pieces[2].kind, QuickAddPieceKind::Tag { name: String::from("family"), id: None });
+    assert_eq!(parsed.pieces[3].kind, QuickAddPieceKind::Paused);
+}
+
+#[test]
+fn quick_add_parse_dates_and_times() {
+    let now = sample_now();
+    let due = |input: &str| parse_quick_add(input, &now, &[]).data.due_time;
+    assert_eq!(due("x today"), Some(all_day_time(NaiveDate::from_ymd_opt(2023, 9, 6).unwrap())));
+    assert_eq!(due("x at noon"), Some(local(6, 12, 0, 0)));
+    assert_eq!(due("x 17:30"), Some(local(6, 17, 30, 0)));
+    assert_eq!(due("x due wed at 9:15am"), Some(local(13, 9, 15, 0)));
+    assert_eq!(due("x in 2 days 12am"), Some(local(8, 0, 0, 0)));
+    assert_eq!(due("x in 1 week"), Some(all_day_time(NaiveDate::from_ymd_opt(2023, 9, 13).unwrap())));
+    assert_eq!(due("x 2023-09-30"), Some(all_day_time(NaiveDate::from_ymd_opt(2023, 9, 30).unwrap())));
+    // not dates or times
+    assert_eq!(due("x 5 in the due date 13pm"), None);
+    assert_eq!(parse_quick_add("Sign in 5 minutes", &now, &[]).data.title, "Sign in 5 minutes");
+    for count in ["9223372036854775807", "1317624576693539401", "95000000"] {
+        let input = format!("Wait in {count} weeks");
+        assert_eq!(parse_quick_add(&input, &now, &[]).data.title, input);
+    }
+}
+
+#[test]
+fn quick_add_parse_utf16_spans() {
+    let parsed = parse_quick_add("café !2", &sample_now(), &[]);
+    assert_eq!(parsed.pieces[1], QuickAddPiece { kind: QuickAddPieceKind::Difficulty { value: 2 }, start: 5, end: 7 });
+}
+
+#[test]
+fn db_quick_add_task() {
+    run_db_test(|mut db| {
+        let tag_result = db.add_new_tag(&sample_tag_data()[1]).unwrap();
+        let added = db.quick_add_task("Eat whee! tomorrow", &sample_now())
+            .expect("Quick add should not fail");
+        assert_eq!(added.parsed.data.tag, None);
+
+        let added = db.quick_add_task("Eat #whee! tomorrow", &sample_now())
+            .expect("Quick add should not fail");
+        assert_eq!(added.parsed.data.tag, Some(tag_result.id));
+        assert_eq!(db.task_by_id(added.generated.id).unwrap().unwrap(),
+                   Task::from_parts(&added.parsed.data, &added.generated));
+    });
+}
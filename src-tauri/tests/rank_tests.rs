@@ -0,0 +1,126 @@
+use rusqlite::Connection;
+use app::*;
+use DbError::TaskDoesNotExistError;
+mod util;
+use util::*;
+
+#[test]
+fn rank_between_sorts_between() {
+    let cases = [
+        (None, None),
+        (Some("V"), None),
+        (None, Some("V")),
+        (Some("1"), Some("2")),
+        (Some("1"), Some("12")),
+        (Some("0z"), Some("1")),
+        (None, Some("01")),
+        (Some("zz"), None),
+    ];
+    for (before, after) in cases {
+        let rank = rank_between(before, after);
+        if let Some(before) = before {
+            assert!(before < rank.as_str(), "{before} < {rank}");
+        }
+        if let Some(after) = after {
+            assert!(rank.as_str() < after, "{rank} < {after}");
+        }
+        assert!(!rank.ends_with('0'));
+    }
+}
+
+#[test]
+fn rank_between_grows_slowly() {
+    // always inserting at the same spot only lengthens keys every few moves
+    let mut after = String::from("1");
+    for _ in 0..20 {
+        after = rank_between(Some("0z"), Some(&after));
+    }
+    assert!(after.len() <= 6, "{after}");
+}
+
+#[test]
+fn rank_after_stays_short() {
+    let mut before = None;
+    for _ in 0..300 {
+        let rank = rank_after(before.as_deref());
+        if let Some(before) = &before {
+            assert!(before < &rank, "{before} < {rank}");
+        }
+        assert!(!rank.ends_with('0'));
+        before = Some(rank);
+    }
+    // one more digit every 61 keys
+    assert!(before.as_ref().unwrap().len() <= 6, "{before:?}");
+    assert_eq!(rank_after(Some("zz")), "zz1");
+    assert_eq!(rank_after(Some("y5")), "z");
+}
+
+#[test]
+fn rank_sequence_is_sorted() {
+    for count in [0, 1, 2, 61, 62, 500] {
+        let ranks = rank_sequence(count);
+        assert_eq!(ranks.len(), count);
+        assert!(ranks.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(ranks.iter().all(|rank| !rank.is_empty() && !rank.ends_with('0')));
+    }
+}
+
+/// Adds the sample tags and `count` tasks, returning the ids of the tasks.
+fn add_tasks(db: &mut Db, count: usize) -> Vec<TaskId> {
+    add_sample_tags(db);
+    (0..count).map(|_| db.add_new_task(&sample_task_data()[1]).unwrap().id).collect()
+}
+
+fn order(db: &Db) -> Vec<TaskId> {
+    db.all_tasks().unwrap().iter().map(|task| task.id).collect()
+}
+
+#[test]
+fn db_move_tasks() {
+    run_db_test(|mut db| {
+        let ids = add_tasks(&mut db, 4);
+        assert_eq!(order(&db), ids);
+        db.move_task_before(ids[3], ids[0]).expect("Move task before should not fail");
+        assert_eq!(order(&db), vec![ids[3], ids[0], ids[1], ids[2]]);
+        db.move_task_after(ids[0], ids[2]).expect("Move task after should not fail");
+        assert_eq!(order(&db), vec![ids[3], ids[1], ids[2], ids[0]]);
+        db.move_task_after(ids[1], ids[3]).unwrap();
+        assert_eq!(order(&db), vec![ids[3], ids[1], ids[2], ids[0]]);
+
+        // new tasks go last
+        let new = db.add_new_task(&sample_task_data()[0]).unwrap().id;
+        assert_eq!(*order(&db).last().unwrap(), new);
+        let filtered: Vec<TaskId> = db.filter_tasks(|task| task.tag.is_none()).unwrap().iter().map(|task| task.id).collect();
+        assert_eq!(filtered, vec![ids[3], ids[1], ids[2], ids[0]]);
+
+        assert_eq!(db.move_task_before(0, ids[0]), Err(TaskDoesNotExistError { id: 0 }));
+        assert_eq!(db.move_task_after(ids[0], 0), Err(TaskDoesNotExistError { id: 0 }));
+    });
+}
+
+#[test]
+fn db_append_tasks_keeps_ranks_short() {
+    run_db_test(|mut db| {
+        // enough appends for keys to reach the length at which they are rebalanced
+        let ids = add_tasks(&mut db, 1000);
+        assert_eq!(order(&db), ids);
+        let conn = Connection::open(TEST_PATH).unwrap();
+        let longest: usize = conn.query_row("SELECT max(length(rank)) FROM tasks", [], |row| row.get(0)).unwrap();
+        assert!(longest <= 16, "{longest}");
+    });
+}
+
+#[test]
+fn db_rebalance_task_ranks() {
+    run_db_test(|mut db| {
+        let ids = add_tasks(&mut db, 3);
+        // squeeze tasks between the first two until keys are rebalanced
+        for i in 0..200 {
+            db.move_task_after(ids[2 - i % 2], ids[0]).unwrap();
+        }
+        let expected = vec![ids[0], ids[1], ids[2]];
+        assert_eq!(order(&db), expected);
+        db.rebalance_task_ranks().expect("Rebalance should not fail");
+        assert_eq!(order(&db), expected);
+    });
+}
@@ -0,0 +1,226 @@
+use std::cell::{Cell, RefCell};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use app::*;
+use DbError::{DurationOutOfRangeError, ReminderDoesNotExistError, TaskDoesNotExistError};
+mod util;
+use util::*;
+
+#[derive(Default)]
+struct FakeNotifier(RefCell<Vec<ReminderId>>);
+impl Notifier for FakeNotifier {
+    fn notify(&self, reminder: &ScheduledReminder) {
+        self.0.borrow_mut().push(reminder.reminder.id);
+    }
+}
+impl FakeNotifier {
+    fn take(&self) -> Vec<ReminderId> {
+        self.0.take()
+    }
+}
+
+fn sample_scheduler() -> ReminderScheduler<FakeClock, FakeNotifier> {
+    ReminderScheduler::new(FakeClock(Cell::new(sample_time())), FakeNotifier::default())
+}
+
+/// Adds a task due at `sample_task_data()[0].due_time`, returning its id.
+fn add_sample_task(db: &mut Db) -> TaskId {
+    let mut task_data = sample_task_data()[0].clone();
+    task_data.tag = None;
+    db.add_new_task(&task_data).unwrap().id
+}
+
+fn add_reminder(db: &mut Db, task_id: TaskId, trigger: ReminderTrigger) -> ReminderId {
+    db.add_new_reminder(&EditableReminderData { task_id, trigger })
+        .expect("Adding reminder should not fail").id
+}
+
+#[test]
+fn db_reminder_add_and_get() {
+    run_db_test(|mut db| {
+        let task_id = add_sample_task(&mut db);
+        let data = EditableReminderData {
+            task_id,
+            trigger: ReminderTrigger::BeforeDue { offset_seconds: 3600 },
+        };
+        let generated = db.add_new_reminder(&data).unwrap();
+        assert_eq!(db.reminders_for_task(task_id).unwrap(), vec![Reminder::from_parts(&data, &generated)]);
+        assert_eq!(db.reminder_by_id(generated.id).unwrap(), Some(Reminder::from_parts(&data, &generated)));
+
+        let missing = EditableReminderData { task_id: 0, ..data };
+        assert_eq!(db.add_new_reminder(&missing), Err(TaskDoesNotExistError { id: 0 }));
+        for offset_seconds in [ReminderTrigger::MAX_OFFSET_SECONDS + 1, i64::MIN, i64::MAX] {
+            let invalid = EditableReminderData { task_id, trigger: ReminderTrigger::BeforeTarget { offset_seconds } };
+            assert_eq!(db.add_new_reminder(&invalid), Err(DurationOutOfRangeError));
+        }
+    });
+}
+
+#[test]
+fn db_reminder_delete() {
+    run_db_test(|mut db| {
+        let task_id = add_sample_task(&mut db);
+        let first = add_reminder(&mut db, task_id, ReminderTrigger::BeforeDue { offset_seconds: 0 });
+        add_reminder(&mut db, task_id, ReminderTrigger::BeforeTarget { offset_seconds: 0 });
+        db.delete_reminder(first).expect("Delete reminder should not fail");
+        assert_eq!(db.delete_reminder(first), Err(ReminderDoesNotExistError { id: first }));
+        assert_eq!(db.reminders_for_task(task_id).unwrap().len(), 1);
+
+        db.delete_task(task_id).unwrap();
+        assert_eq!(db.reminders_for_task(task_id).unwrap(), vec![]);
+    });
+}
+
+#[test]
+fn scheduler_fires_when_time_comes() {
+    run_db_test(|mut db| {
+        let scheduler = sample_scheduler();
+        let task_id = add_sample_task(&mut db);
+        let at = add_reminder(&mut db, task_id, ReminderTrigger::At { time: sample_time() + Duration::minutes(5) });
+        // due on 2023-09-01 at midnight
+        let before_due = add_reminder(&mut db, task_id, ReminderTrigger::BeforeDue { offset_seconds: 3600 });
+
+        assert_eq!(scheduler.tick(&mut db), Ok(Some(sample_time() + Duration::minutes(5))));
+        assert_eq!(scheduler.notifier.take(), vec![]);
+
+        scheduler.clock.advance(Duration::minutes(5));
+        assert_eq!(scheduler.tick(&mut db), Ok(Some(Utc.with_ymd_and_hms(2023, 8, 31, 23, 0, 0).unwrap())));
+        assert_eq!(scheduler.notifier.take(), vec![at]);
+
+        scheduler.clock.advance(Duration::hours(12));
+        assert_eq!(scheduler.tick(&mut db), Ok(None));
+        assert_eq!(scheduler.notifier.take(), vec![before_due]);
+        // fires only once
+        assert_eq!(scheduler.tick(&mut db), Ok(None));
+        assert_eq!(scheduler.notifier.take(), vec![]);
+    });
+}
+
+#[test]
+fn scheduler_follows_due_time_changes() {
+    run_db_test(|mut db| {
+        let scheduler = sample_scheduler();
+        let task_id = add_sample_task(&mut db);
+        add_reminder(&mut db, task_id, ReminderTrigger::BeforeDue { offset_seconds: 60 });
+
+        let mut task_data = sample_task_data()[0].clone();
+        task_data.tag = None;
+        task_data.due_time = Some(sample_time() + Duration::hours(1));
+        db.modify_task(task_id, &task_data).unwrap();
+        assert_eq!(scheduler.tick(&mut db), Ok(Some(sample_time() + Duration::minutes(59))));
+
+        task_data.due_time = None;
+        db.modify_task(task_id, &task_data).unwrap();
+        assert_eq!(scheduler.tick(&mut db), Ok(None));
+    });
+}
+
+#[test]
+fn scheduler_catches_up_after_restart() {
+    run_db_test(|mut db| {
+        let task_id = add_sample_task(&mut db);
+        let later = add_reminder(&mut db, task_id, ReminderTrigger::At { time: sample_time() + Duration::hours(2) });
+        let earlier = add_reminder(&mut db, task_id, ReminderTrigger::At { time: sample_time() + Duration::hours(1) });
+        drop(db);
+
+        // the application was closed while both reminders should have fired
+        let mut db = Db::connect(TEST_PATH).unwrap();
+        let scheduler = sample_scheduler();
+        scheduler.clock.advance(Duration::days(1));
+        assert_eq!(scheduler.tick(&mut db), Ok(None));
+        assert_eq!(scheduler.notifier.take(), vec![earlier, later]);
+        drop(db);
+
+        let mut db = Db::connect(TEST_PATH).unwrap();
+        assert_eq!(scheduler.tick(&mut db), Ok(None));
+        assert_eq!(scheduler.notifier.take(), vec![]);
+    });
+}
+
+#[test]
+fn scheduler_snooze_and_dismiss() {
+    run_db_test(|mut db| {
+        let scheduler = sample_scheduler();
+        let task_id = add_sample_task(&mut db);
+        let snoozed = add_reminder(&mut db, task_id, ReminderTrigger::At { time: sample_time() });
+        let dismissed = add_reminder(&mut db, task_id, ReminderTrigger::At { time: sample_time() });
+        scheduler.tick(&mut db).unwrap();
+        assert_eq!(scheduler.notifier.take(), vec![snoozed, dismissed]);
+
+        scheduler.snooze(&mut db, snoozed, Duration::minutes(10)).expect("Snooze should not fail");
+        scheduler.snooze(&mut db, dismissed, Duration::minutes(10)).unwrap();
+        db.dismiss_reminder(dismissed).expect("Dismiss should not fail");
+        assert_eq!(scheduler.tick(&mut db), Ok(Some(sample_time() + Duration::minutes(10))));
+
+        scheduler.clock.advance(Duration::minutes(10));
+        scheduler.tick(&mut db).unwrap();
+        assert_eq!(scheduler.notifier.take(), vec![snoozed]);
+        assert_eq!(scheduler.snooze(&mut db, 0, Duration::minutes(1)), Err(ReminderDoesNotExistError { id: 0 }));
+        assert_eq!(scheduler.snooze(&mut db, snoozed, Duration::max_value()), Err(DurationOutOfRangeError));
+    });
+}
+
+#[test]
+fn scheduler_fires_again_after_snooze() {
+    run_db_test(|mut db| {
+        let scheduler = sample_scheduler();
+        let task_id = add_sample_task(&mut db);
+        let reminder = add_reminder(&mut db, task_id, ReminderTrigger::BeforeDue { offset_seconds: 0 });
+        scheduler.snooze(&mut db, reminder, Duration::minutes(10)).unwrap();
+        scheduler.clock.advance(Duration::minutes(10));
+        scheduler.tick(&mut db).unwrap();
+        assert_eq!(scheduler.notifier.take(), vec![reminder]);
+        assert_eq!(db.reminder_by_id(reminder).unwrap().unwrap().snoozed_until, None);
+
+        // due again after the snoozed fire, so the reminder follows the due time
+        let mut task_data = sample_task_data()[0].clone();
+        task_data.tag = None;
+        task_data.due_time = Some(sample_time() + Duration::hours(1));
+        db.modify_task(task_id, &task_data).unwrap();
+        assert_eq!(scheduler.tick(&mut db), Ok(Some(sample_time() + Duration::hours(1))));
+        scheduler.clock.advance(Duration::hours(1));
+        scheduler.tick(&mut db).unwrap();
+        assert_eq!(scheduler.notifier.take(), vec![reminder]);
+    });
+}
+
+#[test]
+fn scheduler_fires_before_all_day_date_in_user_timezone() {
+    run_db_test(|mut db| {
+        let scheduler = sample_scheduler();
+        db.set_user_timezone("America/New_York").unwrap();
+        let mut task_data = sample_task_data()[0].clone();
+        task_data.tag = None;
+        task_data.due_time = Some(all_day_time(NaiveDate::from_ymd_opt(2023, 9, 1).unwrap()));
+        task_data.due_all_day = true;
+        let task_id = db.add_new_task(&task_data).unwrap().id;
+        add_reminder(&mut db, task_id, ReminderTrigger::BeforeDue { offset_seconds: 3600 });
+        // an hour before midnight in New York, which is 4:00 UTC during summer time
+        assert_eq!(scheduler.tick(&mut db), Ok(Some(Utc.with_ymd_and_hms(2023, 9, 1, 3, 0, 0).unwrap())));
+    });
+}
+
+#[test]
+fn scheduler_skips_done_tasks() {
+    run_db_test(|mut db| {
+        let scheduler = sample_scheduler();
+        let task_id = add_sample_task(&mut db);
+        add_reminder(&mut db, task_id, ReminderTrigger::At { time: sample_time() });
+        db.finish_task(task_id).unwrap();
+        assert_eq!(scheduler.tick(&mut db), Ok(None));
+        assert_eq!(scheduler.notifier.take(), vec![]);
+    });
+}
+
+#[test]
+fn trigger_out_of_range_never_fires() {
+    let mut task = Task::from_parts(&sample_task_data()[0], &GeneratedTaskData {
+        id: 1,
+        create_time: sample_time(),
+        last_edit_time: sample_time(),
+        done_time: None,
+    });
+    assert!(ReminderTrigger::BeforeDue { offset_seconds: -ReminderTrigger::MAX_OFFSET_SECONDS }.is_valid());
+    assert_eq!(ReminderTrigger::BeforeDue { offset_seconds: i64::MAX }.fire_time(&task, &Utc), None);
+    task.due_time = Some(DateTime::<Utc>::MAX_UTC);
+    assert_eq!(ReminderTrigger::BeforeDue { offset_seconds: -1 }.fire_time(&task, &Utc), None);
+}
@@ -2,7 +2,7 @@ mod util;
 
 use std::collections::HashSet;
 use chrono::{DateTime, TimeZone, Utc};
-use app::{ContainsStringFilter, ExactlyFilter, GeneratedTagData, GeneratedTaskData, OptionalFilter, OrderedRangeFilter, SetFilter, Tag, Task, TaskFilterOptions};
+use app::{ContainsStringFilter, ExactlyFilter, FuzzyStringFilter, GeneratedTagData, GeneratedTaskData, OptionalFilter, OrderedRangeFilter, SetFilter, StringFilter, Tag, Task, TaskFilterOptions};
 use util::{*};
 
 // manually inspect serialization output
@@ -35,9 +35,9 @@ fn task_serialization() {
 fn filter_serialization() {
     let sample_filter = TaskFilterOptions {
         id_filter: Some(ExactlyFilter { value: 0 }),
-        title_filter: Some(ContainsStringFilter { pattern: String::from("hello") } ),
+        title_filter: Some(StringFilter::Contains(ContainsStringFilter { pattern: String::from("hello") })),
         tag_filter: Some(OptionalFilter::OnlySome(SetFilter { set: HashSet::from([1,2,3])})),
-        body_filter: None, // same as title filter
+        body_filter: Some(StringFilter::Fuzzy(FuzzyStringFilter::new("hlo", 0.5).unwrap())),
         difficulty_filter: None, // similar to tag filter
         create_time_filter: Some(OrderedRangeFilter {
             lower_bound: Some(Utc.with_ymd_and_hms(2023, 5, 1, 15,30,0).unwrap()),
@@ -48,6 +48,10 @@ fn filter_serialization() {
         target_time_filter: None, // covered by above
         done_time_filter: None, // covered by above
         paused_filter: Some(ExactlyFilter { value: true }),
+        project_filter: None, // similar to tag filter
+        include_archived: false,
+        deferred_filter: None, // similar to paused filter
+        include_deferred: false,
     };
     let filter_json = serde_json::to_string_pretty(&sample_filter).unwrap();
     println!("{}", filter_json);
@@ -0,0 +1,198 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use rusqlite::Connection;
+use app::*;
+mod util;
+use util::*;
+
+fn time(month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2023, month, day, hour, 0, 0).unwrap()
+}
+
+/// Adds a task, then overwrites its create and done times, which the database always sets to now.
+fn add_task(db: &mut Db, data: &EditableTaskData, create_time: DateTime<Utc>, done_time: Option<DateTime<Utc>>) -> TaskId {
+    let id = db.add_new_task(data).unwrap().id;
+    let conn = Connection::open(TEST_PATH).unwrap();
+    conn.execute("UPDATE tasks SET create_time = ?2, done_time = ?3 WHERE id = ?1",
+                 (id, create_time.timestamp(), done_time.map(|time| time.timestamp()))).unwrap();
+    id
+}
+
+/// Adds tasks around the start of September 2023.
+fn add_dated_tasks(db: &mut Db) {
+    add_sample_tags(db);
+    // tagged, difficulty 3, due 1 September and target 1 August
+    let tagged = &sample_task_data()[0];
+    // untagged, difficulty 0, no deadlines
+    let untagged = &sample_task_data()[1];
+    // on time due, late target, estimated at an hour
+    let estimated = add_task(db, tagged, time(8, 30, 12), Some(time(8, 31, 12)));
+    db.set_task_estimate(estimated, &TaskEstimate { estimate_minutes: Some(60), remaining_minutes: None }).unwrap();
+    // late due
+    add_task(db, tagged, time(8, 31, 12), Some(time(9, 2, 12)));
+    add_task(db, untagged, time(9, 1, 12), Some(time(9, 1, 13)));
+    add_task(db, untagged, time(9, 2, 12), None);
+    // all-day due 1 September, finished late that day, estimated at half an hour
+    let mut all_day = untagged.clone();
+    all_day.due_time = Some(all_day_time(date(2023, 9, 1)));
+    all_day.due_all_day = true;
+    let estimated = add_task(db, &all_day, time(9, 1, 0), Some(time(9, 1, 23)));
+    db.set_task_estimate(estimated, &TaskEstimate { estimate_minutes: Some(30), remaining_minutes: None }).unwrap();
+}
+
+#[test]
+fn stats_period_ranges() {
+    assert_eq!(StatsPeriod::Week.ranges(date(2023, 8, 30), date(2023, 9, 5)), Some(vec![
+        (date(2023, 8, 28), date(2023, 8, 30), date(2023, 9, 4)),
+        (date(2023, 9, 4), date(2023, 9, 4), date(2023, 9, 6)),
+    ]));
+    assert_eq!(StatsPeriod::Month.ranges(date(2023, 1, 31), date(2023, 3, 1)), Some(vec![
+        (date(2023, 1, 1), date(2023, 1, 31), date(2023, 2, 1)),
+        (date(2023, 2, 1), date(2023, 2, 1), date(2023, 3, 1)),
+        (date(2023, 3, 1), date(2023, 3, 1), date(2023, 3, 2)),
+    ]));
+    // the first week starts before the first date there is, and no date follows the last one
+    assert_eq!(StatsPeriod::Week.ranges(NaiveDate::MIN, NaiveDate::MIN), None);
+    assert_eq!(StatsPeriod::Day.ranges(NaiveDate::MAX, NaiveDate::MAX), None);
+    let before_max = NaiveDate::MAX.pred_opt().unwrap();
+    assert_eq!(StatsPeriod::Month.ranges(before_max, before_max), Some(vec![
+        (before_max.with_day(1).unwrap(), before_max, NaiveDate::MAX),
+    ]));
+}
+
+#[test]
+fn stats_period_advance() {
+    assert_eq!(StatsPeriod::Day.advance(date(2023, 8, 30), 3), Some(date(2023, 9, 2)));
+    assert_eq!(StatsPeriod::Week.advance(date(2023, 8, 30), 2), Some(date(2023, 9, 13)));
+    assert_eq!(StatsPeriod::Month.advance(date(2023, 1, 31), 1), Some(date(2023, 2, 28)));
+    for period in [StatsPeriod::Day, StatsPeriod::Week, StatsPeriod::Month] {
+        assert_eq!(period.advance(date(2023, 8, 30), u32::MAX), None);
+    }
+}
+
+#[test]
+fn db_period_stats() {
+    run_db_test(|mut db| {
+        add_dated_tasks(&mut db);
+        assert_eq!(db.period_stats(StatsPeriod::Day, date(2023, 8, 31), date(2023, 9, 2)), Ok(vec![
+            PeriodStats { start: date(2023, 8, 31), created: 1, completed: 1, difficulty_completed: 3, estimate_minutes_completed: 60 },
+            PeriodStats { start: date(2023, 9, 1), created: 2, completed: 2, difficulty_completed: 0, estimate_minutes_completed: 30 },
+            PeriodStats { start: date(2023, 9, 2), created: 1, completed: 1, difficulty_completed: 3, estimate_minutes_completed: 0 },
+        ]));
+        assert_eq!(db.period_stats(StatsPeriod::Month, date(2023, 8, 1), date(2023, 9, 30)), Ok(vec![
+            PeriodStats { start: date(2023, 8, 1), created: 2, completed: 1, difficulty_completed: 3, estimate_minutes_completed: 60 },
+            PeriodStats { start: date(2023, 9, 1), created: 3, completed: 3, difficulty_completed: 3, estimate_minutes_completed: 30 },
+        ]));
+    });
+}
+
+#[test]
+fn db_stats_invalid_range() {
+    run_db_test(|db| {
+        let inverted = (date(2023, 9, 2), date(2023, 9, 1));
+        let too_long = (date(1900, 1, 1), date(2023, 9, 1));
+        for (first, last) in [inverted, too_long] {
+            assert_eq!(db.period_stats(StatsPeriod::Day, first, last), Err(DbError::InvalidDateRangeError));
+            assert_eq!(db.summary_stats(first, last), Err(DbError::InvalidDateRangeError));
+            assert_eq!(db.tag_stats(first, last), Err(DbError::InvalidDateRangeError));
+        }
+        assert_eq!(db.tag_stats(NaiveDate::MAX, NaiveDate::MAX), Err(DbError::DateOutOfRangeError));
+        assert_eq!(db.period_stats(StatsPeriod::Week, NaiveDate::MIN, NaiveDate::MIN), Err(DbError::DateOutOfRangeError));
+        assert!(db.period_stats(StatsPeriod::Month, date(1923, 9, 2), date(2023, 9, 1)).is_ok());
+
+        let filter = TaskFilterOptions::default();
+        for (first, last) in [inverted, too_long] {
+            assert_eq!(db.burndown_report(&filter, StatsPeriod::Day, first, last), Err(DbError::InvalidDateRangeError));
+        }
+        let before_max = NaiveDate::MAX.pred_opt().unwrap();
+        assert_eq!(db.burndown_report(&filter, StatsPeriod::Day, before_max, NaiveDate::MAX), Err(DbError::DateOutOfRangeError));
+        let report = db.burndown_report(&filter, StatsPeriod::Month, before_max, before_max).unwrap();
+        assert_eq!(report.burndown.len(), 1);
+    });
+}
+
+#[test]
+fn db_summary_stats() {
+    run_db_test(|mut db| {
+        add_dated_tasks(&mut db);
+        let summary = db.summary_stats(date(2023, 8, 1), date(2023, 9, 30)).unwrap();
+        assert_eq!(summary.created, 5);
+        assert_eq!(summary.completed, 4);
+        assert_eq!(summary.completion_rate, Some(0.8));
+        // 24, 48, 1 and 23 hours
+        assert_eq!(summary.average_lead_seconds, Some(24.0 * 3600.0));
+        assert_eq!(summary.due, OnTimeStats { finished: 3, on_time: 2, rate: Some(2.0 / 3.0) });
+        assert_eq!(summary.target, OnTimeStats { finished: 2, on_time: 0, rate: Some(0.0) });
+
+        // 23:00 UTC on 1 September is the next day in Tokyo
+        db.set_user_timezone("Asia/Tokyo").unwrap();
+        let summary = db.summary_stats(date(2023, 8, 1), date(2023, 9, 30)).unwrap();
+        assert_eq!(summary.due.on_time, 1);
+
+        let empty = db.summary_stats(date(2023, 1, 1), date(2023, 1, 31)).unwrap();
+        assert_eq!(empty.completion_rate, None);
+        assert_eq!(empty.average_lead_seconds, None);
+        assert_eq!(empty.due.rate, None);
+    });
+}
+
+#[test]
+fn db_tag_stats() {
+    run_db_test(|mut db| {
+        add_dated_tasks(&mut db);
+        assert_eq!(db.tag_stats(date(2023, 8, 1), date(2023, 9, 30)), Ok(vec![
+            TagStats { tag_id: None, completed: 2, difficulty_completed: 0, estimate_minutes_completed: 30 },
+            TagStats { tag_id: Some(1), completed: 2, difficulty_completed: 6, estimate_minutes_completed: 60 },
+        ]));
+        assert_eq!(db.tag_stats(date(2023, 9, 2), date(2023, 9, 2)), Ok(vec![
+            TagStats { tag_id: Some(1), completed: 1, difficulty_completed: 3, estimate_minutes_completed: 0 },
+        ]));
+    });
+}
+
+#[test]
+fn db_burndown_report() {
+    run_db_test(|mut db| {
+        add_sample_tags(&mut db);
+        let tagged = |difficulty| EditableTaskData { difficulty, ..sample_task_data()[0].clone() };
+        let estimate = |minutes| TaskEstimate { estimate_minutes: Some(minutes), remaining_minutes: None };
+        let first = add_task(&mut db, &tagged(3), time(8, 30, 12), Some(time(8, 31, 12)));
+        db.set_task_estimate(first, &estimate(60)).unwrap();
+        add_task(&mut db, &tagged(3), time(8, 31, 12), Some(time(9, 2, 12)));
+        let third = add_task(&mut db, &tagged(4), time(9, 1, 12), None);
+        db.set_task_estimate(third, &estimate(90)).unwrap();
+        let untagged = EditableTaskData { difficulty: 5, ..sample_task_data()[1].clone() };
+        add_task(&mut db, &untagged, time(8, 30, 12), None);
+        let filter = TaskFilterOptions {
+            tag_filter: Some(OptionalFilter::OnlySome(SetFilter { set: [1].into() })),
+            ..Default::default()
+        };
+
+        let report = db.burndown_report(&filter, StatsPeriod::Day, date(2023, 8, 30), date(2023, 9, 2)).unwrap();
+        assert_eq!(report.burndown, vec![
+            BurndownPoint { date: date(2023, 8, 30), total: 3, completed: 0, remaining: 3, remaining_estimate_minutes: 60 },
+            BurndownPoint { date: date(2023, 8, 31), total: 6, completed: 3, remaining: 3, remaining_estimate_minutes: 0 },
+            BurndownPoint { date: date(2023, 9, 1), total: 10, completed: 3, remaining: 7, remaining_estimate_minutes: 90 },
+            BurndownPoint { date: date(2023, 9, 2), total: 10, completed: 6, remaining: 4, remaining_estimate_minutes: 90 },
+        ]);
+        assert_eq!(report.velocity.iter().map(|point| point.completed).collect::<Vec<_>>(), vec![0, 3, 0, 3]);
+        assert_eq!(report.forecast, BurndownForecast {
+            velocity: 1.5,
+            remaining: 4,
+            completion_date: Some(date(2023, 9, 5)),
+        });
+
+        let report = db.burndown_report(&filter, StatsPeriod::Week, date(2023, 8, 30), date(2023, 9, 2)).unwrap();
+        assert_eq!(report.velocity, vec![VelocityPoint { start: date(2023, 8, 28), completed: 6 }]);
+        assert_eq!(report.forecast.completion_date, Some(date(2023, 9, 9)));
+
+        // nothing completed to forecast from
+        let report = db.burndown_report(&TaskFilterOptions::default(), StatsPeriod::Day, date(2023, 8, 30), date(2023, 8, 30)).unwrap();
+        assert_eq!(report.forecast.remaining, 8);
+        assert_eq!(report.forecast.completion_date, None);
+
+        // hidden tasks still count
+        db.defer_task(third, Some(Utc::now() + Duration::days(1))).unwrap();
+        let report = db.burndown_report(&filter, StatsPeriod::Day, date(2023, 8, 30), date(2023, 9, 2)).unwrap();
+        assert_eq!(report.burndown[3].total, 10);
+    });
+}
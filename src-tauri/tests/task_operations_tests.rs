@@ -0,0 +1,138 @@
+use chrono::{TimeZone, Utc};
+use rusqlite::Connection;
+use app::*;
+use DbError::{LineDoesNotExistError, TaskDoesNotExistError};
+mod util;
+use util::*;
+
+fn task_data(title: &str, tag: Option<TagId>, body: &str) -> EditableTaskData {
+    EditableTaskData { title: String::from(title), tag, body: String::from(body), ..sample_task_data()[1].clone() }
+}
+
+fn titles(db: &Db) -> Vec<String> {
+    db.all_tasks().unwrap().into_iter().map(|task| task.title).collect()
+}
+
+#[test]
+fn split_and_merge_bodies() {
+    let body = "Groceries:\n- [ ] milk\n* eggs\n\n  bread  ";
+    assert_eq!(split_body(body, &[1, 2, 3, 4]), Some((String::from("Groceries:"), vec![
+        String::from("milk"), String::from("eggs"), String::from("bread"),
+    ])));
+    assert_eq!(split_body(body, &[5]), None);
+    assert_eq!(merge_bodies("First\n", &[("Second", "More"), ("Third", "")]), "First\n\n## Second\n\nMore\n\n## Third");
+    assert_eq!(merge_bodies("", &[("Second", "More")]), "## Second\n\nMore");
+}
+
+#[test]
+fn db_duplicate_task() {
+    run_db_test(|mut db| {
+        add_sample_tags(&mut db);
+        let original = db.add_new_task(&task_data("Original", Some(1), "Body")).unwrap().id;
+        db.add_new_task(&task_data("Last", None, "")).unwrap();
+        db.add_checklist_item(original, &EditableChecklistItemData { text: String::from("step"), checked: true }).unwrap();
+        db.finish_task(original).unwrap();
+
+        let copy = db.duplicate_task(original, true).expect("Duplicate task should not fail");
+        let task = db.task_by_id(copy.id).unwrap().unwrap();
+        assert_eq!((task.title.as_str(), task.tag, task.body.as_str()), ("Original", Some(1), "Body"));
+        assert_eq!(task.done_time, None);
+        assert_eq!(task.checklist, ChecklistProgress { checked: 0, total: 1 });
+        assert_eq!(titles(&db), ["Original", "Original", "Last"]);
+
+        let untagged = db.duplicate_task(original, false).unwrap();
+        assert_eq!(db.task_by_id(untagged.id).unwrap().unwrap().tag, None);
+        assert_eq!(db.duplicate_task(0, true), Err(TaskDoesNotExistError { id: 0 }));
+    });
+}
+
+#[test]
+fn db_split_task() {
+    run_db_test(|mut db| {
+        add_sample_tags(&mut db);
+        let id = db.add_new_task(&task_data("Shopping", Some(2), "Groceries:\n- milk\n- eggs")).unwrap().id;
+        db.add_new_task(&task_data("Last", None, "")).unwrap();
+
+        let generated = db.split_task(id, &[1, 2]).expect("Split task should not fail");
+        assert_eq!(generated.len(), 2);
+        assert_eq!(db.task_by_id(id).unwrap().unwrap().body, "Groceries:");
+        assert_eq!(db.task_by_id(generated[1].id).unwrap().unwrap().tag, Some(2));
+        assert_eq!(titles(&db), ["Shopping", "milk", "eggs", "Last"]);
+
+        // nothing changes when a line doesn't exist
+        assert_eq!(db.split_task(id, &[0, 3]), Err(LineDoesNotExistError { id, line: 3 }));
+        assert_eq!(db.all_tasks().unwrap().len(), 4);
+        assert_eq!(db.split_task(0, &[0]), Err(TaskDoesNotExistError { id: 0 }));
+    });
+}
+
+#[test]
+fn db_merge_tasks() {
+    run_db_test(|mut db| {
+        add_sample_tags(&mut db);
+        let mut first_data = task_data("First", None, "One");
+        first_data.due_time = Some(Utc.with_ymd_and_hms(2023, 9, 1, 0, 0, 0).unwrap());
+        let first = db.add_new_task(&first_data).unwrap().id;
+        let mut second_data = task_data("Second", Some(2), "Two");
+        second_data.due_time = Some(Utc.with_ymd_and_hms(2023, 9, 5, 0, 0, 0).unwrap());
+        second_data.due_all_day = true;
+        let second = db.add_new_task(&second_data).unwrap().id;
+        let third = db.add_new_task(&task_data("Third", Some(1), "")).unwrap().id;
+        let linking = db.add_new_task(&task_data("Linking", None, &format!("[[Second]] #{third}"))).unwrap().id;
+
+        let connection = Connection::open(TEST_PATH).unwrap();
+        connection.execute("UPDATE tasks SET create_time = 1000 WHERE id = ?1", (third,)).unwrap();
+        let item = EditableChecklistItemData { text: String::from("step"), checked: false };
+        db.add_checklist_item(first, &item).unwrap();
+        db.add_checklist_item(second, &item).unwrap();
+        let comment = EditableCommentData { author: String::from("sam"), text: String::from("hi") };
+        db.add_comment(third, &comment).unwrap();
+        db.add_new_reminder(&EditableReminderData { task_id: second, trigger: ReminderTrigger::BeforeDue { offset_seconds: 0 } }).unwrap();
+        let block = EditableTimeBlockData {
+            task_id: Some(third),
+            title: String::from("Third"),
+            start_time: Utc.with_ymd_and_hms(2023, 9, 1, 10, 0, 0).unwrap(),
+            end_time: Utc.with_ymd_and_hms(2023, 9, 1, 11, 0, 0).unwrap(),
+            fixed: true,
+        };
+        let block = db.add_time_block(&block).unwrap().id;
+        let project = db.add_new_project(&EditableProjectData {
+            name: String::from("Project"),
+            description: String::new(),
+            status: ProjectStatus::Active,
+            deadline: None,
+        }).unwrap().id;
+        db.move_task_to_project(third, Some(project), None).unwrap();
+
+        let merged = db.merge_tasks(first, &[second, third, second]).expect("Merge tasks should not fail");
+        assert_eq!(merged.body, "One\n\n## Second\n\nTwo\n\n## Third");
+        assert_eq!(merged.create_time, Utc.timestamp_opt(1000, 0).unwrap());
+        assert_eq!(merged.due_time, second_data.due_time);
+        assert!(merged.due_all_day);
+        // without a tag of its own, it takes the first tag of the others
+        assert_eq!(merged.tag, Some(2));
+        let activity = db.task_activity(first).unwrap();
+        assert!(activity.iter().any(|activity| activity.kind == ActivityKind::Event {
+            event: TaskEvent::TagChanged { from: None, to: Some(2) },
+        }));
+        assert_eq!(merged.checklist, ChecklistProgress { checked: 0, total: 2 });
+        assert_eq!(merged.project, Some(project));
+        assert_eq!(db.comments_for_task(first).unwrap().len(), 1);
+        assert_eq!(db.reminders_for_task(first).unwrap().len(), 1);
+        assert_eq!(db.time_block_by_id(block).unwrap().unwrap().task_id, Some(first));
+        assert_eq!(db.task_by_id(second), Ok(None));
+        assert_eq!(db.task_by_id(third), Ok(None));
+        // links to an id follow the merge, links to a title break
+        assert_eq!(db.task_by_id(linking).unwrap().unwrap().body, format!("[[Second]] #{first}"));
+        let links = db.task_links(linking).unwrap();
+        assert_eq!(links[0].target, None);
+        assert_eq!(links[1], TaskLink { source: linking, target: Some(first), reference: TaskReference::Id { id: first } });
+
+        // a tag of its own is kept
+        let fourth = db.add_new_task(&task_data("Fourth", Some(1), "")).unwrap().id;
+        assert_eq!(db.merge_tasks(first, &[fourth]).unwrap().tag, Some(2));
+
+        assert_eq!(db.merge_tasks(first, &[0]), Err(TaskDoesNotExistError { id: 0 }));
+        assert_eq!(db.merge_tasks(0, &[first]), Err(TaskDoesNotExistError { id: 0 }));
+    });
+}
@@ -1,6 +1,6 @@
-use chrono::Utc;
+use chrono::{Duration, Utc};
 use app::*;
-use DbError::{TaskDoesNotExistError, TaskStatusError};
+use DbError::{InvalidAllDayError, TaskDoesNotExistError, TaskStatusError};
 mod util;
 use util::*;
 
@@ -244,6 +244,24 @@ fn db_modify_task_failure_no_tag() {
     });
 }
 
+#[test]
+fn db_task_failure_invalid_all_day() {
+    run_db_test(|mut db| {
+        let id = db.add_new_task(&sample_task_data()[1]).unwrap().id;
+        // sample_task_data()[0] is due at midnight UTC, so a valid all-day date
+        let all_day = EditableTaskData { tag: None, due_all_day: true, ..sample_task_data()[0].clone() };
+        assert!(all_day.is_valid());
+        let no_date = EditableTaskData { due_time: None, ..all_day.clone() };
+        let not_midnight = EditableTaskData { due_time: all_day.due_time.map(|time| time + Duration::hours(1)), ..all_day.clone() };
+        for data in [&no_date, &not_midnight] {
+            assert_eq!(db.add_new_task(data), Err(InvalidAllDayError));
+            assert_eq!(db.modify_task(id, data), Err(InvalidAllDayError));
+        }
+        assert_eq!(db.all_tasks().unwrap().len(), 1);
+        db.modify_task(id, &all_day).expect("Valid all-day date should not fail");
+    });
+}
+
 #[test]
 fn db_delete_task_success() {
     run_db_test(|mut db| {
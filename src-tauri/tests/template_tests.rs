@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use chrono::{Duration, TimeZone, Utc};
+use chrono_tz::America::New_York;
+use app::*;
+use rusqlite::Connection;
+use DbError::{DurationOutOfRangeError, TagDoesNotExistError, TemplateDoesNotExistError};
+mod util;
+use util::*;
+
+fn weekly_review() -> EditableTemplateData {
+    EditableTemplateData {
+        name: String::from("Weekly review"),
+        title: String::from("Review {{ week }} ({{date}})"),
+        body: String::from("Started {{weekday}} at {{time}}. {{unknown}} stays."),
+        tag: Some(1),
+        difficulty: 2,
+        due_offset_seconds: Some(Duration::days(2).num_seconds()),
+        due_all_day: true,
+        target_offset_seconds: Some(Duration::hours(3).num_seconds()),
+        checklist: vec![String::from("Inbox zero"), String::from("Plan {{date}}")],
+    }
+}
+
+fn values() -> HashMap<String, String> {
+    HashMap::from([(String::from("week"), String::from("week 34"))])
+}
+
+#[test]
+fn fill_template_placeholders() {
+    // 2023-08-20 22:30 in New York is already the 21st in UTC
+    let now = New_York.with_ymd_and_hms(2023, 8, 20, 22, 30, 0).unwrap();
+    assert_eq!(fill_placeholders("{{date}} {{time}} {{weekday}}", &now, &HashMap::new()), "2023-08-20 22:30 Sunday");
+    assert_eq!(fill_placeholders("{{date}} {{ week }} {{ nope }} {{", &now, &values()), "2023-08-20 week 34 {{ nope }} {{");
+    let overridden = HashMap::from([(String::from("date"), String::from("someday"))]);
+    assert_eq!(fill_placeholders("{{date}}", &now, &overridden), "someday");
+}
+
+#[test]
+fn instantiate_template() {
+    let now = New_York.with_ymd_and_hms(2023, 8, 20, 22, 30, 0).unwrap();
+    let template = Template::from_parts(&weekly_review(), &GeneratedTemplateData { id: 1 });
+    let instance = template.instantiate(&now, &values()).unwrap();
+    assert_eq!(instance.data.title, "Review week 34 (2023-08-20)");
+    assert_eq!(instance.data.body, "Started Sunday at 22:30. {{unknown}} stays.");
+    assert_eq!(instance.data.tag, Some(1));
+    assert_eq!(instance.data.difficulty, 2);
+    // an all-day due date is the local date it falls on
+    assert_eq!(instance.data.due_time, Some(Utc.with_ymd_and_hms(2023, 8, 22, 0, 0, 0).unwrap()));
+    assert!(instance.data.due_all_day);
+    assert_eq!(instance.data.target_time, Some(Utc.with_ymd_and_hms(2023, 8, 21, 5, 30, 0).unwrap()));
+    assert_eq!(instance.checklist, vec![
+        EditableChecklistItemData { text: String::from("Inbox zero"), checked: false },
+        EditableChecklistItemData { text: String::from("Plan 2023-08-20"), checked: false },
+    ]);
+
+    let mut data = weekly_review();
+    data.due_offset_seconds = None;
+    let instance = Template::from_parts(&data, &GeneratedTemplateData { id: 1 }).instantiate(&now, &values()).unwrap();
+    assert_eq!(instance.data.due_time, None);
+    assert!(!instance.data.due_all_day);
+
+    // offsets out of the range of times make no task
+    for offset in [i64::MAX, i64::MIN, Duration::max_value().num_seconds()] {
+        data.target_offset_seconds = Some(offset);
+        assert_eq!(Template::from_parts(&data, &GeneratedTemplateData { id: 1 }).instantiate(&now, &values()), None);
+    }
+}
+
+#[test]
+fn db_templates() {
+    run_db_test(|mut db| {
+        add_sample_tags(&mut db);
+        let generated = db.add_new_template(&weekly_review()).expect("Add template should not fail");
+        let mut expected = Template::from_parts(&weekly_review(), &generated);
+        assert_eq!(db.template_by_id(generated.id), Ok(Some(expected.clone())));
+        assert_eq!(db.all_templates(), Ok(vec![expected.clone()]));
+
+        let mut data = weekly_review();
+        data.name = String::from("Review");
+        data.checklist = vec![String::from("Only this")];
+        db.modify_template(generated.id, &data).expect("Modify template should not fail");
+        expected = Template::from_parts(&data, &generated);
+        assert_eq!(db.template_by_id(generated.id), Ok(Some(expected.clone())));
+
+        // deleting the tag leaves the template without one
+        db.delete_tag(1).unwrap();
+        expected.tag = None;
+        assert_eq!(db.template_by_id(generated.id), Ok(Some(expected)));
+
+        data.tag = Some(100);
+        assert_eq!(db.add_new_template(&data), Err(TagDoesNotExistError { id: 100 }));
+        assert_eq!(db.modify_template(generated.id, &data), Err(TagDoesNotExistError { id: 100 }));
+        data.tag = None;
+        assert_eq!(db.modify_template(0, &data), Err(TemplateDoesNotExistError { id: 0 }));
+        data.due_offset_seconds = Some(-Template::MAX_OFFSET_SECONDS);
+        assert!(data.is_valid());
+        data.due_offset_seconds = Some(Template::MAX_OFFSET_SECONDS + 1);
+        assert_eq!(db.add_new_template(&data), Err(DurationOutOfRangeError));
+        assert_eq!(db.modify_template(generated.id, &data), Err(DurationOutOfRangeError));
+        data.due_offset_seconds = None;
+        data.target_offset_seconds = Some(i64::MIN);
+        assert_eq!(db.add_new_template(&data), Err(DurationOutOfRangeError));
+
+        db.delete_template(generated.id).expect("Delete template should not fail");
+        assert_eq!(db.template_by_id(generated.id), Ok(None));
+        assert_eq!(db.delete_template(generated.id), Err(TemplateDoesNotExistError { id: generated.id }));
+    });
+}
+
+#[test]
+fn db_add_task_from_template() {
+    run_db_test(|mut db| {
+        add_sample_tags(&mut db);
+        let id = db.add_new_template(&weekly_review()).unwrap().id;
+        let now = New_York.with_ymd_and_hms(2023, 8, 20, 22, 30, 0).unwrap();
+        let instance = db.instantiate_template(id, &values(), &now).unwrap();
+        assert!(db.all_tasks().unwrap().is_empty());
+
+        let task_id = db.add_task_from_template(id, &values(), &now).expect("Add task from template should not fail").id;
+        let task = db.task_by_id(task_id).unwrap().unwrap();
+        assert_eq!(task.title, instance.data.title);
+        assert_eq!(task.due_time, instance.data.due_time);
+        let items = db.checklist_items(task_id).unwrap();
+        assert_eq!(items.iter().map(|item| item.text.as_str()).collect::<Vec<_>>(), ["Inbox zero", "Plan 2023-08-20"]);
+
+        assert_eq!(db.instantiate_template(0, &values(), &now), Err(TemplateDoesNotExistError { id: 0 }));
+        assert_eq!(db.add_task_from_template(0, &values(), &now), Err(TemplateDoesNotExistError { id: 0 }));
+    });
+}
+
+#[test]
+fn db_failed_task_from_template_adds_nothing() {
+    run_db_test(|mut db| {
+        add_sample_tags(&mut db);
+        let id = db.add_new_template(&weekly_review()).unwrap().id;
+        let now = New_York.with_ymd_and_hms(2023, 8, 20, 22, 30, 0).unwrap();
+        let conn = Connection::open(TEST_PATH).unwrap();
+        conn.execute_batch(r#"
+            CREATE TRIGGER fail_items BEFORE INSERT ON checklist_items BEGIN SELECT RAISE(ABORT, 'no items'); END;
+        "#).unwrap();
+        assert!(db.add_task_from_template(id, &values(), &now).is_err());
+        assert_eq!(db.all_tasks(), Ok(vec![]));
+
+        // the connection is not left inside the savepoint
+        conn.execute_batch("DROP TRIGGER fail_items;").unwrap();
+        let task_id = db.add_task_from_template(id, &values(), &now).unwrap().id;
+        drop(db);
+        let db = Db::connect(TEST_PATH).unwrap();
+        assert_eq!(db.checklist_items(task_id).unwrap().len(), 2);
+    });
+}
@@ -0,0 +1,138 @@
+use chrono::{Duration, TimeZone, Utc};
+use app::*;
+use DbError::{InvalidTimeEntryError, TaskDoesNotExistError, TimeEntryDoesNotExistError, TimerRunningError};
+mod util;
+use util::*;
+
+fn entry_data(task_id: TaskId, start: i64, end: Option<i64>) -> EditableTimeEntryData {
+    EditableTimeEntryData {
+        task_id,
+        start_time: sample_time() + Duration::minutes(start),
+        end_time: end.map(|end| sample_time() + Duration::minutes(end)),
+    }
+}
+
+#[test]
+fn db_timer_start_and_stop() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        assert_eq!(db.running_timer(), Ok(None));
+        assert_eq!(db.stop_timer(sample_time()), Ok(None));
+
+        let first = db.start_timer(ids[0], sample_time()).expect("Start timer should not fail");
+        assert!(first.is_running());
+        assert_eq!(db.running_timer(), Ok(Some(first.clone())));
+
+        // starting another timer stops the running one
+        let second = db.start_timer(ids[1], sample_time() + Duration::minutes(30)).unwrap();
+        assert_eq!(db.running_timer(), Ok(Some(second.clone())));
+        assert_eq!(db.time_entry_by_id(first.id).unwrap().unwrap().end_time, Some(sample_time() + Duration::minutes(30)));
+
+        let stopped = db.stop_timer(sample_time() + Duration::minutes(45)).unwrap().unwrap();
+        assert_eq!(stopped.end_time, Some(sample_time() + Duration::minutes(45)));
+        assert_eq!(db.running_timer(), Ok(None));
+        assert_eq!(db.start_timer(0, sample_time()), Err(TaskDoesNotExistError { id: 0 }));
+    });
+}
+
+#[test]
+fn db_timer_survives_restart() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let running = db.start_timer(ids[0], sample_time()).unwrap();
+        drop(db);
+        let db = Db::connect(TEST_PATH).unwrap();
+        assert_eq!(db.running_timer(), Ok(Some(running)));
+        assert_eq!(db.task_time_spent(ids[0], sample_time() + Duration::minutes(5)), Ok(300));
+    });
+}
+
+#[test]
+fn db_time_entry_add_modify_delete() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let data = entry_data(ids[0], 0, Some(60));
+        let generated = db.add_time_entry(&data).expect("Add time entry should not fail");
+        assert_eq!(db.time_entries_for_task(ids[0]), Ok(vec![TimeEntry::from_parts(&data, &generated)]));
+
+        assert_eq!(db.add_time_entry(&entry_data(ids[0], 60, Some(0))), Err(InvalidTimeEntryError));
+        assert_eq!(db.add_time_entry(&entry_data(0, 0, None)), Err(TaskDoesNotExistError { id: 0 }));
+
+        let modified = entry_data(ids[1], -60, Some(0));
+        db.modify_time_entry(generated.id, &modified).expect("Modify time entry should not fail");
+        assert_eq!(db.time_entries_for_task(ids[0]), Ok(vec![]));
+        assert_eq!(db.time_entries_for_task(ids[1]), Ok(vec![TimeEntry::from_parts(&modified, &generated)]));
+
+        db.delete_time_entry(generated.id).expect("Delete time entry should not fail");
+        assert_eq!(db.delete_time_entry(generated.id), Err(TimeEntryDoesNotExistError { id: generated.id }));
+        assert_eq!(db.modify_time_entry(generated.id, &modified), Err(TimeEntryDoesNotExistError { id: generated.id }));
+    });
+}
+
+#[test]
+fn db_time_entry_one_running_timer() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let running = db.add_time_entry(&entry_data(ids[0], 0, None)).unwrap();
+        assert_eq!(db.add_time_entry(&entry_data(ids[1], 0, None)), Err(TimerRunningError { id: running.id }));
+        // the running timer itself may still be edited
+        db.modify_time_entry(running.id, &entry_data(ids[0], -10, None)).unwrap();
+        let finished = db.add_time_entry(&entry_data(ids[1], 0, Some(1))).unwrap();
+        assert_eq!(db.modify_time_entry(finished.id, &entry_data(ids[1], 0, None)), Err(TimerRunningError { id: running.id }));
+    });
+}
+
+#[test]
+fn db_finish_task_stops_timer() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        db.start_timer(ids[1], Utc::now() - Duration::minutes(1)).unwrap();
+        db.finish_task(ids[0]).unwrap();
+        assert!(db.running_timer().unwrap().is_some());
+        let done_time = db.finish_task(ids[1]).unwrap().done_time;
+        assert_eq!(db.running_timer(), Ok(None));
+        assert_eq!(db.time_entries_for_task(ids[1]).unwrap()[0].end_time, done_time);
+    });
+}
+
+#[test]
+fn db_stopping_future_timer_never_ends_before_start() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        let future = Utc.with_ymd_and_hms(2100, 1, 1, 0, 0, 0).unwrap();
+        let first = db.start_timer(ids[0], future).unwrap();
+        db.start_timer(ids[1], sample_time()).unwrap();
+        assert_eq!(db.time_entry_by_id(first.id).unwrap().unwrap().end_time, Some(future));
+
+        let running = db.start_timer(ids[1], future).unwrap();
+        db.finish_task(ids[1]).unwrap();
+        assert_eq!(db.time_entry_by_id(running.id).unwrap().unwrap().end_time, Some(future));
+    });
+}
+
+#[test]
+fn db_time_spent_totals() {
+    run_db_test(|mut db| {
+        let ids = add_sample_tasks(&mut db);
+        // 23:00 to 01:00 the next day
+        db.add_time_entry(&entry_data(ids[0], 11 * 60, Some(13 * 60))).unwrap();
+        db.add_time_entry(&entry_data(ids[1], 0, Some(30))).unwrap();
+        db.start_timer(ids[0], sample_time() + Duration::days(1)).unwrap();
+        let now = sample_time() + Duration::days(1) + Duration::minutes(10);
+
+        assert_eq!(db.task_time_spent(ids[0], now), Ok(2 * 3600 + 600));
+        assert_eq!(db.task_time_spent(ids[1], now), Ok(1800));
+        assert_eq!(db.tag_time_spent(1, now), Ok(2 * 3600 + 600));
+        assert_eq!(db.tag_time_spent(2, now), Ok(0));
+
+        assert_eq!(db.time_spent_by_day(date(2023, 8, 30), date(2023, 9, 1), now), Ok(vec![
+            DayTimeSpent { date: date(2023, 8, 30), seconds: 0 },
+            DayTimeSpent { date: date(2023, 8, 31), seconds: 1800 + 3600 },
+            DayTimeSpent { date: date(2023, 9, 1), seconds: 3600 + 600 },
+        ]));
+        db.set_user_timezone("America/New_York").unwrap();
+        assert_eq!(db.time_spent_by_day(date(2023, 8, 31), date(2023, 8, 31), now), Ok(vec![
+            DayTimeSpent { date: date(2023, 8, 31), seconds: 2 * 3600 + 1800 },
+        ]));
+    });
+}
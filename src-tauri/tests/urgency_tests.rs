@@ -0,0 +1,128 @@
+use chrono::{Duration, Utc};
+use app::*;
+mod util;
+use util::*;
+
+/// A task created at `sample_time()` without deadlines.
+fn sample_task() -> Task {
+    let generated = GeneratedTaskData {
+        id: 1,
+        create_time: sample_time(),
+        last_edit_time: sample_time(),
+        done_time: None,
+    };
+    Task::from_parts(&sample_task_data()[1], &generated)
+}
+
+fn ids(tasks: &[UrgentTask]) -> Vec<TaskId> {
+    tasks.iter().map(|task| task.task.id).collect()
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-9, "{actual} is not {expected}");
+}
+
+#[test]
+fn urgency_deadline_proximity() {
+    assert_close(deadline_proximity(sample_time() + Duration::days(30), sample_time()), 0.2);
+    assert_close(deadline_proximity(sample_time() + Duration::days(14), sample_time()), 0.2);
+    assert_close(deadline_proximity(sample_time(), sample_time()), 0.2 + 0.8 * 14.0 / 21.0);
+    assert_close(deadline_proximity(sample_time() - Duration::days(7), sample_time()), 1.0);
+    assert_close(deadline_proximity(sample_time() - Duration::days(30), sample_time()), 1.0);
+}
+
+#[test]
+fn urgency_score_formula() {
+    let coefficients = UrgencyCoefficients::default();
+    let mut task = sample_task();
+    assert_close(coefficients.score(&task, sample_time(), 0.0, false), 0.0);
+
+    task.due_time = Some(sample_time() - Duration::days(7));
+    task.target_time = Some(sample_time() + Duration::days(14));
+    task.difficulty = 4;
+    task.paused = true;
+    // half a year old
+    let now = sample_time() + Duration::seconds((365.0 / 2.0 * 86400.0) as i64);
+    let expected = 12.0 * 1.0 + 6.0 * deadline_proximity(sample_time() + Duration::days(14), now)
+        + 0.5 * 4.0 + 2.0 * 0.5 - 5.0 - 5.0 + 1.0 * 3.0;
+    assert_close(coefficients.score(&task, now, 3.0, true), expected);
+
+    // age is capped at a year
+    let task = sample_task();
+    assert_close(coefficients.score(&task, sample_time() + Duration::days(3650), 0.0, false), 2.0);
+}
+
+#[test]
+fn db_urgency_settings() {
+    run_db_test(|mut db| {
+        assert_eq!(db.urgency_coefficients(), Ok(UrgencyCoefficients::default()));
+        let coefficients = UrgencyCoefficients { due: 1.5, tag: -2.0, ..Default::default() };
+        db.set_urgency_coefficients(&coefficients).expect("Set coefficients should not fail");
+        assert_eq!(db.urgency_coefficients(), Ok(coefficients));
+
+        let tag_id = db.add_new_tag(&sample_tag_data()[0]).unwrap().id;
+        db.set_tag_urgency_weight(tag_id, 2.5).expect("Set tag weight should not fail");
+        assert_eq!(db.tag_urgency_weights().unwrap().get(&tag_id), Some(&2.5));
+        assert_eq!(db.set_tag_urgency_weight(0, 1.0), Err(DbError::TagDoesNotExistError { id: 0 }));
+        db.delete_tag(tag_id).unwrap();
+        assert!(db.tag_urgency_weights().unwrap().is_empty());
+    });
+}
+
+#[test]
+fn db_filter_urgent_tasks() {
+    run_db_test(|mut db| {
+        let tag_id = db.add_new_tag(&sample_tag_data()[0]).unwrap().id;
+        let plain = db.add_new_task(&sample_task_data()[1]).unwrap().id;
+        let tagged = db.add_new_task(&EditableTaskData { tag: Some(tag_id), ..sample_task_data()[1].clone() }).unwrap().id;
+        let due = db.add_new_task(&EditableTaskData { due_time: Some(Utc::now()), ..sample_task_data()[1].clone() }).unwrap().id;
+        db.set_tag_urgency_weight(tag_id, 1.0).unwrap();
+
+        let tasks = db.filter_urgent_tasks(|_| true, Utc::now()).unwrap();
+        assert_eq!(ids(&tasks), vec![plain, tagged, due]);
+        assert_close(tasks[1].urgency - tasks[0].urgency, 1.0);
+        let tasks = db.filter_tasks_by_urgency(|_| true, Utc::now()).unwrap();
+        assert_eq!(ids(&tasks), vec![due, tagged, plain]);
+        assert_eq!(ids(&db.filter_tasks_by_urgency(|task| task.id != due, Utc::now()).unwrap()), vec![tagged, plain]);
+
+        let json = serde_json::to_value(&tasks[0]).unwrap();
+        assert_eq!(json["id"], due);
+        assert!(json["urgency"].is_f64());
+    });
+}
+
+#[test]
+fn db_blocked_tasks() {
+    run_db_test(|mut db| {
+        let untagged = |title: &str, body: &str| EditableTaskData {
+            title: String::from(title),
+            body: String::from(body),
+            ..sample_task_data()[1].clone()
+        };
+        let first = db.add_new_task(&untagged("First", "[[First]]")).unwrap().id;
+        let second = db.add_new_task(&untagged("Second", "After [[First]]")).unwrap().id;
+        let third = db.add_new_task(&untagged("Third", "")).unwrap().id;
+        // linking to itself does not block a task
+        assert_eq!(db.blocked_tasks(), Ok([second].into()));
+        let scores = db.filter_urgent_tasks(|_| true, Utc::now()).unwrap();
+        // up to a second apart in age
+        assert!((scores[0].urgency - scores[1].urgency - 5.0).abs() < 1e-6);
+
+        db.finish_task(first).unwrap();
+        assert_eq!(db.blocked_tasks(), Ok([].into()));
+
+        // a habit is never done, so linking to one blocks nothing
+        let habit = db.add_new_task(&untagged("Exercise", "")).unwrap().id;
+        db.set_habit(&Habit { task_id: habit, every_days: 1, grace_days: 0 }).unwrap();
+        db.modify_task(third, &untagged("Third", "Between [[Exercise]] sessions")).unwrap();
+        assert_eq!(db.blocked_tasks(), Ok([].into()));
+
+        // neither does a full column
+        let board = db.add_new_board(&EditableBoardData { name: String::from("Work") }).unwrap().id;
+        let column = EditableColumnData { name: String::from("Doing"), wip_limit: Some(2), terminal: false };
+        let doing = db.add_column(board, &column).unwrap().id;
+        db.move_card(second, doing, None).unwrap();
+        db.move_card(third, doing, None).unwrap();
+        assert_eq!(db.blocked_tasks(), Ok([].into()));
+    });
+}
@@ -1,9 +1,10 @@
+use std::cell::Cell;
 use std::panic;
 use std::sync::Mutex;
 use std::fs;
-use chrono::{TimeZone, Utc};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
 use once_cell::sync::Lazy;
-use app::{Db, EditableTagData, EditableTaskData, HslColor};
+use app::{Clock, Db, EditableTagData, EditableTaskData, HslColor, TaskId};
 
 pub const TEST_PATH: &str = "test-outputs/test-db.sqlite";
 // We want to run each test synchronously because they modify the same file,
@@ -64,6 +65,7 @@ static SAMPLE_TASKS: Lazy<Vec<EditableTaskData>> = Lazy::new(|| vec![
         body: String::from("A type of berry"),
         difficulty: 3,
         due_time: Some(Utc.with_ymd_and_hms(2023, 9, 1, 0,0,0).unwrap()),
+        due_all_day: false,
         target_time: Some(Utc.with_ymd_and_hms(2023, 8, 1, 0,0,0).unwrap()),
         paused: false,
     },
@@ -73,6 +75,7 @@ static SAMPLE_TASKS: Lazy<Vec<EditableTaskData>> = Lazy::new(|| vec![
         body: String::from("Not a berry"),
         difficulty: 0,
         due_time: None,
+        due_all_day: false,
         target_time: None,
         paused: false,
     },
@@ -80,3 +83,37 @@ static SAMPLE_TASKS: Lazy<Vec<EditableTaskData>> = Lazy::new(|| vec![
 pub fn sample_task_data() -> &'static [EditableTaskData] {
     &SAMPLE_TASKS
 }
+
+pub fn add_sample_tags(db: &mut Db) {
+    for tag in sample_tag_data() {
+        db.add_new_tag(tag).unwrap();
+    }
+}
+
+/// Adds the sample tags and tasks, returning the ids of the tasks.
+pub fn add_sample_tasks(db: &mut Db) -> Vec<TaskId> {
+    add_sample_tags(db);
+    sample_task_data().iter().map(|task| db.add_new_task(task).unwrap().id).collect()
+}
+
+/// The time tests start at: 31 August 2023 at noon UTC.
+pub fn sample_time() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2023, 8, 31, 12, 0, 0).unwrap()
+}
+
+pub fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+/// A clock that only moves when told to.
+pub struct FakeClock(pub Cell<DateTime<Utc>>);
+impl Clock for FakeClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0.get()
+    }
+}
+impl FakeClock {
+    pub fn advance(&self, duration: Duration) {
+        self.0.set(self.0.get() + duration);
+    }
+}